@@ -10,7 +10,7 @@ fn main() -> io::Result<()> {
     Command::new("rustfmt")
         .arg("src/expr.rs")
         .arg("src/stmt.rs")
-        .spawn()
+        .status()
         .expect("Failed to run 'rustfmt' on build-generated files.");
 
     Ok(())
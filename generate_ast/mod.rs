@@ -6,18 +6,24 @@ pub fn generate_ast(output_dir: &str) -> std::io::Result<()> {
         output_dir,
         "Expr",
         vec![
-            "Assign   : Token name, Box<Expr> value".to_string(),
+            "Assign   : usize id, Token name, Box<Expr> value".to_string(),
             "Binary   : Box<Expr> left, Token operator, Box<Expr> right".to_string(),
             "Call     : Box<Expr> callee, Token paren, Vec<Expr> arguments".to_string(),
             "Get      : Box<Expr> object, Token name".to_string(),
+            "Index    : Box<Expr> object, Token bracket, Box<Expr> index".to_string(),
+            "Lambda   : Token keyword, Vec<Token> params, Vec<Stmt> body".to_string(),
+            "List     : Token bracket, Vec<Expr> elements".to_string(),
             "Logical  : Box<Expr> left, Token operator, Box<Expr> right".to_string(),
+            "Map      : Token brace, Vec<Expr> keys, Vec<Expr> values".to_string(),
             "Set      : Box<Expr> object, Token name, Box<Expr> value".to_string(),
-            "Super    : Token keyword, Token method".to_string(),
-            "This     : Token keyword".to_string(),
+            "SetIndex : Box<Expr> object, Token bracket, Box<Expr> index, Box<Expr> value"
+                .to_string(),
+            "Super    : usize id, Token keyword, Token method".to_string(),
+            "This     : usize id, Token keyword".to_string(),
             "Unary    : Token operator, Box<Expr> right".to_string(),
             "Grouping : Box<Expr> expression".to_string(),
-            "Literal  : Option<Object> value".to_string(),
-            "Variable : Token name".to_string(),
+            "Literal  : Token token, Option<Object> value".to_string(),
+            "Variable : usize id, Token name".to_string(),
         ],
     )?;
 
@@ -26,15 +32,24 @@ pub fn generate_ast(output_dir: &str) -> std::io::Result<()> {
         "Stmt",
         vec![
             "Block      : Vec<Stmt> statements".to_string(),
-            "Class      : Token name, Option<Expr> superclass, Vec<Stmt> methods".to_string(),
+            "Break      : Token keyword".to_string(),
+            "Class      : Token name, Option<Expr> superclass, Vec<Stmt> methods, \
+                          Vec<Stmt> class_methods, Vec<Stmt> getters"
+                .to_string(),
+            "Continue   : Token keyword".to_string(),
             "Expression : Expr expression".to_string(),
             "Function   : Token name, Vec<Token> params, Vec<Stmt> body".to_string(),
             "If         : Expr condition, Box<Stmt> then_branch, Box<Option<Stmt>> else_branch"
                 .to_string(),
-            "Print      : Expr expression".to_string(),
+            "Import     : Token keyword, Token name, Token path".to_string(),
+            "Print      : Token keyword, Expr expression".to_string(),
             "Return     : Token keyword, Option<Expr> value".to_string(),
+            "Throw      : Token keyword, Expr value".to_string(),
+            "Try        : Token keyword, Vec<Stmt> body, Option<Token> catch_name, \
+                          Vec<Stmt> catch_body, Vec<Stmt> finally_body"
+                .to_string(),
             "Var        : Token name, Option<Expr> initializer".to_string(),
-            "While      : Expr condition, Box<Stmt> body".to_string(),
+            "While      : Expr condition, Box<Stmt> body, Option<Expr> increment".to_string(),
         ],
     )?;
 
@@ -50,6 +65,7 @@ fn define_ast(output_dir: &str, base_name: &str, types: Vec<String>) -> std::io:
         file.write_all(b"use crate::token::Token;\n")?;
     } else if base_name == "Expr" {
         file.write_all(b"use crate::object::Object;\n")?;
+        file.write_all(b"use crate::stmt::Stmt;\n")?;
         file.write_all(b"use crate::token::Token;\n")?;
     }
     file.write_all(b"use crate::errors::LoxResult;\n")?;
@@ -0,0 +1,95 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::errors::{Interruption, LoxResult};
+
+/**
+ * Limits how much a program may run, so that a runaway script (e.g. `while (true) {}`) is stopped
+ * with a `LoxResult::Interrupted` error rather than running forever. Each run of the interpreter
+ * gets the whole budget, e.g. `Budget::new().with_timeout(Duration::from_secs(1))`.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct Budget {
+    /// The maximum number of statements a run may execute.
+    max_statements: Option<u64>,
+    /// The maximum time a run may take.
+    timeout: Option<Duration>,
+    /// A flag cancelling the run once set, e.g. from another thread.
+    cancel_flag: Option<Arc<AtomicBool>>,
+    /// The number of statements executed by the current run.
+    statements: u64,
+    /// The instant the current run has to end by.
+    deadline: Option<Instant>,
+}
+
+impl Budget {
+    /**
+     * Creates an unlimited budget.
+     */
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_statements(mut self, max_statements: u64) -> Self {
+        self.max_statements = Some(max_statements);
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /**
+     * Cancels the runs once `cancel_flag` is set. The flag isn't cleared by the interpreter.
+     */
+    pub fn with_cancel_flag(mut self, cancel_flag: Arc<AtomicBool>) -> Self {
+        self.cancel_flag = Some(cancel_flag);
+        self
+    }
+
+    /**
+     * Starts a run with the whole budget.
+     */
+    pub fn start(&mut self) {
+        self.statements = 0;
+        self.deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+    }
+
+    /**
+     * Checks that the run can go on, counting one more executed statement if `statement` is
+     * set.
+     *
+     * Note: Returns a `LoxResult::Interrupted` error once the budget is exhausted.
+     */
+    pub fn check(&mut self, statement: bool) -> Result<(), LoxResult> {
+        let interruption = if self
+            .cancel_flag
+            .as_ref()
+            .is_some_and(|flag| flag.load(Ordering::Relaxed))
+        {
+            Some(Interruption::Cancelled)
+        } else if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            Some(Interruption::Timeout)
+        } else if statement {
+            self.statements += 1;
+            match self.max_statements {
+                Some(max_statements) if self.statements > max_statements => {
+                    Some(Interruption::StatementLimit)
+                }
+                _ => None,
+            }
+        } else {
+            None
+        };
+
+        match interruption {
+            Some(interruption) => Err(LoxResult::Interrupted { interruption }),
+            None => Ok(()),
+        }
+    }
+}
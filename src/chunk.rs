@@ -0,0 +1,184 @@
+use std::fmt;
+use std::rc::Rc;
+
+use crate::object::Object;
+use crate::token::Token;
+use crate::vm_object::CompiledFunction;
+
+/**
+ * The instructions of the bytecode run by the `Vm`. Operands are stored in the instruction
+ * itself.
+ *
+ * Note: Jump offsets are relative to the instruction following the jump.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpCode {
+    /// Pushes the constant at the given index.
+    Constant(u16),
+    Nil,
+    True,
+    False,
+    /// Discards the value on top of the stack.
+    Pop,
+    /// Pushes the local variable stored in the given slot of the current call frame.
+    GetLocal(u16),
+    /// Stores the value on top of the stack in the given slot of the current call frame.
+    SetLocal(u16),
+    /// Pushes the global variable whose name is the given constant.
+    GetGlobal(u16),
+    /// Pops the value on top of the stack into a new global variable.
+    DefineGlobal(u16),
+    /// Stores the value on top of the stack in an existing global variable.
+    SetGlobal(u16),
+    /// Pushes the variable captured by the current closure at the given index.
+    GetUpvalue(u16),
+    /// Stores the value on top of the stack in the variable captured at the given index.
+    SetUpvalue(u16),
+    /// Replaces the instance (or class) on top of the stack with its named member.
+    GetProperty(u16),
+    /// Sets the named field of an instance: `[instance, value]` -> `[value]`.
+    SetProperty(u16),
+    /// Gets the named method of a superclass bound to `this`: `[this, superclass]` -> `[method]`.
+    GetSuper(u16),
+    /// `[object, index]` -> `[element]`
+    GetIndex,
+    /// `[object, index, value]` -> `[value]`
+    SetIndex,
+    Equal,
+    NotEqual,
+    Greater,
+    GreaterEqual,
+    Less,
+    LessEqual,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Not,
+    Negate,
+    /// Pops the value on top of the stack and writes it to the output.
+    Print,
+    Jump(u16),
+    /// Jumps if the value on top of the stack is falsy, without popping it.
+    JumpIfFalse(u16),
+    /// Jumps backward, to the start of a loop.
+    Loop(u16),
+    /// Calls the value below the given number of arguments.
+    Call(u16),
+    /// Pushes a closure over the function at the given index of the chunk's functions.
+    Closure(u16),
+    /// Moves the local variable on top of the stack to the heap, then pops it.
+    CloseUpvalue,
+    Return,
+    /// Replaces the given number of values on top of the stack with a list of them.
+    List(u16),
+    /// Pushes a new empty map.
+    Map,
+    /// Inserts an entry in a map: `[map, key, value]` -> `[map]`.
+    MapInsert,
+    /// Pushes a new class with the given name.
+    Class(u16),
+    /// Replaces the superclass on top of the stack with a new class with the given name.
+    Subclass(u16),
+    /// Adds the closure on top of the stack to the methods of the class below it, then pops it.
+    Method(u16),
+    /// Adds the closure on top of the stack to the static methods of the class below it.
+    StaticMethod(u16),
+    /// Starts an expression statement: a runtime error in it is reported and execution resumes
+    /// at the given offset.
+    PushHandler(u16),
+    /// Ends an expression statement.
+    PopHandler,
+}
+
+/**
+ * A chunk of bytecode: the compiled body of a function.
+ */
+#[derive(Debug, Default)]
+pub struct Chunk {
+    /// The instructions of the chunk.
+    pub code: Vec<OpCode>,
+    /// The values referenced by the instructions (e.g. literals and names).
+    pub constants: Vec<Object>,
+    /// The functions declared in the chunk, referenced by `OpCode::Closure`.
+    pub functions: Vec<Rc<CompiledFunction>>,
+    /// For each instruction, the index in `self.tokens` of the token it was compiled from.
+    token_indexes: Vec<usize>,
+    /// The tokens the instructions were compiled from, used to report errors.
+    tokens: Vec<Token>,
+}
+
+impl Chunk {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Appends an instruction compiled from `token` and returns its offset.
+     */
+    pub fn write(&mut self, op: OpCode, token: &Token) -> usize {
+        // Consecutive instructions usually come from the same token, only store it once
+        let is_new_token = self
+            .tokens
+            .last()
+            .is_none_or(|last| last.ttype != token.ttype || last.span() != token.span());
+        if is_new_token {
+            self.tokens.push(token.clone());
+        }
+
+        self.token_indexes.push(self.tokens.len() - 1);
+        self.code.push(op);
+        self.code.len() - 1
+    }
+
+    /**
+     * Adds a constant to the chunk and returns its index. Equal strings share the same index.
+     */
+    pub fn add_constant(&mut self, value: Object) -> usize {
+        if let Object::Str(_) = value {
+            if let Some(index) = self.constants.iter().position(|c| *c == value) {
+                return index;
+            }
+        }
+
+        self.constants.push(value);
+        self.constants.len() - 1
+    }
+
+    /**
+     * Returns the token the instruction at `offset` was compiled from.
+     */
+    pub fn token(&self, offset: usize) -> &Token {
+        &self.tokens[self.token_indexes[offset]]
+    }
+}
+
+impl fmt::Display for Chunk {
+    /**
+     * Disassembles the chunk: one instruction per line along with its source line.
+     */
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (offset, op) in self.code.iter().enumerate() {
+            write!(f, "{offset:04} {:4} {op:?}", self.token(offset).src_line)?;
+            match op {
+                OpCode::Constant(index)
+                | OpCode::GetGlobal(index)
+                | OpCode::DefineGlobal(index)
+                | OpCode::SetGlobal(index)
+                | OpCode::GetProperty(index)
+                | OpCode::SetProperty(index)
+                | OpCode::GetSuper(index)
+                | OpCode::Class(index)
+                | OpCode::Subclass(index)
+                | OpCode::Method(index)
+                | OpCode::StaticMethod(index) => {
+                    writeln!(f, " {}", self.constants[*index as usize])?
+                }
+                OpCode::Closure(index) => writeln!(f, " {}", self.functions[*index as usize])?,
+                _ => writeln!(f)?,
+            }
+        }
+
+        Ok(())
+    }
+}
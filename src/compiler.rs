@@ -0,0 +1,941 @@
+use std::rc::Rc;
+
+use crate::chunk::{Chunk, OpCode};
+use crate::errors::{CompilerErrorType, LoxResult};
+use crate::expr::*;
+use crate::object::Object;
+use crate::stmt::*;
+use crate::token::Token;
+use crate::token_type::TokenType;
+use crate::vm_object::{CompiledFunction, UpvalueRef};
+
+#[derive(PartialEq, Clone, Copy)]
+enum FunctionType {
+    Script,
+    Function,
+    Method,
+    Init,
+    Getter,
+}
+
+/**
+ * A local variable of the function being compiled. Its position in `FunctionState::locals` is
+ * its slot in the call frame.
+ */
+struct Local {
+    name: String,
+    /// The depth of the scope the variable was declared in.
+    depth: usize,
+    /// Whether a closure captured the variable, in which case it must be moved off the stack
+    /// when it goes out of scope.
+    is_captured: bool,
+}
+
+/**
+ * The loop being compiled, used to compile its `break` and `continue` statements.
+ */
+struct Loop {
+    /// The number of local variables declared when entering the loop.
+    local_count: usize,
+    /// The jumps of the `break` statements, patched to the end of the loop.
+    breaks: Vec<usize>,
+    /// The jumps of the `continue` statements, patched to the increment of the loop.
+    continues: Vec<usize>,
+}
+
+/**
+ * The state of a function being compiled.
+ */
+struct FunctionState {
+    function: CompiledFunction,
+    function_type: FunctionType,
+    locals: Vec<Local>,
+    scope_depth: usize,
+    loops: Vec<Loop>,
+}
+
+impl FunctionState {
+    fn new(function: CompiledFunction, function_type: FunctionType) -> Self {
+        // The first slot holds the called function, or `this` in methods
+        let name = match function_type {
+            FunctionType::Script | FunctionType::Function => "",
+            FunctionType::Method | FunctionType::Init | FunctionType::Getter => "this",
+        };
+
+        FunctionState {
+            function,
+            function_type,
+            locals: vec![Local {
+                name: name.to_string(),
+                depth: 0,
+                is_captured: false,
+            }],
+            scope_depth: 0,
+            loops: Vec::new(),
+        }
+    }
+}
+
+/**
+ * The Compiler turns the statements of a resolved program into bytecode for the `Vm`.
+ *
+ * Note: The program is expected to have gone through the `Resolver`, so the static errors
+ * (e.g. `return` at top level) are not checked again here.
+ */
+pub struct Compiler {
+    /// The functions being compiled, the innermost one last.
+    functions: Vec<FunctionState>,
+    /// The token the next instructions are compiled from.
+    token: Token,
+}
+
+impl Default for Compiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Compiler {
+    pub fn new() -> Self {
+        Compiler {
+            functions: Vec::new(),
+            token: Token::default(),
+        }
+    }
+
+    /**
+     * Compiles a program into the function running it at top level.
+     *
+     * Note: If the last statement is an expression statement, the function returns its value.
+     */
+    pub fn compile(&mut self, statements: &[Stmt]) -> Result<CompiledFunction, LoxResult> {
+        self.functions = vec![FunctionState::new(
+            CompiledFunction::default(),
+            FunctionType::Script,
+        )];
+
+        match statements.split_last() {
+            Some((Stmt::Expression { expression }, statements)) => {
+                self.compile_stmts(statements)?;
+                self.compile_expr(expression)?;
+                self.emit(OpCode::Return);
+            }
+            _ => {
+                self.compile_stmts(statements)?;
+                self.emit_return();
+            }
+        }
+
+        Ok(self.functions.pop().unwrap().function)
+    }
+
+    fn compile_stmts(&mut self, statements: &[Stmt]) -> Result<(), LoxResult> {
+        statements
+            .iter()
+            .try_for_each(|stmt| self.compile_stmt(stmt))
+    }
+
+    fn compile_stmt(&mut self, stmt: &Stmt) -> Result<(), LoxResult> {
+        stmt.accept(self)
+    }
+
+    fn compile_expr(&mut self, expr: &Expr) -> Result<(), LoxResult> {
+        expr.accept(self)
+    }
+
+    fn current(&mut self) -> &mut FunctionState {
+        self.functions.last_mut().unwrap()
+    }
+
+    fn chunk(&mut self) -> &mut Chunk {
+        &mut self.current().function.chunk
+    }
+
+    fn error(&self, error_type: CompilerErrorType) -> LoxResult {
+        LoxResult::Compiler {
+            token: self.token.clone(),
+            error_type,
+        }
+    }
+
+    /**
+     * Appends an instruction compiled from the current token and returns its offset.
+     */
+    fn emit(&mut self, op: OpCode) -> usize {
+        let token = self.token.clone();
+        self.chunk().write(op, &token)
+    }
+
+    /**
+     * Appends an instruction compiled from `token`, e.g. the operator of a binary expression
+     * so that runtime errors point at it.
+     */
+    fn emit_at(&mut self, op: OpCode, token: &Token) -> usize {
+        self.token = token.clone();
+        self.emit(op)
+    }
+
+    /**
+     * Emits the implicit return at the end of a function: `this` in an `init()` function, `nil`
+     * otherwise.
+     */
+    fn emit_return(&mut self) {
+        if self.current().function_type == FunctionType::Init {
+            self.emit(OpCode::GetLocal(0));
+        } else {
+            self.emit(OpCode::Nil);
+        }
+        self.emit(OpCode::Return);
+    }
+
+    /**
+     * Emits a jump whose offset is set later on by `self.patch_jump()`.
+     */
+    fn emit_jump(&mut self, op: fn(u16) -> OpCode) -> usize {
+        self.emit(op(0))
+    }
+
+    /**
+     * Makes the jump at `offset` land on the next instruction to be emitted.
+     */
+    fn patch_jump(&mut self, offset: usize) -> Result<(), LoxResult> {
+        let jump = self.chunk().code.len() - offset - 1;
+        let jump = u16::try_from(jump).map_err(|_| self.error(CompilerErrorType::JumpTooLarge))?;
+
+        let chunk = self.chunk();
+        chunk.code[offset] = match chunk.code[offset] {
+            OpCode::Jump(_) => OpCode::Jump(jump),
+            OpCode::JumpIfFalse(_) => OpCode::JumpIfFalse(jump),
+            OpCode::PushHandler(_) => OpCode::PushHandler(jump),
+            op => unreachable!("{op:?} is not a jump"),
+        };
+
+        Ok(())
+    }
+
+    /**
+     * Emits a jump back to the instruction at `start`.
+     */
+    fn emit_loop(&mut self, start: usize) -> Result<(), LoxResult> {
+        let jump = self.chunk().code.len() - start + 1;
+        let jump = u16::try_from(jump).map_err(|_| self.error(CompilerErrorType::JumpTooLarge))?;
+        self.emit(OpCode::Loop(jump));
+
+        Ok(())
+    }
+
+    /**
+     * Adds a constant to the current chunk and returns its index.
+     */
+    fn make_constant(&mut self, value: Object) -> Result<u16, LoxResult> {
+        let index = self.chunk().add_constant(value);
+        u16::try_from(index).map_err(|_| self.error(CompilerErrorType::TooManyConstants))
+    }
+
+    fn begin_scope(&mut self) {
+        self.current().scope_depth += 1;
+    }
+
+    /**
+     * Leaves a scope, discarding the local variables declared in it.
+     */
+    fn end_scope(&mut self) {
+        self.current().scope_depth -= 1;
+
+        let depth = self.current().scope_depth;
+        while let Some(local) = self.current().locals.last() {
+            if local.depth <= depth {
+                break;
+            }
+            let op = match local.is_captured {
+                true => OpCode::CloseUpvalue,
+                false => OpCode::Pop,
+            };
+            self.emit(op);
+            self.current().locals.pop();
+        }
+    }
+
+    /**
+     * Declares a local variable in the current scope. Its value is the one on top of the stack.
+     */
+    fn add_local(&mut self, name: &str) -> Result<(), LoxResult> {
+        if self.current().locals.len() > u16::MAX as usize {
+            return Err(self.error(CompilerErrorType::TooManyLocals));
+        }
+
+        let depth = self.current().scope_depth;
+        self.current().locals.push(Local {
+            name: name.to_string(),
+            depth,
+            is_captured: false,
+        });
+
+        Ok(())
+    }
+
+    /**
+     * Defines the variable `name` with the value on top of the stack: a local variable inside a
+     * scope, a global variable otherwise.
+     */
+    fn define_variable(&mut self, name: &Token) -> Result<(), LoxResult> {
+        if self.current().scope_depth > 0 {
+            self.add_local(&name.lexeme)
+        } else {
+            let constant = self.make_constant(Object::from(name.lexeme.as_str()))?;
+            self.emit_at(OpCode::DefineGlobal(constant), name);
+            Ok(())
+        }
+    }
+
+    /**
+     * Returns the slot of the local variable `name` of the function at `level`, if any.
+     */
+    fn resolve_local(&self, level: usize, name: &str) -> Option<usize> {
+        self.functions[level]
+            .locals
+            .iter()
+            .rposition(|local| local.name == name)
+    }
+
+    /**
+     * Returns the index of the upvalue through which the function at `level` captures the
+     * variable `name` of an enclosing function, if any.
+     */
+    fn resolve_upvalue(&mut self, level: usize, name: &str) -> Result<Option<u16>, LoxResult> {
+        // The top level script has no enclosing function
+        if level == 0 {
+            return Ok(None);
+        }
+
+        // Capture a local variable of the enclosing function
+        if let Some(slot) = self.resolve_local(level - 1, name) {
+            self.functions[level - 1].locals[slot].is_captured = true;
+            return self.add_upvalue(level, slot, true).map(Some);
+        }
+
+        // Capture a variable the enclosing function captured itself
+        if let Some(index) = self.resolve_upvalue(level - 1, name)? {
+            return self.add_upvalue(level, index as usize, false).map(Some);
+        }
+
+        Ok(None)
+    }
+
+    fn add_upvalue(
+        &mut self,
+        level: usize,
+        index: usize,
+        is_local: bool,
+    ) -> Result<u16, LoxResult> {
+        let index =
+            u16::try_from(index).map_err(|_| self.error(CompilerErrorType::TooManyLocals))?;
+        let upvalue = UpvalueRef { is_local, index };
+
+        // Reuse the upvalue if the variable was already captured
+        let upvalues = &mut self.functions[level].function.upvalues;
+        if let Some(position) = upvalues.iter().position(|u| *u == upvalue) {
+            return Ok(position as u16);
+        }
+
+        if upvalues.len() > u16::MAX as usize {
+            return Err(self.error(CompilerErrorType::TooManyUpvalues));
+        }
+        upvalues.push(upvalue);
+        Ok((upvalues.len() - 1) as u16)
+    }
+
+    /**
+     * Emits the instruction reading (or writing if `assign` is true) the variable `name`.
+     */
+    fn named_variable(&mut self, name: &Token, assign: bool) -> Result<(), LoxResult> {
+        self.token = name.clone();
+        let level = self.functions.len() - 1;
+
+        let op = if let Some(slot) = self.resolve_local(level, &name.lexeme) {
+            let slot = slot as u16;
+            match assign {
+                true => OpCode::SetLocal(slot),
+                false => OpCode::GetLocal(slot),
+            }
+        } else if let Some(index) = self.resolve_upvalue(level, &name.lexeme)? {
+            match assign {
+                true => OpCode::SetUpvalue(index),
+                false => OpCode::GetUpvalue(index),
+            }
+        } else {
+            let constant = self.make_constant(Object::from(name.lexeme.as_str()))?;
+            match assign {
+                true => OpCode::SetGlobal(constant),
+                false => OpCode::GetGlobal(constant),
+            }
+        };
+        self.emit(op);
+
+        Ok(())
+    }
+
+    /**
+     * Compiles a function and emits the instruction creating a closure of it.
+     */
+    fn function(
+        &mut self,
+        name: Option<&Token>,
+        params: &[Token],
+        body: &[Stmt],
+        function_type: FunctionType,
+    ) -> Result<(), LoxResult> {
+        let function = CompiledFunction {
+            name: name.map(|name| name.lexeme.clone()),
+            params: params.iter().map(|param| param.lexeme.clone()).collect(),
+            is_init_function: function_type == FunctionType::Init,
+            is_getter: function_type == FunctionType::Getter,
+            ..Default::default()
+        };
+        self.functions
+            .push(FunctionState::new(function, function_type));
+
+        // The parameters are the first local variables of the function
+        self.begin_scope();
+        for param in params {
+            self.add_local(&param.lexeme)?;
+        }
+        self.compile_stmts(body)?;
+        // The call frame is discarded on return, no need to end the scope
+        self.emit_return();
+
+        let function = self.functions.pop().unwrap().function;
+        let chunk = self.chunk();
+        chunk.functions.push(Rc::new(function));
+        let index = u16::try_from(chunk.functions.len() - 1)
+            .map_err(|_| self.error(CompilerErrorType::TooManyConstants))?;
+        self.emit(OpCode::Closure(index));
+
+        Ok(())
+    }
+
+    /**
+     * Compiles the methods of a class, the class being on top of the stack.
+     */
+    fn methods(
+        &mut self,
+        methods: &[Stmt],
+        function_type: FunctionType,
+        op: fn(u16) -> OpCode,
+    ) -> Result<(), LoxResult> {
+        for method in methods {
+            if let Stmt::Function { name, params, body } = method {
+                let method_type = match function_type {
+                    FunctionType::Method if name.lexeme == "init" => FunctionType::Init,
+                    _ => function_type,
+                };
+                let constant = self.make_constant(Object::from(name.lexeme.as_str()))?;
+                self.function(Some(name), params, body, method_type)?;
+                self.emit_at(op(constant), name);
+            } else {
+                unreachable!()
+            }
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Emits the instructions discarding the local variables declared since the start of the
+     * innermost loop, before jumping out of it.
+     */
+    fn discard_loop_locals(&mut self) {
+        let local_count = self.current().loops.last().unwrap().local_count;
+        let captures: Vec<bool> = self.current().locals[local_count..]
+            .iter()
+            .rev()
+            .map(|local| local.is_captured)
+            .collect();
+        for is_captured in captures {
+            let op = match is_captured {
+                true => OpCode::CloseUpvalue,
+                false => OpCode::Pop,
+            };
+            self.emit(op);
+        }
+    }
+}
+
+/**
+ * Returns a token with the same location as `token` but a different lexeme, used for the
+ * variables implicitly read by some expressions (e.g. `this` in `super.method`).
+ */
+fn synthetic_token(token: &Token, lexeme: &str) -> Token {
+    Token {
+        lexeme: lexeme.to_string(),
+        ..token.clone()
+    }
+}
+
+/**
+ * Implementation of the expression visitor pattern for the `Compiler`. Each expression leaves
+ * its value on top of the stack.
+ */
+impl ExprVisitor<()> for Compiler {
+    fn visit_assign_expr(
+        &mut self,
+        _id: &usize,
+        name: &Token,
+        value: &Expr,
+    ) -> Result<(), LoxResult> {
+        self.compile_expr(value)?;
+        self.named_variable(name, true)
+    }
+
+    fn visit_binary_expr(
+        &mut self,
+        left: &Expr,
+        operator: &Token,
+        right: &Expr,
+    ) -> Result<(), LoxResult> {
+        self.compile_expr(left)?;
+        self.compile_expr(right)?;
+
+        let op = match operator.ttype {
+            TokenType::Minus => OpCode::Subtract,
+            TokenType::Slash => OpCode::Divide,
+            TokenType::Star => OpCode::Multiply,
+            TokenType::Plus => OpCode::Add,
+            TokenType::Greater => OpCode::Greater,
+            TokenType::GreaterEqual => OpCode::GreaterEqual,
+            TokenType::Less => OpCode::Less,
+            TokenType::LessEqual => OpCode::LessEqual,
+            TokenType::BangEqual => OpCode::NotEqual,
+            TokenType::EqualEqual => OpCode::Equal,
+            _ => unreachable!(),
+        };
+        self.emit_at(op, operator);
+
+        Ok(())
+    }
+
+    fn visit_call_expr(
+        &mut self,
+        callee: &Expr,
+        paren: &Token,
+        arguments: &[Expr],
+    ) -> Result<(), LoxResult> {
+        self.compile_expr(callee)?;
+        for argument in arguments {
+            self.compile_expr(argument)?;
+        }
+        self.emit_at(OpCode::Call(arguments.len() as u16), paren);
+
+        Ok(())
+    }
+
+    fn visit_get_expr(&mut self, object: &Expr, name: &Token) -> Result<(), LoxResult> {
+        self.compile_expr(object)?;
+        let constant = self.make_constant(Object::from(name.lexeme.as_str()))?;
+        self.emit_at(OpCode::GetProperty(constant), name);
+
+        Ok(())
+    }
+
+    fn visit_lambda_expr(
+        &mut self,
+        keyword: &Token,
+        params: &[Token],
+        body: &[Stmt],
+    ) -> Result<(), LoxResult> {
+        self.token = keyword.clone();
+        self.function(None, params, body, FunctionType::Function)
+    }
+
+    fn visit_index_expr(
+        &mut self,
+        object: &Expr,
+        bracket: &Token,
+        index: &Expr,
+    ) -> Result<(), LoxResult> {
+        self.compile_expr(object)?;
+        self.compile_expr(index)?;
+        self.emit_at(OpCode::GetIndex, bracket);
+
+        Ok(())
+    }
+
+    fn visit_list_expr(&mut self, bracket: &Token, elements: &[Expr]) -> Result<(), LoxResult> {
+        for element in elements {
+            self.compile_expr(element)?;
+        }
+        let count = u16::try_from(elements.len())
+            .map_err(|_| self.error(CompilerErrorType::TooManyConstants))?;
+        self.emit_at(OpCode::List(count), bracket);
+
+        Ok(())
+    }
+
+    fn visit_logical_expr(
+        &mut self,
+        left: &Expr,
+        operator: &Token,
+        right: &Expr,
+    ) -> Result<(), LoxResult> {
+        self.compile_expr(left)?;
+        self.token = operator.clone();
+
+        if operator.ttype == TokenType::Or {
+            // Keep the left value if it is truthy
+            let else_jump = self.emit_jump(OpCode::JumpIfFalse);
+            let end_jump = self.emit_jump(OpCode::Jump);
+            self.patch_jump(else_jump)?;
+            self.emit(OpCode::Pop);
+            self.compile_expr(right)?;
+            self.patch_jump(end_jump)
+        } else {
+            // Keep the left value if it is falsy
+            let end_jump = self.emit_jump(OpCode::JumpIfFalse);
+            self.emit(OpCode::Pop);
+            self.compile_expr(right)?;
+            self.patch_jump(end_jump)
+        }
+    }
+
+    fn visit_map_expr(
+        &mut self,
+        brace: &Token,
+        keys: &[Expr],
+        values: &[Expr],
+    ) -> Result<(), LoxResult> {
+        self.emit_at(OpCode::Map, brace);
+        // Insert each entry as soon as it is evaluated, like the tree-walking interpreter
+        for (key, value) in keys.iter().zip(values) {
+            self.compile_expr(key)?;
+            self.compile_expr(value)?;
+            self.emit_at(OpCode::MapInsert, brace);
+        }
+
+        Ok(())
+    }
+
+    fn visit_set_expr(
+        &mut self,
+        object: &Expr,
+        name: &Token,
+        value: &Expr,
+    ) -> Result<(), LoxResult> {
+        self.compile_expr(object)?;
+        self.compile_expr(value)?;
+        let constant = self.make_constant(Object::from(name.lexeme.as_str()))?;
+        self.emit_at(OpCode::SetProperty(constant), name);
+
+        Ok(())
+    }
+
+    fn visit_setindex_expr(
+        &mut self,
+        object: &Expr,
+        bracket: &Token,
+        index: &Expr,
+        value: &Expr,
+    ) -> Result<(), LoxResult> {
+        self.compile_expr(object)?;
+        self.compile_expr(index)?;
+        self.compile_expr(value)?;
+        self.emit_at(OpCode::SetIndex, bracket);
+
+        Ok(())
+    }
+
+    fn visit_super_expr(
+        &mut self,
+        _id: &usize,
+        keyword: &Token,
+        method: &Token,
+    ) -> Result<(), LoxResult> {
+        self.named_variable(&synthetic_token(keyword, "this"), false)?;
+        self.named_variable(keyword, false)?;
+        let constant = self.make_constant(Object::from(method.lexeme.as_str()))?;
+        self.emit_at(OpCode::GetSuper(constant), method);
+
+        Ok(())
+    }
+
+    fn visit_this_expr(&mut self, _id: &usize, keyword: &Token) -> Result<(), LoxResult> {
+        self.named_variable(keyword, false)
+    }
+
+    fn visit_unary_expr(&mut self, operator: &Token, right: &Expr) -> Result<(), LoxResult> {
+        self.compile_expr(right)?;
+
+        let op = match operator.ttype {
+            TokenType::Minus => OpCode::Negate,
+            TokenType::Bang => OpCode::Not,
+            _ => unreachable!(),
+        };
+        self.emit_at(op, operator);
+
+        Ok(())
+    }
+
+    fn visit_grouping_expr(&mut self, expression: &Expr) -> Result<(), LoxResult> {
+        self.compile_expr(expression)
+    }
+
+    fn visit_literal_expr(
+        &mut self,
+        _token: &Token,
+        value: &Option<Object>,
+    ) -> Result<(), LoxResult> {
+        match value {
+            None | Some(Object::Nil) => self.emit(OpCode::Nil),
+            Some(Object::True) => self.emit(OpCode::True),
+            Some(Object::False) => self.emit(OpCode::False),
+            Some(value) => {
+                let constant = self.make_constant(value.clone())?;
+                self.emit(OpCode::Constant(constant))
+            }
+        };
+
+        Ok(())
+    }
+
+    fn visit_variable_expr(&mut self, _id: &usize, name: &Token) -> Result<(), LoxResult> {
+        self.named_variable(name, false)
+    }
+}
+
+/**
+ * Implementation of the statement visitor pattern for the `Compiler`. Statements leave the stack
+ * as they found it, except for the declarations of local variables.
+ */
+impl StmtVisitor<()> for Compiler {
+    fn visit_block_stmt(&mut self, statements: &[Stmt]) -> Result<(), LoxResult> {
+        self.begin_scope();
+        self.compile_stmts(statements)?;
+        self.end_scope();
+
+        Ok(())
+    }
+
+    fn visit_break_stmt(&mut self, keyword: &Token) -> Result<(), LoxResult> {
+        self.token = keyword.clone();
+        self.discard_loop_locals();
+        let jump = self.emit_jump(OpCode::Jump);
+        self.current().loops.last_mut().unwrap().breaks.push(jump);
+
+        Ok(())
+    }
+
+    fn visit_class_stmt(
+        &mut self,
+        name: &Token,
+        superclass: &Option<Expr>,
+        methods: &[Stmt],
+        class_methods: &[Stmt],
+        getters: &[Stmt],
+    ) -> Result<(), LoxResult> {
+        let constant = self.make_constant(Object::from(name.lexeme.as_str()))?;
+
+        // Create the class, from its superclass if any
+        if let Some(superclass) = superclass {
+            self.compile_expr(superclass)?;
+            self.emit_at(OpCode::Subclass(constant), name);
+        } else {
+            self.emit_at(OpCode::Class(constant), name);
+        }
+        self.define_variable(name)?;
+
+        // Methods capture the superclass in a `super` variable of their enclosing scope
+        if let Some(superclass) = superclass {
+            self.begin_scope();
+            self.compile_expr(superclass)?;
+            self.add_local("super")?;
+        }
+
+        // Add the methods to the class, then discard it
+        self.named_variable(name, false)?;
+        self.methods(methods, FunctionType::Method, OpCode::Method)?;
+        self.methods(getters, FunctionType::Getter, OpCode::Method)?;
+        self.methods(class_methods, FunctionType::Method, OpCode::StaticMethod)?;
+        self.emit(OpCode::Pop);
+
+        if superclass.is_some() {
+            self.end_scope();
+        }
+
+        Ok(())
+    }
+
+    fn visit_continue_stmt(&mut self, keyword: &Token) -> Result<(), LoxResult> {
+        self.token = keyword.clone();
+        self.discard_loop_locals();
+        let jump = self.emit_jump(OpCode::Jump);
+        self.current()
+            .loops
+            .last_mut()
+            .unwrap()
+            .continues
+            .push(jump);
+
+        Ok(())
+    }
+
+    /**
+     * Note: A runtime error in an expression statement is reported and the execution continues
+     * after it, like in the tree-walking interpreter.
+     */
+    fn visit_expression_stmt(&mut self, expression: &Expr) -> Result<(), LoxResult> {
+        let handler = self.emit_jump(OpCode::PushHandler);
+        self.compile_expr(expression)?;
+        self.emit(OpCode::Pop);
+        self.emit(OpCode::PopHandler);
+        self.patch_jump(handler)
+    }
+
+    fn visit_function_stmt(
+        &mut self,
+        name: &Token,
+        params: &[Token],
+        body: &[Stmt],
+    ) -> Result<(), LoxResult> {
+        self.token = name.clone();
+
+        // A local function is declared before its body so that it can call itself
+        if self.current().scope_depth > 0 {
+            self.add_local(&name.lexeme)?;
+            self.function(Some(name), params, body, FunctionType::Function)
+        } else {
+            self.function(Some(name), params, body, FunctionType::Function)?;
+            self.define_variable(name)
+        }
+    }
+
+    fn visit_if_stmt(
+        &mut self,
+        condition: &Expr,
+        then_branch: &Stmt,
+        else_branch: &Option<Stmt>,
+    ) -> Result<(), LoxResult> {
+        self.compile_expr(condition)?;
+
+        let else_jump = self.emit_jump(OpCode::JumpIfFalse);
+        self.emit(OpCode::Pop);
+        self.compile_stmt(then_branch)?;
+        let end_jump = self.emit_jump(OpCode::Jump);
+
+        self.patch_jump(else_jump)?;
+        self.emit(OpCode::Pop);
+        if let Some(else_branch) = else_branch {
+            self.compile_stmt(else_branch)?;
+        }
+
+        self.patch_jump(end_jump)
+    }
+
+    fn visit_import_stmt(
+        &mut self,
+        keyword: &Token,
+        _name: &Token,
+        _path: &Token,
+    ) -> Result<(), LoxResult> {
+        self.token = keyword.clone();
+        Err(self.error(CompilerErrorType::UnsupportedImport))
+    }
+
+    fn visit_throw_stmt(&mut self, keyword: &Token, _value: &Expr) -> Result<(), LoxResult> {
+        self.token = keyword.clone();
+        Err(self.error(CompilerErrorType::UnsupportedExceptions))
+    }
+
+    fn visit_try_stmt(
+        &mut self,
+        keyword: &Token,
+        _body: &[Stmt],
+        _catch_name: &Option<Token>,
+        _catch_body: &[Stmt],
+        _finally_body: &[Stmt],
+    ) -> Result<(), LoxResult> {
+        self.token = keyword.clone();
+        Err(self.error(CompilerErrorType::UnsupportedExceptions))
+    }
+
+    fn visit_print_stmt(&mut self, keyword: &Token, expression: &Expr) -> Result<(), LoxResult> {
+        self.compile_expr(expression)?;
+        self.token = keyword.clone();
+        self.emit(OpCode::Print);
+
+        Ok(())
+    }
+
+    fn visit_return_stmt(
+        &mut self,
+        keyword: &Token,
+        value: &Option<Expr>,
+    ) -> Result<(), LoxResult> {
+        self.token = keyword.clone();
+
+        match value {
+            Some(value) => {
+                self.compile_expr(value)?;
+                self.emit_at(OpCode::Return, keyword);
+            }
+            None => self.emit_return(),
+        }
+
+        Ok(())
+    }
+
+    fn visit_var_stmt(
+        &mut self,
+        name: &Token,
+        initializer: &Option<Expr>,
+    ) -> Result<(), LoxResult> {
+        match initializer {
+            Some(initializer) => self.compile_expr(initializer)?,
+            None => {
+                self.emit_at(OpCode::Nil, name);
+            }
+        }
+
+        self.define_variable(name)
+    }
+
+    /**
+     * Note: The increment (of a desugared for loop) is the target of `continue` statements.
+     */
+    fn visit_while_stmt(
+        &mut self,
+        condition: &Expr,
+        body: &Stmt,
+        increment: &Option<Expr>,
+    ) -> Result<(), LoxResult> {
+        let loop_start = self.chunk().code.len();
+        self.compile_expr(condition)?;
+        let exit_jump = self.emit_jump(OpCode::JumpIfFalse);
+        self.emit(OpCode::Pop);
+
+        let local_count = self.current().locals.len();
+        self.current().loops.push(Loop {
+            local_count,
+            breaks: Vec::new(),
+            continues: Vec::new(),
+        });
+        self.compile_stmt(body)?;
+        let current_loop = self.current().loops.pop().unwrap();
+
+        for jump in current_loop.continues {
+            self.patch_jump(jump)?;
+        }
+        if let Some(increment) = increment {
+            self.compile_expr(increment)?;
+            self.emit(OpCode::Pop);
+        }
+        self.emit_loop(loop_start)?;
+
+        // The condition is still on the stack when leaving the loop through it
+        self.patch_jump(exit_jump)?;
+        self.emit(OpCode::Pop);
+        for jump in current_loop.breaks {
+            self.patch_jump(jump)?;
+        }
+
+        Ok(())
+    }
+}
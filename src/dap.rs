@@ -0,0 +1,444 @@
+use std::cell::RefCell;
+use std::fs;
+use std::io::{BufRead, Write};
+use std::rc::Rc;
+
+use serde_json::{json, Value};
+
+use crate::debugger::{
+    Breakpoints, DebugCommand, DebugFrontend, Debugger, Frame, Pause, PauseReason,
+};
+use crate::diagnostic::SourceFile;
+use crate::errors::{EvalError, LoxResult};
+use crate::interpreter::Interpreter;
+use crate::lox::{Backend, Lox};
+use crate::native_functions::NativeModule;
+use crate::object::Object;
+use crate::output::OutputSink;
+use crate::protocol;
+
+/// The id of the only thread of a Lox program.
+const THREAD_ID: i64 = 1;
+
+/**
+ * The stream of Debug Adapter Protocol messages exchanged with the client (e.g. an editor).
+ */
+struct Connection {
+    /// Where the requests are read from.
+    input: Box<dyn BufRead>,
+    /// Where the responses and events are written to.
+    output: Box<dyn Write>,
+    /// The sequence number of the last message sent.
+    seq: i64,
+    /// Whether the client asked to end the session, or can't be written to anymore.
+    disconnected: bool,
+}
+
+impl Connection {
+    fn read_request(&mut self) -> Option<Value> {
+        protocol::read_message(&mut self.input)
+    }
+
+    /**
+     * Sends a message to the client, unless it went away. The session ends once a message
+     * can't be sent.
+     */
+    fn send(&mut self, mut message: Value) {
+        if self.disconnected {
+            return;
+        }
+        self.seq += 1;
+        message["seq"] = json!(self.seq);
+        if protocol::write_message(&mut self.output, &message).is_err() {
+            self.disconnected = true;
+        }
+    }
+
+    fn respond(&mut self, request: &Value, body: Value) {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": true,
+            "body": body,
+        }));
+    }
+
+    fn respond_error(&mut self, request: &Value, message: &str) {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": false,
+            "message": message,
+        }));
+    }
+
+    fn event(&mut self, event: &str, body: Value) {
+        self.send(json!({
+            "type": "event",
+            "event": event,
+            "body": body,
+        }));
+    }
+
+    /**
+     * Answers the requests that can be made at any time of the session, and tells if `request`
+     * was one of them.
+     */
+    fn handle_common(&mut self, request: &Value, breakpoints: &mut Breakpoints) -> bool {
+        match request["command"].as_str().unwrap_or_default() {
+            "threads" => self.respond(
+                request,
+                json!({ "threads": [{ "id": THREAD_ID, "name": "main" }] }),
+            ),
+            "setBreakpoints" => {
+                // The breakpoints of the request replace the previous ones of its file
+                let arguments = &request["arguments"];
+                let requested = arguments["breakpoints"]
+                    .as_array()
+                    .map_or(&[][..], Vec::as_slice);
+                let lines = requested
+                    .iter()
+                    .filter_map(|breakpoint| breakpoint["line"].as_u64())
+                    .map(|line| line as usize);
+                match arguments["source"]["path"].as_str() {
+                    Some(path) => breakpoints.set_file(path, lines),
+                    None => breakpoints.set_lines(lines),
+                }
+
+                // One breakpoint for each requested one, in the same order
+                let verified = requested
+                    .iter()
+                    .map(|breakpoint| match breakpoint["line"].as_u64() {
+                        Some(line) => json!({ "verified": true, "line": line }),
+                        None => json!({ "verified": false, "message": "Expected a line." }),
+                    })
+                    .collect::<Vec<Value>>();
+                self.respond(request, json!({ "breakpoints": verified }));
+            }
+            "setExceptionBreakpoints" => self.respond(request, json!({})),
+            "disconnect" | "terminate" => {
+                self.disconnected = true;
+                self.respond(request, json!({}));
+            }
+            _ => return false,
+        }
+
+        true
+    }
+}
+
+/**
+ * Sends each line written by the program to the client as an `output` event.
+ */
+struct OutputEvents {
+    connection: Rc<RefCell<Connection>>,
+    /// The kind of output, `stdout` or `stderr`.
+    category: &'static str,
+}
+
+impl OutputSink for OutputEvents {
+    fn write_line(&mut self, line: &str) {
+        self.connection.borrow_mut().event(
+            "output",
+            json!({ "category": self.category, "output": format!("{line}\n") }),
+        );
+    }
+}
+
+/**
+ * The front end of the `Debugger` driven by the client: it reports each pause with a `stopped`
+ * event, then answers requests about the paused program until one of them resumes it.
+ */
+struct DapFrontend {
+    connection: Rc<RefCell<Connection>>,
+    /// The path of the program being debugged.
+    program: String,
+    /// Whether to report the pause on the first statement, or to run past it.
+    stop_on_entry: bool,
+}
+
+impl DapFrontend {
+    fn stack_trace(&self, pause: &Pause) -> Value {
+        let frames = pause
+            .frames
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(index, frame)| {
+                // A frame is in the program unless it runs the code of a module
+                let path = frame
+                    .file
+                    .as_deref()
+                    .map_or(self.program.clone(), |file| file.display().to_string());
+                json!({
+                    "id": index + 1,
+                    "name": frame.name,
+                    "line": frame.line,
+                    "column": 1,
+                    "source": { "path": path },
+                })
+            })
+            .collect::<Vec<Value>>();
+
+        json!({ "stackFrames": frames, "totalFrames": pause.frames.len() })
+    }
+}
+
+/**
+ * Returns the scopes of a frame as DAP `scopes`, keeping their variables in `references` so
+ * that they can be looked up by `variablesReference`.
+ */
+fn scopes(frame: &Frame, references: &mut Vec<Vec<(String, Object)>>) -> Value {
+    let scopes = frame.scopes();
+    let count = scopes.len();
+
+    let scopes = scopes
+        .into_iter()
+        .enumerate()
+        .map(|(depth, variables)| {
+            let name = match depth {
+                _ if depth + 1 == count => "Globals",
+                0 => "Locals",
+                _ => "Closure",
+            };
+            references.push(variables);
+            json!({
+                "name": name,
+                "variablesReference": references.len(),
+                "expensive": false,
+            })
+        })
+        .collect::<Vec<Value>>();
+
+    json!({ "scopes": scopes })
+}
+
+impl DebugFrontend for DapFrontend {
+    fn paused(&mut self, pause: &mut Pause) -> DebugCommand {
+        if pause.reason == PauseReason::Entry && !self.stop_on_entry {
+            return DebugCommand::Continue;
+        }
+
+        let reason = match pause.reason {
+            PauseReason::Entry => "entry",
+            PauseReason::Breakpoint => "breakpoint",
+            PauseReason::Step => "step",
+        };
+        let mut connection = self.connection.borrow_mut();
+        if connection.disconnected {
+            return DebugCommand::Stop;
+        }
+        connection.event(
+            "stopped",
+            json!({ "reason": reason, "threadId": THREAD_ID, "allThreadsStopped": true }),
+        );
+
+        // The variables of the scopes sent to the client, only valid during this pause
+        let mut references = Vec::new();
+
+        loop {
+            // Stop the program if the client went away
+            let Some(request) = connection.read_request() else {
+                return DebugCommand::Stop;
+            };
+            if connection.disconnected {
+                return DebugCommand::Stop;
+            }
+            if connection.handle_common(&request, pause.breakpoints) {
+                if connection.disconnected {
+                    return DebugCommand::Stop;
+                }
+                continue;
+            }
+
+            let arguments = &request["arguments"];
+            let command = match request["command"].as_str().unwrap_or_default() {
+                "continue" => DebugCommand::Continue,
+                "next" => DebugCommand::StepOver,
+                "stepIn" => DebugCommand::StepInto,
+                "stepOut" => DebugCommand::StepOut,
+                "stackTrace" => {
+                    connection.respond(&request, self.stack_trace(pause));
+                    continue;
+                }
+                "scopes" => {
+                    let frame = arguments["frameId"]
+                        .as_u64()
+                        .and_then(|id| pause.frames.get((id as usize).checked_sub(1)?));
+                    match frame {
+                        Some(frame) => connection.respond(&request, scopes(frame, &mut references)),
+                        None => connection.respond_error(&request, "Unknown stack frame."),
+                    }
+                    continue;
+                }
+                "variables" => {
+                    let variables = arguments["variablesReference"]
+                        .as_u64()
+                        .and_then(|id| references.get((id as usize).checked_sub(1)?));
+                    match variables {
+                        Some(variables) => {
+                            let variables = variables
+                                .iter()
+                                .map(|(name, value)| {
+                                    json!({
+                                        "name": name,
+                                        "value": value.to_string(),
+                                        "variablesReference": 0,
+                                    })
+                                })
+                                .collect::<Vec<Value>>();
+                            connection.respond(&request, json!({ "variables": variables }));
+                        }
+                        None => connection.respond_error(&request, "Unknown variables reference."),
+                    }
+                    continue;
+                }
+                _ => {
+                    connection.respond_error(&request, "Unsupported request while paused.");
+                    continue;
+                }
+            };
+
+            connection.respond(&request, json!({ "allThreadsContinued": true }));
+            return command;
+        }
+    }
+}
+
+/**
+ * A Debug Adapter Protocol server, letting editors (e.g. VS Code or Neovim) debug a script on
+ * the tree-walking interpreter.
+ *
+ * The session goes `initialize` -> `launch` (with the `program` path and an optional
+ * `stopOnEntry`) -> `setBreakpoints` -> `configurationDone`, which runs the program. While it is
+ * paused, the client can ask for the `stackTrace`, `scopes` and `variables`, and resume it with
+ * `continue`, `next`, `stepIn` or `stepOut`.
+ *
+ * Note: Lox programs have a single thread. Breakpoints are set by line, in the launched program
+ * or the modules it imports. The program is linted, its warnings are sent as output.
+ */
+pub struct DapServer {
+    connection: Rc<RefCell<Connection>>,
+}
+
+impl DapServer {
+    pub fn new(input: impl BufRead + 'static, output: impl Write + 'static) -> Self {
+        DapServer {
+            connection: Rc::new(RefCell::new(Connection {
+                input: Box::new(input),
+                output: Box::new(output),
+                seq: 0,
+                disconnected: false,
+            })),
+        }
+    }
+
+    /**
+     * Serves a debugging session until the client disconnects or closes the input.
+     */
+    pub fn run(&mut self) {
+        let mut program = None;
+        let mut stop_on_entry = false;
+        let mut breakpoints = Breakpoints::new();
+
+        loop {
+            let mut connection = self.connection.borrow_mut();
+            if connection.disconnected {
+                return;
+            }
+            let Some(request) = connection.read_request() else {
+                return;
+            };
+            if connection.handle_common(&request, &mut breakpoints) {
+                continue;
+            }
+
+            match request["command"].as_str().unwrap_or_default() {
+                "initialize" => {
+                    connection.respond(
+                        &request,
+                        json!({ "supportsConfigurationDoneRequest": true }),
+                    );
+                    connection.event("initialized", json!({}));
+                }
+                "launch" => {
+                    let arguments = &request["arguments"];
+                    program = arguments["program"].as_str().map(|path| path.to_string());
+                    stop_on_entry = arguments["stopOnEntry"].as_bool().unwrap_or(false);
+                    match program {
+                        Some(_) => connection.respond(&request, json!({})),
+                        None => connection.respond_error(&request, "Expected a program to launch."),
+                    }
+                }
+                "configurationDone" => {
+                    connection.respond(&request, json!({}));
+                    drop(connection);
+                    if let Some(program) = &program {
+                        self.launch(program, &breakpoints, stop_on_entry);
+                    }
+                }
+                _ => connection.respond_error(&request, "Unsupported request."),
+            }
+        }
+    }
+
+    /**
+     * Runs the program in the debugger, then tells the client it ended.
+     */
+    fn launch(&mut self, program: &str, breakpoints: &Breakpoints, stop_on_entry: bool) {
+        let output = |category| {
+            Box::new(OutputEvents {
+                connection: Rc::clone(&self.connection),
+                category,
+            })
+        };
+
+        let exit_code = match fs::read_to_string(program) {
+            Ok(source) => {
+                let mut debugger = Debugger::new(Box::new(DapFrontend {
+                    connection: Rc::clone(&self.connection),
+                    program: program.to_string(),
+                    stop_on_entry,
+                }));
+                debugger.set_breakpoints(breakpoints.clone());
+
+                // The protocol is read from the standard input, which `input()` can't take
+                let interpreter = Interpreter::builder()
+                    .natives(&NativeModule::DEBUGGED)
+                    .build();
+                let mut lox = Lox::with_interpreter(interpreter, Backend::TreeWalk);
+                lox.set_lint(true);
+                lox.interpreter().set_output(output("stdout"));
+                lox.interpreter().set_diagnostics(output("stderr"));
+                lox.interpreter().set_debugger(debugger);
+                lox.interpreter().set_script(program);
+                lox.set_source_file(program, &source);
+
+                let result = lox.eval_source(&source);
+                let warnings = lox.take_warnings();
+                let file = SourceFile::new(program, &source).with_sources(lox.sources());
+                for warning in warnings {
+                    output("stderr").write_line(&format!("{}\n", file.render(&warning)));
+                }
+
+                match result {
+                    Ok(_) | Err(EvalError::Runtime(LoxResult::Stop)) => 0,
+                    Err(e) => {
+                        output("stderr").write_line(&file.render(&e.into()));
+                        70
+                    }
+                }
+            }
+            Err(_) => {
+                output("stderr").write_line(&format!("Unable to read '{program}'."));
+                66
+            }
+        };
+
+        let mut connection = self.connection.borrow_mut();
+        connection.event("exited", json!({ "exitCode": exit_code }));
+        connection.event("terminated", json!({}));
+    }
+}
@@ -0,0 +1,423 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::environment::Environment;
+use crate::errors::LoxResult;
+use crate::expr::Expr;
+use crate::object::Object;
+use crate::stmt::Stmt;
+use crate::token::{SourceId, Token};
+
+/**
+ * Why the program paused.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PauseReason {
+    /// The program is about to run its first statement.
+    Entry,
+    /// The program reached a line with a breakpoint, or came back to it (e.g. in a loop).
+    Breakpoint,
+    /// The program finished the step it was asked to do.
+    Step,
+}
+
+/**
+ * How the program resumes after a pause.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugCommand {
+    /// Run until the next breakpoint.
+    Continue,
+    /// Run until the next line, entering the called functions.
+    StepInto,
+    /// Run until the next line of the current function (or of its caller once it returns).
+    StepOver,
+    /// Run until the current function returns to its caller.
+    StepOut,
+    /// Stop the program with a `LoxResult::Stop` error.
+    Stop,
+}
+
+/**
+ * A function being run, along with the file and line it is currently at.
+ */
+#[derive(Debug, Clone)]
+pub struct Frame {
+    /// The name of the function, `script` for the top level code.
+    pub name: String,
+    /// The file of the statement being run in this function, if known (e.g. not for a snippet).
+    pub file: Option<PathBuf>,
+    /// The line of the statement being run in this function.
+    pub line: usize,
+    /// The environment of the statement being run in this function, once it started.
+    pub environment: Option<Rc<RefCell<Environment>>>,
+    /// The source and offset of the statement being run in this function.
+    position: Option<(SourceId, usize)>,
+}
+
+impl Frame {
+    fn new(name: &str) -> Self {
+        Frame {
+            name: name.to_string(),
+            file: None,
+            line: 0,
+            environment: None,
+            position: None,
+        }
+    }
+
+    /**
+     * Returns the variables of each environment of the chain, from the innermost one to the
+     * global one.
+     */
+    pub fn scopes(&self) -> Vec<Vec<(String, Object)>> {
+        let mut scopes = Vec::new();
+        let mut env = self.environment.clone();
+        while let Some(current) = env {
+            scopes.push(current.borrow().variables());
+            env = current.borrow().enclosing.clone();
+        }
+
+        scopes
+    }
+}
+
+/**
+ * The user interface of the debugger (e.g. a command line), asked what to do each time the
+ * program pauses.
+ */
+pub trait DebugFrontend {
+    /**
+     * Called when the program pauses. The front end can inspect the state of the program and
+     * change the breakpoints, then tells how the program resumes.
+     */
+    fn paused(&mut self, pause: &mut Pause) -> DebugCommand;
+}
+
+/**
+ * The state of a paused program, as seen by a `DebugFrontend`.
+ */
+pub struct Pause<'a> {
+    /// Why the program paused.
+    pub reason: PauseReason,
+    /// The call stack, from the top level code to the function being run.
+    pub frames: &'a [Frame],
+    /// The lines the program pauses at. They can be changed before resuming.
+    pub breakpoints: &'a mut Breakpoints,
+}
+
+impl Pause<'_> {
+    /**
+     * Returns the line of the statement about to run.
+     */
+    pub fn line(&self) -> usize {
+        self.frames.last().map_or(0, |frame| frame.line)
+    }
+
+    /**
+     * Returns the variables of each environment visible from the statement about to run, from
+     * the innermost one to the global one.
+     */
+    pub fn scopes(&self) -> Vec<Vec<(String, Object)>> {
+        self.frames.last().map_or(Vec::new(), Frame::scopes)
+    }
+
+    /**
+     * Returns the value of the variable `name` visible from the statement about to run.
+     */
+    pub fn variable(&self, name: &str) -> Option<Object> {
+        self.scopes().into_iter().find_map(|scope| {
+            scope
+                .into_iter()
+                .rev()
+                .find(|(variable, _)| variable == name)
+                .map(|(_, value)| value)
+        })
+    }
+}
+
+/**
+ * The lines the program pauses at: the ones of the program being debugged, and the ones of
+ * other files by path (e.g. the modules it imports).
+ *
+ * Note: Paths are compared once canonicalized, so a file can be named relative to the current
+ * directory or not, and the program being debugged can be named by its path too.
+ */
+#[derive(Debug, Default, Clone)]
+pub struct Breakpoints {
+    /// The lines of the program being debugged.
+    main: BTreeSet<usize>,
+    /// The lines of each file, by canonical path.
+    files: BTreeMap<PathBuf, BTreeSet<usize>>,
+}
+
+impl Breakpoints {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Returns the lines of the program being debugged.
+     */
+    pub fn lines(&self) -> &BTreeSet<usize> {
+        &self.main
+    }
+
+    /**
+     * Adds a line of the program being debugged, and tells if it wasn't one already.
+     */
+    pub fn insert(&mut self, line: usize) -> bool {
+        self.main.insert(line)
+    }
+
+    /**
+     * Removes a line of the program being debugged, and tells if it was one.
+     */
+    pub fn remove(&mut self, line: usize) -> bool {
+        self.main.remove(&line)
+    }
+
+    /**
+     * Replaces the lines of the program being debugged.
+     */
+    pub fn set_lines(&mut self, lines: impl IntoIterator<Item = usize>) {
+        self.main = lines.into_iter().collect();
+    }
+
+    /**
+     * Replaces the lines of the file at `path`.
+     */
+    pub fn set_file(&mut self, path: &str, lines: impl IntoIterator<Item = usize>) {
+        self.files
+            .insert(canonical(Path::new(path)), lines.into_iter().collect());
+    }
+
+    /**
+     * Removes every breakpoint, of every file.
+     */
+    pub fn clear(&mut self) {
+        self.main.clear();
+        self.files.clear();
+    }
+
+    /**
+     * Tells if the program pauses at `line` of the given source, whose canonical path is `file`.
+     */
+    fn contains(&self, source: SourceId, file: Option<&Path>, line: usize) -> bool {
+        (source == SourceId::MAIN && self.main.contains(&line))
+            || file
+                .and_then(|file| self.files.get(file))
+                .is_some_and(|lines| lines.contains(&line))
+    }
+}
+
+/**
+ * Returns the canonical form of a path, or the path itself if it can't be found.
+ */
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/**
+ * Tells where the program pauses next, apart from the breakpoints.
+ */
+#[derive(Clone, Copy)]
+enum StepMode {
+    /// Only pause at breakpoints.
+    Run,
+    /// Pause at the next line, at any call depth.
+    Into(usize),
+    /// Pause at the next line at the given call depth or above.
+    Over(usize),
+    /// Pause as soon as the call depth is below the given one.
+    Out(usize),
+}
+
+/**
+ * A debugger attached to the `Interpreter`. It is told about each statement about to run and
+ * each function call, and pauses the program by handing it to its `DebugFrontend`.
+ *
+ * Note: The program pauses on its first statement, so that breakpoints can be set.
+ */
+pub struct Debugger {
+    /// The user interface of the debugger.
+    frontend: Box<dyn DebugFrontend>,
+    /// The lines the program pauses at.
+    breakpoints: Breakpoints,
+    /// The canonical path of each source the program ran, if known.
+    files: HashMap<SourceId, Option<PathBuf>>,
+    /// Where the program pauses next, apart from the breakpoints.
+    mode: StepMode,
+    /// The call stack of the program.
+    frames: Vec<Frame>,
+    /// Whether the program paused on its first statement already.
+    started: bool,
+}
+
+impl Debugger {
+    pub fn new(frontend: Box<dyn DebugFrontend>) -> Self {
+        Debugger {
+            frontend,
+            breakpoints: Breakpoints::new(),
+            files: HashMap::new(),
+            mode: StepMode::Run,
+            frames: vec![Frame::new("script")],
+            started: false,
+        }
+    }
+
+    /**
+     * Makes the program pause when it reaches the given line.
+     */
+    pub fn add_breakpoint(&mut self, line: usize) {
+        self.breakpoints.insert(line);
+    }
+
+    /**
+     * Replaces the lines the program pauses at.
+     */
+    pub fn set_breakpoints(&mut self, breakpoints: Breakpoints) {
+        self.breakpoints = breakpoints;
+    }
+
+    /**
+     * Called when entering the function `name`.
+     */
+    pub fn enter_function(&mut self, name: &str) {
+        self.frames.push(Frame::new(name));
+    }
+
+    /**
+     * Called when leaving the current function, whether it returned or failed.
+     */
+    pub fn exit_function(&mut self) {
+        self.frames.pop();
+    }
+
+    /**
+     * Called before running a statement in the given environment, read from `file` if known.
+     * Pauses the program if it reached a breakpoint or the end of a step.
+     *
+     * Note: Only statements containing a token can pause the program, e.g. not the blocks. The
+     * program pauses on the first statement it runs on a line, and again each time it comes
+     * back to the line through an earlier statement (e.g. on each pass of a one-line loop).
+     */
+    pub fn before_statement(
+        &mut self,
+        stmt: &Stmt,
+        environment: &Rc<RefCell<Environment>>,
+        file: Option<&Path>,
+    ) -> Result<(), LoxResult> {
+        let Some(token) = stmt_token(stmt) else {
+            return Ok(());
+        };
+        let (source, line, offset) = (token.src_id, token.src_line, token.src_start);
+        let file = self
+            .files
+            .entry(source)
+            .or_insert_with(|| file.map(canonical))
+            .clone();
+
+        let depth = self.frames.len();
+        let frame = self.frames.last_mut().unwrap();
+        let new_line = match frame.position {
+            Some((previous_source, previous_offset)) => {
+                previous_source != source || frame.line != line || offset <= previous_offset
+            }
+            None => true,
+        };
+        let on_breakpoint = new_line && self.breakpoints.contains(source, file.as_deref(), line);
+        frame.file = file;
+        frame.line = line;
+        frame.position = Some((source, offset));
+        frame.environment = Some(Rc::clone(environment));
+
+        let reason = if !self.started {
+            PauseReason::Entry
+        } else if on_breakpoint {
+            PauseReason::Breakpoint
+        } else {
+            let step_done = match self.mode {
+                StepMode::Run => false,
+                StepMode::Into(from) => new_line || depth != from,
+                StepMode::Over(from) => (new_line && depth <= from) || depth < from,
+                StepMode::Out(from) => depth < from,
+            };
+            if !step_done {
+                return Ok(());
+            }
+            PauseReason::Step
+        };
+        self.started = true;
+
+        // Hand the paused program to the front end
+        let mut pause = Pause {
+            reason,
+            frames: &self.frames,
+            breakpoints: &mut self.breakpoints,
+        };
+        self.mode = match self.frontend.paused(&mut pause) {
+            DebugCommand::Continue => StepMode::Run,
+            DebugCommand::StepInto => StepMode::Into(depth),
+            DebugCommand::StepOver => StepMode::Over(depth),
+            DebugCommand::StepOut => StepMode::Out(depth),
+            DebugCommand::Stop => return Err(LoxResult::Stop),
+        };
+
+        Ok(())
+    }
+}
+
+/**
+ * Returns the token a statement starts at, if it contains one.
+ */
+pub fn stmt_token(stmt: &Stmt) -> Option<&Token> {
+    match stmt {
+        Stmt::Block { .. } => None,
+        Stmt::Break { keyword }
+        | Stmt::Continue { keyword }
+        | Stmt::Import { keyword, .. }
+        | Stmt::Print { keyword, .. }
+        | Stmt::Return { keyword, .. }
+        | Stmt::Throw { keyword, .. }
+        | Stmt::Try { keyword, .. } => Some(keyword),
+        Stmt::Class { name, .. } | Stmt::Function { name, .. } | Stmt::Var { name, .. } => {
+            Some(name)
+        }
+        Stmt::Expression { expression } => expr_token(expression),
+        Stmt::If { condition, .. } | Stmt::While { condition, .. } => expr_token(condition),
+    }
+}
+
+/**
+ * Returns the token an expression starts at.
+ */
+pub fn expr_token(expr: &Expr) -> Option<&Token> {
+    match expr {
+        Expr::Assign { name, .. } | Expr::Variable { name, .. } => Some(name),
+        Expr::Binary { left, operator, .. } | Expr::Logical { left, operator, .. } => {
+            expr_token(left).or(Some(operator))
+        }
+        Expr::Call { callee, paren, .. } => expr_token(callee).or(Some(paren)),
+        Expr::Get { object, name } | Expr::Set { object, name, .. } => {
+            expr_token(object).or(Some(name))
+        }
+        Expr::Index {
+            object, bracket, ..
+        }
+        | Expr::SetIndex {
+            object, bracket, ..
+        } => expr_token(object).or(Some(bracket)),
+        Expr::Lambda { keyword, .. } | Expr::Super { keyword, .. } | Expr::This { keyword, .. } => {
+            Some(keyword)
+        }
+        Expr::List { bracket, .. } => Some(bracket),
+        Expr::Map { brace, .. } => Some(brace),
+        Expr::Unary { operator, .. } => Some(operator),
+        Expr::Grouping { expression } => expr_token(expression),
+        Expr::Literal { token, .. } => Some(token),
+    }
+}
@@ -0,0 +1,157 @@
+use std::io::{BufRead, Write};
+
+use crate::debugger::{DebugCommand, DebugFrontend, Pause, PauseReason};
+
+const HELP: &str = "Commands:
+  c, continue        Run until the next breakpoint
+  s, step            Run until the next line, entering the called functions
+  n, next            Run until the next line of the current function
+  o, out             Run until the current function returns
+  b, break [line]    Pause the program at the given line, or list the breakpoints
+  d, delete <line>   Remove the breakpoint at the given line
+  bt, backtrace      Show the call stack
+  p, print <name>    Show the value of a variable
+  v, vars            Show the variables of each scope, innermost first
+  q, quit            Stop the program
+  h, help            Show this help";
+
+/**
+ * A command line front end for the `Debugger`: it shows where the program paused and reads
+ * commands until one of them resumes the program.
+ */
+pub struct CliDebugger<R: BufRead, W: Write> {
+    /// The lines of the source code being debugged.
+    source: Vec<String>,
+    /// Where the commands are read from.
+    input: R,
+    /// Where the debugger writes to.
+    output: W,
+}
+
+impl<R: BufRead, W: Write> CliDebugger<R, W> {
+    pub fn new(source: &str, input: R, output: W) -> Self {
+        CliDebugger {
+            source: source.lines().map(|line| line.to_string()).collect(),
+            input,
+            output,
+        }
+    }
+
+    fn say(&mut self, text: &str) {
+        writeln!(self.output, "{text}").expect("Unable to write to the debugger output.");
+    }
+
+    /**
+     * Shows where the program paused, along with the source line about to run.
+     */
+    fn show_location(&mut self, pause: &Pause) {
+        let line = pause.line();
+        let function = pause.frames.last().map_or("script", |f| f.name.as_str());
+        let reason = match pause.reason {
+            PauseReason::Entry => "Paused on entry",
+            PauseReason::Breakpoint => "Paused on breakpoint",
+            PauseReason::Step => "Paused",
+        };
+        self.say(&format!("{reason} at line {line} in {function}"));
+
+        if let Some(source_line) = line.checked_sub(1).and_then(|i| self.source.get(i)) {
+            let source_line = format!("{line:>4} | {}", source_line.trim_end());
+            self.say(&source_line);
+        }
+    }
+
+    /**
+     * Runs a command that doesn't resume the program.
+     */
+    fn inspect(&mut self, pause: &mut Pause, command: &str, argument: Option<&str>) {
+        let line = argument.and_then(|line| line.parse::<usize>().ok());
+
+        match (command, argument) {
+            ("b" | "break", None) => {
+                let lines = pause
+                    .breakpoints
+                    .lines()
+                    .iter()
+                    .map(|line| line.to_string())
+                    .collect::<Vec<String>>();
+                match lines.is_empty() {
+                    true => self.say("No breakpoints."),
+                    false => self.say(&format!("Breakpoints at lines {}.", lines.join(", "))),
+                }
+            }
+            ("b" | "break", Some(_)) => match line {
+                Some(line) => {
+                    pause.breakpoints.insert(line);
+                    self.say(&format!("Breakpoint set at line {line}."));
+                }
+                None => self.say("Expected a line number."),
+            },
+            ("d" | "delete", _) => match line {
+                Some(line) if pause.breakpoints.remove(line) => {
+                    self.say(&format!("Breakpoint removed at line {line}."))
+                }
+                Some(line) => self.say(&format!("No breakpoint at line {line}.")),
+                None => self.say("Expected a line number."),
+            },
+            ("bt" | "backtrace", _) => {
+                for (i, frame) in pause.frames.iter().rev().enumerate() {
+                    self.say(&format!("#{i} {} at line {}", frame.name, frame.line));
+                }
+            }
+            ("p" | "print", Some(name)) => match pause.variable(name) {
+                Some(value) => self.say(&format!("{name} = {value}")),
+                None => self.say(&format!("No variable named '{name}'.")),
+            },
+            ("p" | "print", None) => self.say("Expected a variable name."),
+            ("v" | "vars", _) => {
+                for (depth, scope) in pause.scopes().iter().enumerate() {
+                    self.say(&format!("Scope {depth}:"));
+                    for (name, value) in scope {
+                        self.say(&format!("  {name} = {value}"));
+                    }
+                }
+            }
+            ("h" | "help", _) => self.say(HELP),
+            _ => self.say(&format!(
+                "Unknown command '{command}', type 'help' for help."
+            )),
+        }
+    }
+}
+
+impl<R: BufRead, W: Write> DebugFrontend for CliDebugger<R, W> {
+    /**
+     * Reads commands until one of them resumes the program.
+     *
+     * Note: The program runs to the end once the input is closed.
+     */
+    fn paused(&mut self, pause: &mut Pause) -> DebugCommand {
+        self.show_location(pause);
+
+        loop {
+            write!(self.output, "(debug) ").expect("Unable to write to the debugger output.");
+            self.output
+                .flush()
+                .expect("Unable to flush the debugger output.");
+
+            let mut line = String::new();
+            if self.input.read_line(&mut line).unwrap_or(0) == 0 {
+                pause.breakpoints.clear();
+                return DebugCommand::Continue;
+            }
+
+            let mut words = line.split_whitespace();
+            let Some(command) = words.next() else {
+                continue;
+            };
+            match command {
+                "c" | "continue" => return DebugCommand::Continue,
+                "s" | "step" => return DebugCommand::StepInto,
+                "n" | "next" => return DebugCommand::StepOver,
+                "o" | "out" => return DebugCommand::StepOut,
+                "q" | "quit" => return DebugCommand::Stop,
+                _ => self.inspect(pause, command, words.next()),
+            }
+        }
+    }
+}
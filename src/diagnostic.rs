@@ -0,0 +1,252 @@
+use crate::errors::{CallFrame, LoxResult};
+use crate::token::{SourceId, Span};
+
+/**
+ * The sources of a session besides the code it was given (`SourceId::MAIN`), e.g. its imported
+ * modules, so that their errors are rendered against their own file.
+ */
+#[derive(Debug, Default)]
+pub struct SourceMap {
+    /// The name and source code of each source, the one of `SourceId(n)` at `n - 1`.
+    sources: Vec<(String, String)>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Registers a named piece of source code and returns the id its tokens are scanned with.
+     */
+    pub fn add(&mut self, name: &str, source: &str) -> SourceId {
+        self.sources.push((name.to_string(), source.to_string()));
+        SourceId(self.sources.len() as u32)
+    }
+
+    /**
+     * Returns the source registered as `id`, if any.
+     */
+    pub fn get(&self, id: SourceId) -> Option<SourceFile<'_>> {
+        let index = (id.0 as usize).checked_sub(1)?;
+        self.sources
+            .get(index)
+            .map(|(name, source)| SourceFile::new(name, source))
+    }
+}
+
+/**
+ * A named piece of source code, used to render errors the way rustc does: the severity and
+ * code, the message, the file location, the offending source line with the exact token
+ * underlined and a hint on how to fix it, if any.
+ *
+ * ```text
+ * error[E0502]: Operand must be a number.
+ *  --> data/00_bad.lox:2:10
+ *   |
+ * 2 | 2 * (3 / -"muffin");
+ *   |          ^
+ * ```
+ */
+#[derive(Clone, Copy)]
+pub struct SourceFile<'a> {
+    /// The name displayed in the location line (e.g. the path of the file).
+    pub name: &'a str,
+    /// The source code the error spans refer to.
+    pub source: &'a str,
+    /// The other sources the spans may be in, e.g. the modules the source imports.
+    sources: Option<&'a SourceMap>,
+}
+
+impl<'a> SourceFile<'a> {
+    pub fn new(name: &'a str, source: &'a str) -> Self {
+        SourceFile {
+            name,
+            source,
+            sources: None,
+        }
+    }
+
+    /**
+     * Renders the spans that aren't in this file (`SourceId::MAIN`) against the source they are
+     * in, e.g. the errors of an imported module.
+     */
+    pub fn with_sources(mut self, sources: &'a SourceMap) -> Self {
+        self.sources = Some(sources);
+        self
+    }
+
+    /**
+     * Returns the file the spans of `source` are in, this one if it is unknown.
+     */
+    fn file(&self, source: SourceId) -> SourceFile<'a> {
+        match self.sources.and_then(|sources| sources.get(source)) {
+            Some(file) => SourceFile {
+                sources: self.sources,
+                ..file
+            },
+            None => *self,
+        }
+    }
+
+    /**
+     * Renders the given error. Errors without any location are rendered on a single line and
+     * `LoxResult::Multiple` renders each of its errors. Errors without a code are named after
+     * the step that reported them. A runtime error or a value thrown in a function is preceded
+     * by the traceback of the calls that led to it. The error of an imported module is rendered
+     * against the module, followed by the import that ran it.
+     */
+    pub fn render(&self, error: &LoxResult) -> String {
+        match error {
+            LoxResult::Multiple { errors } => {
+                return errors
+                    .iter()
+                    .map(|e| self.render(e))
+                    .collect::<Vec<String>>()
+                    .join("\n\n")
+            }
+            LoxResult::Import { token, error } => {
+                return format!(
+                    "{}\nnote: In the module imported here.\n{}",
+                    self.render(error),
+                    self.file(token.src_id).snippet(token.span())
+                )
+            }
+            _ => {}
+        }
+
+        let code = error.code().unwrap_or(error.phase());
+        let mut header = format!("{}[{code}]: {}", error.severity(), error.message());
+        let trace = match error {
+            LoxResult::Runtime { trace, .. } | LoxResult::Thrown { trace, .. } => trace.as_slice(),
+            _ => &[],
+        };
+        if let (false, Some(span)) = (trace.is_empty(), error.span()) {
+            header = format!("{}\n{header}", self.traceback(trace, span));
+        }
+        let mut rendered = match error.span() {
+            Some(span) => format!("{header}\n{}", self.file(span.source).snippet(span)),
+            None => header,
+        };
+
+        if let Some(help) = error.help() {
+            let gutter = match error.span() {
+                Some(span) => {
+                    let (line_number, ..) = self.file(span.source).locate(span.start);
+                    " ".repeat(line_number.to_string().len())
+                }
+                None => String::new(),
+            };
+            rendered.push_str(&format!("\n{gutter} = help: {help}"));
+        }
+
+        rendered
+    }
+
+    /**
+     * Renders the calls that led to an error raised at `span` the way Python does, innermost
+     * last: each call is in the function called by the previous one, the top-level code being
+     * named `script`. Past three times in a row, a repeated entry (e.g. of a recursion) is
+     * only counted.
+     *
+     * ```text
+     * Traceback (most recent call last):
+     *   File "data/area.lox", line 7, in script
+     *     print area(square);
+     *   File "data/area.lox", line 3, in area
+     *     return shape.side * shape.side;
+     * ```
+     */
+    fn traceback(&self, trace: &[CallFrame], span: Span) -> String {
+        let functions = std::iter::once("script").chain(trace.iter().map(|frame| &*frame.function));
+        let locations = trace.iter().map(|frame| frame.call.span()).chain([span]);
+
+        let mut traceback = String::from("Traceback (most recent call last):");
+        let mut previous = None;
+        let mut repeated = 0;
+        let repeated_line = |repeated: usize| {
+            format!(
+                "\n  [Previous line repeated {repeated} more time{}]",
+                if repeated == 1 { "" } else { "s" }
+            )
+        };
+        for (function, location) in functions.zip(locations) {
+            let file = self.file(location.source);
+            let (line_number, _, line) = file.locate(location.start);
+            if previous == Some((function, location.source, line_number)) {
+                repeated += 1;
+                if repeated > 2 {
+                    continue;
+                }
+            } else {
+                if repeated > 2 {
+                    traceback.push_str(&repeated_line(repeated - 2));
+                }
+                previous = Some((function, location.source, line_number));
+                repeated = 0;
+            }
+
+            traceback.push_str(&format!(
+                "\n  File \"{}\", line {line_number}, in {function}\n    {}",
+                file.name,
+                line.trim()
+            ));
+        }
+        if repeated > 2 {
+            traceback.push_str(&repeated_line(repeated - 2));
+        }
+
+        traceback
+    }
+
+    /**
+     * Renders the location of the span followed by its source line, underlined.
+     */
+    fn snippet(&self, span: Span) -> String {
+        let (line_number, column, line) = self.locate(span.start);
+
+        // Underline at least one character and never go past the end of the line
+        let line_len = line.chars().count();
+        let underline_len = span
+            .end
+            .saturating_sub(span.start)
+            .min(line_len.saturating_sub(column))
+            .max(1);
+
+        let line_number = line_number.to_string();
+        let gutter = " ".repeat(line_number.len());
+
+        format!(
+            "{gutter}--> {}:{}:{}\n{gutter} |\n{line_number} | {line}\n{gutter} | {}{}",
+            self.name,
+            line_number,
+            column + 1,
+            " ".repeat(column),
+            "^".repeat(underline_len)
+        )
+    }
+
+    /**
+     * Returns the line number (from 1), the column (from 0) and the text of the line containing
+     * the character at `offset`.
+     *
+     * Note: The line is computed from the offset rather than taken from the span, because the
+     * line of a token spanning several lines (e.g. a string) is the one it ends on.
+     */
+    fn locate(&self, offset: usize) -> (usize, usize, &'a str) {
+        let mut line_start = 0;
+        for (index, line) in self.source.split('\n').enumerate() {
+            let line_len = line.chars().count();
+            if offset <= line_start + line_len {
+                return (index + 1, offset - line_start, line.trim_end_matches('\r'));
+            }
+            // Skip the line and its '\n'
+            line_start += line_len + 1;
+        }
+
+        // The offset is past the end of the source (e.g. the `Eof` token)
+        let last_line = self.source.split('\n').count();
+        let line = self.source.rsplit('\n').next().unwrap_or("");
+        (last_line, line.chars().count(), line)
+    }
+}
@@ -1,5 +1,4 @@
 use std::cell::RefCell;
-use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use std::fmt;
 use std::rc::Rc;
@@ -8,10 +7,23 @@ use crate::errors::{EnvironmentErrorType, LoxResult};
 use crate::object::Object;
 use crate::token::Token;
 
+/**
+ * The storage of the variables of a scope.
+ *
+ * Note: The global environment stores its variables by name, as they are looked up dynamically.
+ * The local environments store them in slots, in order of declaration: the `Resolver` tells the
+ * slot of each local variable so that it is accessed by index.
+ */
 #[derive(Debug)]
 pub struct Environment {
     pub enclosing: Option<Rc<RefCell<Environment>>>,
-    values: HashMap<String, Object>,
+    /// The variables of the global environment.
+    globals: HashMap<String, Object>,
+    /// The variables of a local environment, indexed by slot.
+    values: Vec<Object>,
+    /// The names of the variables of a local environment, indexed by slot. They are only used to
+    /// display the environment.
+    names: Vec<String>,
 }
 
 impl Default for Environment {
@@ -22,16 +34,15 @@ impl Default for Environment {
 
 impl fmt::Display for Environment {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for (k, v) in &self.values {
+        for (k, v) in &self.globals {
+            writeln!(f, "{} = {}", k, v)?
+        }
+        for (k, v) in self.names.iter().zip(&self.values) {
             writeln!(f, "{} = {}", k, v)?
         }
 
-        if self.enclosing.is_some() {
-            writeln!(
-                f,
-                "Enclosing: {}",
-                self.enclosing.as_ref().unwrap().borrow()
-            )?
+        if let Some(enclosing) = &self.enclosing {
+            writeln!(f, "Enclosing: {}", enclosing.borrow())?
         } else {
             writeln!(f, "Enclosing: false.")?
         }
@@ -45,69 +56,114 @@ impl Environment {
     pub fn new() -> Self {
         Environment {
             enclosing: None,
-            values: HashMap::new(),
+            globals: HashMap::new(),
+            values: Vec::new(),
+            names: Vec::new(),
         }
     }
 
     pub fn from_enclosing(env: Rc<RefCell<Environment>>) -> Self {
         Environment {
             enclosing: Some(env),
-            values: HashMap::new(),
+            globals: HashMap::new(),
+            values: Vec::new(),
+            names: Vec::new(),
         }
     }
 
     /**
-     * Inserts a key-value pair in the global HashMap storage.
+     * Returns the global environment `env` is enclosed in (e.g. the one of the module a function
+     * was defined in).
      */
-    pub fn define(&mut self, name: String, obj: Object) {
-        self.values.insert(name, obj);
+    pub fn root(env: &Rc<RefCell<Environment>>) -> Rc<RefCell<Environment>> {
+        let mut env = Rc::clone(env);
+        loop {
+            let enclosing = env.borrow().enclosing.clone();
+            match enclosing {
+                Some(enclosing) => env = enclosing,
+                None => return env,
+            }
+        }
     }
 
     /**
-     * Gets a value using its key name from the storage HashMap.
-     *
-     * Note: Throws an error if the key does not exist.
+     * Defines a new variable. A global variable is stored under its name, a local one in the
+     * next slot.
      */
-    pub fn get(&self, token: &Token) -> Result<Object, LoxResult> {
-        // Check if the variable exists locally
-        if let Some(v) = self.values.get(&token.lexeme) {
-            return Ok(v.clone());
+    pub fn define(&mut self, name: &str, obj: Object) {
+        if self.enclosing.is_none() {
+            self.globals.insert(name.to_string(), obj);
+        } else {
+            self.values.push(obj);
+            self.names.push(name.to_string());
         }
+    }
+
+    /**
+     * Returns the values of the variables stored in this environment (not the enclosing ones).
+     */
+    pub fn objects(&self) -> impl Iterator<Item = &Object> {
+        self.globals.values().chain(self.values.iter())
+    }
 
-        // If we have an enclosing environment, check inside too (recursive)
+    /**
+     * Returns the variables stored in this environment (not the enclosing ones) along with their
+     * names: in order of declaration for a local environment, by name for the global one.
+     */
+    pub fn variables(&self) -> Vec<(String, Object)> {
+        let mut globals = self
+            .globals
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect::<Vec<(String, Object)>>();
+        globals.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let locals = self.names.iter().cloned().zip(self.values.iter().cloned());
+        globals.into_iter().chain(locals).collect()
+    }
+
+    /**
+     * Gets the value of a global variable using its name.
+     *
+     * Note: Throws an error if the variable does not exist.
+     */
+    pub fn get(&self, token: &Token) -> Result<Object, LoxResult> {
+        // Global variables are stored in the outermost environment
         if let Some(enclosing) = &self.enclosing {
             return enclosing.borrow().get(token);
         }
 
+        // Check if the variable exists
+        if let Some(v) = self.globals.get(&token.lexeme) {
+            return Ok(v.clone());
+        }
+
         // Else, throw an error
         Err(LoxResult::Environment {
+            token: token.clone(),
             error_type: EnvironmentErrorType::UnknownVariable,
-            msg: format!(
-                "{} -> No such variable '{}'.",
-                token.location(),
-                token.lexeme
-            ),
+            msg: format!("No such variable '{}'.", token.lexeme),
         })
     }
 
     /**
-     * Gets a variable from the environment that is at depth `distance`.
+     * Gets the local variable stored in `slot` of the environment that is at depth `distance`.
+     *
+     * Note: Throws an error if the variable is not defined yet.
      */
-    pub fn get_at(&self, distance: usize, name: &Token) -> Result<Object, LoxResult> {
-        // If given a distance, get the corresponding ancestor and try to get the value from it
-        if distance > 0 {
-            self.ancestor(distance).borrow().get(name)
-        }
-        // If no distance, only try to get the value from self.values or return an error
-        else {
-            match self.values.get(&name.lexeme) {
-                Some(val) => Ok(val.clone()),
-                None => Err(LoxResult::Environment {
-                    error_type: EnvironmentErrorType::UnknownVariable,
-                    msg: format!("{} -> No such variable '{}'.", name.location(), name.lexeme),
-                }),
-            }
-        }
+    pub fn get_at(&self, distance: usize, slot: usize, name: &Token) -> Result<Object, LoxResult> {
+        // If given a distance, get the corresponding ancestor and get the value from it
+        let value = if distance > 0 {
+            self.ancestor(distance).borrow().values.get(slot).cloned()
+        } else {
+            self.values.get(slot).cloned()
+        };
+
+        value.ok_or_else(|| LoxResult::Environment {
+            token: name.clone(),
+            error_type: EnvironmentErrorType::UnknownVariable,
+            msg: format!("No such variable '{}'.", name.lexeme),
+        })
     }
 
     /**
@@ -134,35 +190,59 @@ impl Environment {
         env
     }
 
+    /**
+     * Assigns a value to an existing global variable using its name.
+     */
     pub fn assign(&mut self, token: &Token, value: Object) -> Result<(), LoxResult> {
-        // Try inserting in the local variables
-        if let Entry::Occupied(mut e) = self.values.entry(token.lexeme.clone()) {
-            e.insert(value);
-            return Ok(());
-        }
-
-        // If we have an enclosing, check if we can insert into it
+        // Global variables are stored in the outermost environment
         if let Some(enclosing) = &self.enclosing {
             return enclosing.borrow_mut().assign(token, value);
         }
 
+        // Try inserting in the global variables
+        if let Some(v) = self.globals.get_mut(&token.lexeme) {
+            *v = value;
+            return Ok(());
+        }
+
         // Otherwise, throw an error because the variable we tried to assign does not exist
         Err(LoxResult::Environment {
+            token: token.clone(),
             error_type: EnvironmentErrorType::UnknownVariable,
             msg: format!(
-                "{} -> Cannot assign value to unknown variable '{}'.",
-                token.location(),
+                "Cannot assign value to unknown variable '{}'.",
                 token.lexeme
             ),
         })
     }
 
+    /**
+     * Assigns a value to the local variable stored in `slot` of the environment that is at
+     * depth `distance`.
+     */
     pub fn assign_at(
         &mut self,
         distance: usize,
+        slot: usize,
         name: &Token,
         value: Object,
     ) -> Result<(), LoxResult> {
-        self.ancestor(distance).borrow_mut().assign(name, value)
+        let assign = |values: &mut Vec<Object>| match values.get_mut(slot) {
+            Some(v) => {
+                *v = value;
+                Ok(())
+            }
+            None => Err(LoxResult::Environment {
+                token: name.clone(),
+                error_type: EnvironmentErrorType::UnknownVariable,
+                msg: format!("Cannot assign value to unknown variable '{}'.", name.lexeme),
+            }),
+        };
+
+        if distance > 0 {
+            assign(&mut self.ancestor(distance).borrow_mut().values)
+        } else {
+            assign(&mut self.values)
+        }
     }
 }
@@ -1,7 +1,30 @@
 use std::fmt;
 
+use crate::lint::Lint;
 use crate::object::Object;
-use crate::token::Token;
+use crate::token::{Span, Token};
+
+/**
+ * How serious a diagnostic is. Only errors stop the program, unless warnings are denied.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// A remark about code that may be intended, e.g. a shadowed variable.
+    Note,
+    /// Code that is valid but most likely wrong.
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Note => write!(f, "note"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
 
 #[derive(Debug, PartialEq)]
 pub enum RuntimeErrorType {
@@ -14,6 +37,20 @@ pub enum RuntimeErrorType {
     InvalidObjectProperty,
     UndefinedProperty,
     SuperclassNotClass,
+    InvalidIndexedObject,
+    InvalidIndex,
+    IndexOutOfBounds,
+    InvalidArgumentType,
+    PopFromEmptyList,
+    UnhashableKey,
+    UndefinedKey,
+    ModuleNotFound,
+    CircularImport,
+    StackOverflow,
+    NativeIoError,
+    ReadOnlyModule,
+    ImportNotAllowed,
+    NanKey,
 }
 
 #[derive(Debug, PartialEq)]
@@ -28,6 +65,7 @@ pub enum ParserErrorType {
     InvalidConsumeType,
     InvalidAssignTarget,
     MaxArgNumber,
+    InvalidModuleName,
 }
 
 #[derive(Debug, PartialEq)]
@@ -38,6 +76,18 @@ pub enum ResolverErrorType {
     ThisOutsideClass,
     ReturnFromInit,
     ClassInheritItself,
+    BreakOutsideLoop,
+    ContinueOutsideLoop,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CompilerErrorType {
+    TooManyConstants,
+    TooManyLocals,
+    TooManyUpvalues,
+    JumpTooLarge,
+    UnsupportedImport,
+    UnsupportedExceptions,
 }
 
 #[derive(Debug, PartialEq)]
@@ -45,6 +95,27 @@ pub enum EnvironmentErrorType {
     UnknownVariable,
 }
 
+/**
+ * Why a program was stopped before its end by its `Budget`.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Interruption {
+    StatementLimit,
+    Timeout,
+    Cancelled,
+}
+
+/**
+ * A call of a Lox function, kept to trace back how a runtime error was reached.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallFrame {
+    /// The name of the called function.
+    pub function: String,
+    /// The closing parenthesis of the call.
+    pub call: Token,
+}
+
 #[derive(Debug, PartialEq)]
 pub enum LoxResult {
     IOError,
@@ -56,161 +127,539 @@ pub enum LoxResult {
     Runtime {
         token: Token,
         error_type: RuntimeErrorType,
+        /// The calls being run when the error was raised, outermost first. Empty if it was
+        /// raised by the top-level code.
+        trace: Vec<CallFrame>,
     },
     Scanner {
         c: char,
+        span: Span,
         error_type: ScannerErrorType,
     },
     Environment {
+        token: Token,
         error_type: EnvironmentErrorType,
         msg: String,
     },
     ReturnValue {
         value: Object,
     },
+    /// Raised by a `break` statement, caught by the enclosing loop.
+    Break,
+    /// Raised by a `continue` statement, caught by the enclosing loop.
+    Continue,
+    /// Raised when the debugger stops the program, it is never caught.
+    Stop,
     Resolver {
         token: Token,
         error_type: ResolverErrorType,
     },
+    /// Raised when a program doesn't fit in the limits of the bytecode.
+    Compiler {
+        token: Token,
+        error_type: CompilerErrorType,
+    },
+    /// Several errors reported at once, e.g. every syntax error of a source file.
+    Multiple {
+        errors: Vec<LoxResult>,
+    },
+    /// A finding that doesn't stop the program, e.g. a lint.
+    Warning {
+        lint: Lint,
+    },
+    /// An error raised while importing a module, reported at the import.
+    Import {
+        /// The path of the module in the import statement.
+        token: Token,
+        error: Box<LoxResult>,
+    },
+    /// The program ran out of its execution budget. It can't be caught.
+    Interrupted {
+        interruption: Interruption,
+    },
+    /// A value thrown by a `throw` statement, until a `try` statement catches it.
+    Thrown {
+        /// The location of the 'throw' keyword.
+        span: Span,
+        value: Object,
+        /// The calls being run when the value was thrown, outermost first.
+        trace: Vec<CallFrame>,
+    },
 }
 
-impl fmt::Display for LoxResult {
+/**
+ * Error returned when evaluating source code through a `Lox` session. It tells which step of
+ * the pipeline failed.
+ */
+#[derive(Debug, PartialEq)]
+pub enum EvalError {
+    /// The source code could not be read.
+    Io(LoxResult),
+    /// The source code could not be scanned into tokens.
+    Scanner(LoxResult),
+    /// Every syntax error found while parsing.
+    Parser(Vec<LoxResult>),
+    /// The variables of the program could not be resolved.
+    Resolver(LoxResult),
+    /// The program could not be compiled to bytecode.
+    Compiler(LoxResult),
+    /// An error occured while running the program.
+    Runtime(LoxResult),
+    /// The warnings of the program, which was not run because warnings are denied.
+    Warnings(Vec<LoxResult>),
+}
+
+impl From<EvalError> for LoxResult {
+    fn from(error: EvalError) -> Self {
+        match error {
+            EvalError::Io(e) => e,
+            EvalError::Scanner(e) => e,
+            EvalError::Parser(errors) => LoxResult::Multiple { errors },
+            EvalError::Resolver(e) => e,
+            EvalError::Compiler(e) => e,
+            EvalError::Runtime(e) => e,
+            EvalError::Warnings(errors) => LoxResult::Multiple { errors },
+        }
+    }
+}
+
+impl fmt::Display for EvalError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EvalError::Io(e) => write!(f, "{e}"),
+            EvalError::Scanner(e) => write!(f, "{e}"),
+            EvalError::Parser(errors) | EvalError::Warnings(errors) => {
+                for (i, error) in errors.iter().enumerate() {
+                    if i > 0 {
+                        writeln!(f)?;
+                    }
+                    write!(f, "{error}")?;
+                }
+                Ok(())
+            }
+            EvalError::Resolver(e) => write!(f, "{e}"),
+            EvalError::Compiler(e) => write!(f, "{e}"),
+            EvalError::Runtime(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl LoxResult {
+    /**
+     * Returns the name of the step that reported this error.
+     */
+    pub fn phase(&self) -> &'static str {
+        match self {
+            LoxResult::IOError => "IOError",
+            LoxResult::Scanner { .. } => "scanner",
+            LoxResult::Parser { .. } => "parser",
+            LoxResult::Resolver { .. } => "resolver",
+            LoxResult::Compiler { .. } => "compiler",
+            LoxResult::Runtime { .. } => "runtime",
+            LoxResult::Environment { .. } => "env",
+            LoxResult::ReturnValue { .. } => "return",
+            LoxResult::Break => "break",
+            LoxResult::Continue => "continue",
+            LoxResult::Stop => "stop",
+            LoxResult::Multiple { .. } => "multiple",
+            LoxResult::Warning { .. } => "lint",
+            LoxResult::Import { .. } => "import",
+            LoxResult::Thrown { .. } => "runtime",
+            LoxResult::Interrupted { .. } => "runtime",
+        }
+    }
+
+    pub fn severity(&self) -> Severity {
+        match self {
+            LoxResult::Warning { lint } => lint.code.severity(),
+            _ => Severity::Error,
+        }
+    }
+
+    /**
+     * Returns the stable code of this diagnostic, e.g. `E0102`. The first two digits tell the
+     * step that reported it: `00` reading, `01` scanning, `02` parsing, `03` resolving, `04`
+     * compiling, `05` running and `06` looking up variables. Lints have `W` codes.
+     *
+     * Note: Codes are never reused, new ones are added at the end of their step.
+     */
+    pub fn code(&self) -> Option<&'static str> {
+        let code = match self {
+            LoxResult::IOError => "E0001",
+
+            LoxResult::Scanner { error_type, .. } => match error_type {
+                ScannerErrorType::InvalidCharacter => "E0101",
+                ScannerErrorType::UnterminatedString => "E0102",
+            },
+
+            LoxResult::Parser { error_type, .. } => match error_type {
+                ParserErrorType::ExpectedExpression => "E0201",
+                ParserErrorType::InvalidConsumeType => "E0202",
+                ParserErrorType::InvalidAssignTarget => "E0203",
+                ParserErrorType::MaxArgNumber => "E0204",
+                ParserErrorType::InvalidModuleName => "E0205",
+            },
+
+            LoxResult::Resolver { error_type, .. } => match error_type {
+                ResolverErrorType::VariableNotInitialized => "E0301",
+                ResolverErrorType::VariableAlreadyExists => "E0302",
+                ResolverErrorType::TopLevelReturn => "E0303",
+                ResolverErrorType::ThisOutsideClass => "E0304",
+                ResolverErrorType::ReturnFromInit => "E0305",
+                ResolverErrorType::ClassInheritItself => "E0306",
+                ResolverErrorType::BreakOutsideLoop => "E0307",
+                ResolverErrorType::ContinueOutsideLoop => "E0308",
+            },
+
+            LoxResult::Compiler { error_type, .. } => match error_type {
+                CompilerErrorType::TooManyConstants => "E0401",
+                CompilerErrorType::TooManyLocals => "E0402",
+                CompilerErrorType::TooManyUpvalues => "E0403",
+                CompilerErrorType::JumpTooLarge => "E0404",
+                CompilerErrorType::UnsupportedImport => "E0405",
+                CompilerErrorType::UnsupportedExceptions => "E0406",
+            },
+
+            LoxResult::Runtime { error_type, .. } => match error_type {
+                RuntimeErrorType::UnreachableCode => "E0501",
+                RuntimeErrorType::ExpectedNumberOperand => "E0502",
+                RuntimeErrorType::ExpectedNumberOperands => "E0503",
+                RuntimeErrorType::ExpectedAddableOperands => "E0504",
+                RuntimeErrorType::InvalidCallObjectType => "E0505",
+                RuntimeErrorType::InvalidArgsCount => "E0506",
+                RuntimeErrorType::InvalidObjectProperty => "E0507",
+                RuntimeErrorType::UndefinedProperty => "E0508",
+                RuntimeErrorType::SuperclassNotClass => "E0509",
+                RuntimeErrorType::InvalidIndexedObject => "E0510",
+                RuntimeErrorType::InvalidIndex => "E0511",
+                RuntimeErrorType::IndexOutOfBounds => "E0512",
+                RuntimeErrorType::InvalidArgumentType => "E0513",
+                RuntimeErrorType::PopFromEmptyList => "E0514",
+                RuntimeErrorType::UnhashableKey => "E0515",
+                RuntimeErrorType::UndefinedKey => "E0516",
+                RuntimeErrorType::ModuleNotFound => "E0517",
+                RuntimeErrorType::CircularImport => "E0518",
+                RuntimeErrorType::StackOverflow => "E0520",
+                RuntimeErrorType::NativeIoError => "E0524",
+                RuntimeErrorType::ReadOnlyModule => "E0525",
+                RuntimeErrorType::ImportNotAllowed => "E0526",
+                RuntimeErrorType::NanKey => "E0528",
+            },
+
+            LoxResult::Environment { error_type, .. } => match error_type {
+                EnvironmentErrorType::UnknownVariable => "E0601",
+            },
+
+            LoxResult::Warning { lint } => lint.code.code(),
+            LoxResult::Import { error, .. } => return error.code(),
+            LoxResult::Thrown { .. } => "E0519",
+            LoxResult::Interrupted { interruption } => match interruption {
+                Interruption::StatementLimit => "E0521",
+                Interruption::Timeout => "E0522",
+                Interruption::Cancelled => "E0523",
+            },
+
+            LoxResult::ReturnValue { .. }
+            | LoxResult::Break
+            | LoxResult::Continue
+            | LoxResult::Stop
+            | LoxResult::Multiple { .. } => return None,
+        };
+
+        Some(code)
+    }
+
+    /**
+     * Returns a hint on how to fix the code, if there is one.
+     */
+    pub fn help(&self) -> Option<&'static str> {
+        match self {
+            LoxResult::Parser {
+                error_type: ParserErrorType::MaxArgNumber,
+                ..
+            } => Some("Pass the values in a list instead."),
+            LoxResult::Parser {
+                error_type: ParserErrorType::InvalidModuleName,
+                ..
+            } => Some("Name the module with 'import name from \"path\";'."),
+            LoxResult::Resolver { error_type, .. } => match error_type {
+                ResolverErrorType::VariableNotInitialized => {
+                    Some("Give the local variable another name to read the outer one.")
+                }
+                ResolverErrorType::VariableAlreadyExists => {
+                    Some("Assign the variable instead of declaring it again.")
+                }
+                ResolverErrorType::TopLevelReturn => Some("Only functions can return."),
+                ResolverErrorType::ReturnFromInit => {
+                    Some("Use 'return;' alone, init() always returns the instance.")
+                }
+                ResolverErrorType::BreakOutsideLoop | ResolverErrorType::ContinueOutsideLoop => {
+                    Some("Use it in the body of a 'while' or 'for' loop.")
+                }
+                _ => None,
+            },
+            LoxResult::Runtime {
+                error_type: RuntimeErrorType::StackOverflow,
+                ..
+            } => Some("Make sure the recursion reaches its base case."),
+            LoxResult::Runtime {
+                error_type: RuntimeErrorType::ReadOnlyModule,
+                ..
+            } => Some("Change it from a function of the module instead."),
+            LoxResult::Runtime {
+                error_type: RuntimeErrorType::ImportNotAllowed,
+                ..
+            } => Some("Build the interpreter with the 'fs' native module."),
+            LoxResult::Environment {
+                error_type: EnvironmentErrorType::UnknownVariable,
+                ..
+            } => Some("Declare the variable with 'var' before using it."),
+            LoxResult::Warning { lint } => lint.code.help(),
+            LoxResult::Thrown { .. } => Some("Catch it with 'try { ... } catch (error) { ... }'."),
+            _ => None,
+        }
+    }
+
+    /**
+     * Returns the location in the source code this error points at, if any.
+     */
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            LoxResult::Scanner { span, .. } => Some(*span),
+            LoxResult::Parser { token, .. }
+            | LoxResult::Resolver { token, .. }
+            | LoxResult::Compiler { token, .. }
+            | LoxResult::Runtime { token, .. }
+            | LoxResult::Environment { token, .. } => Some(token.span()),
+            LoxResult::IOError
+            | LoxResult::ReturnValue { .. }
+            | LoxResult::Break
+            | LoxResult::Continue
+            | LoxResult::Stop
+            | LoxResult::Interrupted { .. }
+            | LoxResult::Multiple { .. } => None,
+            LoxResult::Warning { lint } => Some(lint.span),
+            LoxResult::Import { token, .. } => Some(token.span()),
+            LoxResult::Thrown { span, .. } => Some(*span),
+        }
+    }
+
+    /**
+     * Returns the description of this error, without its location.
+     */
+    pub fn message(&self) -> String {
         match self {
             // IO reading error
-            LoxResult::IOError => write!(f, "[IOError] There was an IO error.")?,
+            LoxResult::IOError => "There was an IO error.".to_string(),
 
             // Scanner error
-            LoxResult::Scanner { c, error_type } => match error_type {
-                ScannerErrorType::InvalidCharacter => {
-                    write!(f, "[scanner] Invalid character {c}.")?
-                }
+            LoxResult::Scanner { c, error_type, .. } => match error_type {
+                ScannerErrorType::InvalidCharacter => format!("Invalid character {c}."),
                 ScannerErrorType::UnterminatedString => {
-                    write!(f, "[scanner] Encountered an unterminated string.")?
+                    "Encountered an unterminated string.".to_string()
                 }
             },
 
             // Parser error
             LoxResult::Parser {
-                token,
-                error_type,
-                msg,
+                error_type, msg, ..
             } => match error_type {
-                ParserErrorType::InvalidConsumeType => {
-                    write!(f, "[parser] {} -> {msg}", token.location())?
-                }
-                ParserErrorType::ExpectedExpression => {
-                    write!(f, "[parser] {} -> {msg}", token.location())?
+                ParserErrorType::InvalidConsumeType => msg.clone(),
+                ParserErrorType::ExpectedExpression => msg.clone(),
+                ParserErrorType::InvalidAssignTarget => "Invalid assignment target.".to_string(),
+                ParserErrorType::MaxArgNumber => "Cannot have more than 255 arguments.".to_string(),
+                ParserErrorType::InvalidModuleName => {
+                    "The name of the module file isn't a valid identifier.".to_string()
                 }
-                ParserErrorType::InvalidAssignTarget => write!(
-                    f,
-                    "[parser] {} -> Invalid assignment target.",
-                    token.location()
-                )?,
-                ParserErrorType::MaxArgNumber => write!(
-                    f,
-                    "[parser] {} -> Cannot have more than 255 arguments.",
-                    token.location()
-                )?,
             },
 
             // Runtime error
-            LoxResult::Runtime { token, error_type } => match error_type {
-                RuntimeErrorType::UnreachableCode => {
-                    writeln!(
-                        f,
-                        "[runtime] {} -> This code is unreachable.",
-                        token.location()
-                    )?;
-                }
-                RuntimeErrorType::ExpectedNumberOperand => write!(
-                    f,
-                    "[runtime] {} -> Operand must be a number.",
-                    token.location()
-                )?,
-                RuntimeErrorType::ExpectedNumberOperands => write!(
-                    f,
-                    "[runtime] {} -> Both operands must be a number.",
-                    token.location()
-                )?,
-                RuntimeErrorType::InvalidCallObjectType => write!(
-                    f,
-                    "[runtime] {} -> Can only call functions and classes.",
-                    token.location()
-                )?,
-                RuntimeErrorType::ExpectedAddableOperands => write!(
-                    f,
-                    "[runtime] {} -> Operands must be two numbers or two strings.",
-                    token.location()
-                )?,
-                RuntimeErrorType::InvalidArgsCount => write!(
-                    f,
-                    "[runtime] {} -> Invalid argument count for {} or class.",
-                    token.location(),
-                    token.lexeme
-                )?,
-                RuntimeErrorType::InvalidObjectProperty => write!(
-                    f,
-                    "[runtime] {} -> Only classes have properties.",
-                    token.location()
-                )?,
-                RuntimeErrorType::UndefinedProperty => write!(
-                    f,
-                    "[runtime] {} -> Undefined property {} for this class.",
-                    token.location(),
-                    token.lexeme
-                )?,
-                RuntimeErrorType::SuperclassNotClass => write!(
-                    f,
-                    "[runtime] {} -> Superclass must be a class.",
-                    token.location()
-                )?,
+            LoxResult::Runtime {
+                token, error_type, ..
+            } => match error_type {
+                RuntimeErrorType::UnreachableCode => "This code is unreachable.".to_string(),
+                RuntimeErrorType::ExpectedNumberOperand => "Operand must be a number.".to_string(),
+                RuntimeErrorType::ExpectedNumberOperands => {
+                    "Both operands must be a number.".to_string()
+                }
+                RuntimeErrorType::InvalidCallObjectType => {
+                    "Can only call functions and classes.".to_string()
+                }
+                RuntimeErrorType::ExpectedAddableOperands => {
+                    "Operands must be two numbers or two strings.".to_string()
+                }
+                RuntimeErrorType::InvalidArgsCount => {
+                    format!("Invalid argument count for {} or class.", token.lexeme)
+                }
+                RuntimeErrorType::InvalidObjectProperty => {
+                    "Only classes have properties.".to_string()
+                }
+                RuntimeErrorType::UndefinedProperty => {
+                    format!("Undefined property {} for this class.", token.lexeme)
+                }
+                RuntimeErrorType::SuperclassNotClass => "Superclass must be a class.".to_string(),
+                RuntimeErrorType::InvalidIndexedObject => {
+                    "Only lists and maps can be indexed.".to_string()
+                }
+                RuntimeErrorType::InvalidIndex => {
+                    "List index must be a non-negative integer.".to_string()
+                }
+                RuntimeErrorType::IndexOutOfBounds => "List index out of bounds.".to_string(),
+                RuntimeErrorType::InvalidArgumentType => {
+                    "Invalid argument type for native function.".to_string()
+                }
+                RuntimeErrorType::PopFromEmptyList => "Cannot pop from an empty list.".to_string(),
+                RuntimeErrorType::UnhashableKey => {
+                    "Map keys must be strings, numbers, booleans or nil.".to_string()
+                }
+                RuntimeErrorType::NanKey => "NaN can't be used as a map key.".to_string(),
+                RuntimeErrorType::UndefinedKey => "Undefined map key.".to_string(),
+                RuntimeErrorType::ModuleNotFound => match &token.literal {
+                    Some(path) => format!("Cannot read the module {path}."),
+                    None => "Cannot read the module.".to_string(),
+                },
+                RuntimeErrorType::CircularImport => match &token.literal {
+                    Some(path) => format!("Circular import of the module {path}."),
+                    None => "Circular import.".to_string(),
+                },
+                RuntimeErrorType::StackOverflow => {
+                    "Stack overflow, too many nested function calls.".to_string()
+                }
+                RuntimeErrorType::NativeIoError => {
+                    "The input or the file couldn't be accessed.".to_string()
+                }
+                RuntimeErrorType::ReadOnlyModule => {
+                    format!("Cannot assign '{}', modules are read-only.", token.lexeme)
+                }
+                RuntimeErrorType::ImportNotAllowed => match &token.literal {
+                    Some(path) => format!("Cannot import the module {path} without file access."),
+                    None => "Cannot import a module without file access.".to_string(),
+                },
             },
 
             // Environment errors
-            LoxResult::Environment { error_type, msg } => match error_type {
-                EnvironmentErrorType::UnknownVariable => write!(f, "[env] {msg}")?,
+            LoxResult::Environment {
+                error_type, msg, ..
+            } => match error_type {
+                EnvironmentErrorType::UnknownVariable => msg.clone(),
             },
 
             // Return value
-            LoxResult::ReturnValue { value } => write!(f, "return {value}")?,
+            LoxResult::ReturnValue { value } => format!("return {value}"),
+
+            // Loop control flow
+            LoxResult::Break => "break".to_string(),
+            LoxResult::Continue => "continue".to_string(),
+
+            // Debugger
+            LoxResult::Stop => "Program stopped by the debugger.".to_string(),
+
+            // Execution budget
+            LoxResult::Interrupted { interruption } => match interruption {
+                Interruption::StatementLimit => {
+                    "Program stopped after executing too many statements.".to_string()
+                }
+                Interruption::Timeout => "Program stopped after running out of time.".to_string(),
+                Interruption::Cancelled => "Program cancelled.".to_string(),
+            },
 
             // Resolver Error
             LoxResult::Resolver { token, error_type } => match error_type {
-                ResolverErrorType::VariableNotInitialized => write!(
-                    f,
-                    "[resolver] {} -> Can't read local variable in its own initializer.",
-                    token.location()
-                )?,
-                ResolverErrorType::VariableAlreadyExists => write!(
-                    f,
-                    "[resolver] {} -> A variable with the name '{}' already exists in this scope.",
-                    token.location(),
+                ResolverErrorType::VariableNotInitialized => {
+                    "Can't read local variable in its own initializer.".to_string()
+                }
+                ResolverErrorType::VariableAlreadyExists => format!(
+                    "A variable with the name '{}' already exists in this scope.",
                     token.lexeme
-                )?,
-                ResolverErrorType::TopLevelReturn => write!(
-                    f,
-                    "[resolver] {} -> Can't return from top level code.",
-                    token.location()
-                )?,
-                ResolverErrorType::ThisOutsideClass => write!(
-                    f,
-                    "[resolver] {} -> Cannot use 'this' outside a class.",
-                    token.location(),
-                )?,
-                ResolverErrorType::ReturnFromInit => write!(
-                    f,
-                    "[resolver] {} -> Cannot return a value from the init() function.",
-                    token.location(),
-                )?,
-                ResolverErrorType::ClassInheritItself => write!(
-                    f,
-                    "[resolver] {} -> A class cannot inherit from itself.",
-                    token.location()
-                )?,
+                ),
+                ResolverErrorType::TopLevelReturn => {
+                    "Can't return from top level code.".to_string()
+                }
+                ResolverErrorType::ThisOutsideClass => {
+                    "Cannot use 'this' outside a class.".to_string()
+                }
+                ResolverErrorType::ReturnFromInit => {
+                    "Cannot return a value from the init() function.".to_string()
+                }
+                ResolverErrorType::ClassInheritItself => {
+                    "A class cannot inherit from itself.".to_string()
+                }
+                ResolverErrorType::BreakOutsideLoop => {
+                    "Can't use 'break' outside a loop.".to_string()
+                }
+                ResolverErrorType::ContinueOutsideLoop => {
+                    "Can't use 'continue' outside a loop.".to_string()
+                }
             },
+
+            // Compiler errors
+            LoxResult::Compiler { error_type, .. } => match error_type {
+                CompilerErrorType::TooManyConstants => {
+                    "Too many constants in one function.".to_string()
+                }
+                CompilerErrorType::TooManyLocals => {
+                    "Too many local variables in function.".to_string()
+                }
+                CompilerErrorType::TooManyUpvalues => {
+                    "Too many closure variables in function.".to_string()
+                }
+                CompilerErrorType::JumpTooLarge => "Too much code to jump over.".to_string(),
+                CompilerErrorType::UnsupportedImport => {
+                    "Imports are only supported by the tree-walking interpreter.".to_string()
+                }
+                CompilerErrorType::UnsupportedExceptions => {
+                    "Exceptions are only supported by the tree-walking interpreter.".to_string()
+                }
+            },
+
+            // Lints
+            LoxResult::Warning { lint } => lint.message.clone(),
+
+            // Errors of an imported module, located in its own file
+            LoxResult::Import { token, error } => match &token.literal {
+                Some(path) => format!("In the module {path}: {}", error.message()),
+                None => format!("In a module: {}", error.message()),
+            },
+
+            // A thrown value nothing caught, an error object being described by its message
+            LoxResult::Thrown { value, .. } => match value {
+                Object::Instance(instance) => match instance.fields.borrow().get("message") {
+                    Some(message) => format!("Uncaught {}: {message}", instance.class.name),
+                    None => format!("Uncaught {}.", instance.class.name),
+                },
+                value => format!("Uncaught {value}."),
+            },
+
+            // Multiple errors, one per line
+            LoxResult::Multiple { errors } => errors
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<String>>()
+                .join("\n"),
         }
+    }
+}
 
-        Ok(())
+impl fmt::Display for LoxResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoxResult::ReturnValue { .. }
+            | LoxResult::Break
+            | LoxResult::Continue
+            | LoxResult::Stop
+            | LoxResult::Multiple { .. } => {
+                write!(f, "{}", self.message())
+            }
+            _ => match self.span() {
+                Some(span) => write!(
+                    f,
+                    "[{}] {} -> {}",
+                    self.phase(),
+                    span.location(),
+                    self.message()
+                ),
+                None => write!(f, "[{}] {}", self.phase(), self.message()),
+            },
+        }
     }
 }
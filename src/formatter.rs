@@ -0,0 +1,324 @@
+use crate::errors::LoxResult;
+use crate::scanner::Scanner;
+use crate::token::{Comment, Token};
+use crate::token_type::TokenType;
+
+/// The indentation of each level of blocks.
+const INDENT: &str = "  ";
+
+/**
+ * A piece of source code to lay out, in the order of the source.
+ */
+enum Item<'a> {
+    Token(&'a Token),
+    Comment(&'a Comment),
+}
+
+impl Item<'_> {
+    fn start(&self) -> usize {
+        match self {
+            Item::Token(token) => token.src_start,
+            Item::Comment(comment) => comment.span.start,
+        }
+    }
+
+    fn end(&self) -> usize {
+        match self {
+            Item::Token(token) => token.src_end,
+            Item::Comment(comment) => comment.span.end,
+        }
+    }
+}
+
+/**
+ * A pair of braces being laid out.
+ */
+struct Brace {
+    /// Whether the braces hold statements (one per line) rather than the entries of a map.
+    block: bool,
+    /// The depth of parentheses and brackets inside the braces.
+    parens: usize,
+}
+
+/**
+ * Lays out the tokens and comments of a source file, one line at a time.
+ */
+struct Formatter {
+    source: Vec<char>,
+    lines: Vec<String>,
+    /// The line being laid out, without its indentation.
+    line: String,
+    /// The indentation level of the line being laid out.
+    indent: usize,
+    /// Whether the next item goes on a new line.
+    newline: bool,
+    /// Whether the next line continues a statement, and is indented one more level.
+    continuation: bool,
+    /// The braces around the current item, the top level code being a block.
+    braces: Vec<Brace>,
+    /// The previous token, along with whether it was a prefix operator and whether it ended an
+    /// operand.
+    previous: Option<(TokenType, bool, bool)>,
+    /// Whether the previous item opened a block.
+    opened_block: bool,
+}
+
+/**
+ * Formats Lox source code into its canonical layout:
+ * - one statement per line, blocks indented by two spaces and their braces in the K&R style,
+ * - one space around binary operators and after commas, none inside parentheses,
+ * - at most one blank line between statements,
+ * - comments kept where they are, either on their own line or after a statement.
+ *
+ * Formatting is idempotent: formatted code is left unchanged. The code only needs to be
+ * scanned, syntax errors are laid out as well as possible.
+ */
+pub fn format(source: &str) -> Result<String, LoxResult> {
+    let mut scanner = Scanner::new(source);
+    scanner.scan_tokens()?;
+
+    // Merge the tokens and comments in the order of the source
+    let mut items = scanner
+        .tokens
+        .iter()
+        .filter(|token| token.ttype != TokenType::Eof)
+        .map(Item::Token)
+        .chain(scanner.comments.iter().map(Item::Comment))
+        .collect::<Vec<Item>>();
+    items.sort_by_key(Item::start);
+
+    let mut formatter = Formatter {
+        source: source.chars().collect(),
+        lines: Vec::new(),
+        line: String::new(),
+        indent: 0,
+        newline: false,
+        continuation: false,
+        braces: vec![Brace {
+            block: true,
+            parens: 0,
+        }],
+        previous: None,
+        opened_block: false,
+    };
+
+    let mut end = 0;
+    for (i, item) in items.iter().enumerate() {
+        let newlines = formatter.source[end.min(item.start())..item.start()]
+            .iter()
+            .filter(|&&c| c == '\n')
+            .count();
+
+        match item {
+            Item::Token(token) => {
+                let next = items[i + 1..].iter().find_map(|item| match item {
+                    Item::Token(token) => Some(token.ttype),
+                    Item::Comment(_) => None,
+                });
+                formatter.token(token, next, newlines);
+            }
+            Item::Comment(comment) => formatter.comment(comment, newlines, i == 0),
+        }
+        end = item.end();
+    }
+    formatter.flush();
+
+    let mut formatted = formatter.lines.join("\n");
+    formatted.push('\n');
+    Ok(formatted)
+}
+
+/**
+ * Tells if a token ends an operand (as does the closing brace of a map), so that a following `-`
+ * is a binary operator and a following `(` or `[` is a call or an index.
+ */
+fn ends_operand(ttype: TokenType) -> bool {
+    matches!(
+        ttype,
+        TokenType::Identifier
+            | TokenType::String
+            | TokenType::Number
+            | TokenType::True
+            | TokenType::False
+            | TokenType::Nil
+            | TokenType::This
+            | TokenType::Super
+            | TokenType::RightParen
+            | TokenType::RightBracket
+    )
+}
+
+impl Formatter {
+    fn depth(&self) -> usize {
+        self.braces.iter().filter(|brace| brace.block).count() - 1
+    }
+
+    /**
+     * Ends the line being laid out, if any.
+     */
+    fn flush(&mut self) {
+        if !self.line.is_empty() {
+            let line = format!("{}{}", INDENT.repeat(self.indent), self.line);
+            self.lines.push(line.trim_end().to_string());
+            self.line.clear();
+        }
+    }
+
+    /**
+     * Starts a new line, after a blank one if the source had one there.
+     */
+    fn start_line(&mut self, newlines: usize, closing: bool) {
+        self.flush();
+        if newlines > 1 && !self.opened_block && !closing && !self.lines.is_empty() {
+            self.lines.push(String::new());
+        }
+        self.indent = self.depth() + usize::from(self.continuation);
+        self.newline = false;
+    }
+
+    fn comment(&mut self, comment: &Comment, newlines: usize, first: bool) {
+        if newlines == 0 && !first {
+            // A comment following code stays on its line
+            self.line.push(' ');
+        } else {
+            self.start_line(newlines, false);
+        }
+        self.line.push_str(&comment.text);
+        self.newline = true;
+        self.opened_block = false;
+    }
+
+    fn token(&mut self, token: &Token, next: Option<TokenType>, newlines: usize) {
+        let text = self.source[token.src_start..token.src_end]
+            .iter()
+            .collect::<String>();
+        let ttype = token.ttype;
+        let previous = self.previous.map(|(ttype, _, _)| ttype);
+        let after_operand = self.previous.is_some_and(|(_, _, operand)| operand);
+        let prefix = match ttype {
+            TokenType::Bang => true,
+            TokenType::Minus => !after_operand,
+            _ => false,
+        };
+
+        // Close the braces before laying out the line, to unindent it
+        let closed = match ttype {
+            TokenType::RightBrace if self.braces.len() > 1 => self.braces.pop(),
+            _ => None,
+        };
+        let closed_block = closed.as_ref().is_some_and(|brace| brace.block);
+        if closed_block {
+            self.continuation = false;
+        }
+
+        if closed_block && self.opened_block {
+            // An empty block stays on the line that opened it
+            self.newline = false;
+        } else if self.newline {
+            self.start_line(newlines, ttype == TokenType::RightBrace);
+        } else if self.space_before(ttype) && !self.line.is_empty() {
+            self.line.push(' ');
+        }
+        self.line.push_str(&text);
+        let operand = ends_operand(ttype) || (closed.is_some() && !closed_block);
+        self.previous = Some((ttype, prefix, operand));
+        self.opened_block = false;
+
+        let brace = self.braces.last_mut().unwrap();
+        match ttype {
+            TokenType::LeftParen | TokenType::LeftBracket => {
+                brace.parens += 1;
+                self.continuation = true;
+            }
+            TokenType::RightParen | TokenType::RightBracket => {
+                brace.parens = brace.parens.saturating_sub(1);
+            }
+            TokenType::LeftBrace => {
+                // Braces starting a statement or ending the head of a declaration, an `if`, a
+                // loop or a `try` are a block, the others are a map
+                let block = match previous {
+                    None => true,
+                    Some(previous) => {
+                        matches!(
+                            previous,
+                            TokenType::RightParen
+                                | TokenType::Identifier
+                                | TokenType::Else
+                                | TokenType::Try
+                                | TokenType::Finally
+                                | TokenType::Semicolon
+                        ) || (matches!(previous, TokenType::LeftBrace | TokenType::RightBrace)
+                            && brace.block)
+                    }
+                };
+                self.braces.push(Brace { block, parens: 0 });
+                if block {
+                    self.newline = true;
+                    self.continuation = false;
+                    self.opened_block = true;
+                }
+            }
+            TokenType::RightBrace => {
+                if closed_block {
+                    // A block ends the line, unless it is part of an expression, an `if` or a
+                    // `try`, e.g. a function ending a map whose closing brace follows it
+                    let ends_map = !brace.block && next == Some(TokenType::RightBrace);
+                    self.newline = !ends_map
+                        && !matches!(
+                            next,
+                            Some(
+                                TokenType::Semicolon
+                                    | TokenType::LeftParen
+                                    | TokenType::Dot
+                                    | TokenType::RightParen
+                                    | TokenType::RightBracket
+                                    | TokenType::Comma
+                                    | TokenType::Else
+                                    | TokenType::Catch
+                                    | TokenType::Finally
+                            )
+                        );
+                    self.continuation = !self.newline
+                        && !matches!(
+                            next,
+                            Some(TokenType::Else | TokenType::Catch | TokenType::Finally)
+                        );
+                }
+            }
+            TokenType::Semicolon if brace.parens == 0 => {
+                self.newline = true;
+                self.continuation = false;
+            }
+            _ => self.continuation = true,
+        }
+    }
+
+    /**
+     * Tells if a space separates the previous token from the next one, on the same line.
+     */
+    fn space_before(&self, ttype: TokenType) -> bool {
+        let Some((previous, prefix, operand)) = self.previous else {
+            return false;
+        };
+
+        match (previous, ttype) {
+            (
+                _,
+                TokenType::Semicolon
+                | TokenType::Comma
+                | TokenType::Dot
+                | TokenType::Colon
+                | TokenType::RightParen
+                | TokenType::RightBracket
+                | TokenType::RightBrace,
+            ) => false,
+            (TokenType::LeftParen | TokenType::LeftBracket | TokenType::Dot, _) => false,
+            (TokenType::LeftBrace, _) => false,
+            _ if prefix => false,
+            // A call of an anonymous function follows its body
+            (TokenType::RightBrace, TokenType::LeftParen) => false,
+            (_, TokenType::LeftParen | TokenType::LeftBracket) => !operand,
+            _ => true,
+        }
+    }
+}
@@ -0,0 +1,466 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+
+use crate::environment::Environment;
+use crate::lox_class::LoxClass;
+use crate::lox_function::LoxFunction;
+use crate::lox_instance::LoxInstance;
+use crate::lox_map::LoxMap;
+use crate::lox_module::LoxModule;
+use crate::object::Object;
+use crate::vm_object::{BoundMethod, Closure, Upvalue, VmClass, VmInstance};
+
+/// The number of tracked objects allocated before the first collection.
+const INITIAL_COLLECTION_THRESHOLD: usize = 4096;
+
+thread_local! {
+    /// The objects allocated by the interpreters and vms of the current thread.
+    static HEAP: RefCell<Heap> = RefCell::new(Heap::new());
+}
+
+/**
+ * A heap object whose content can change after its creation, the only kind of objects that can
+ * end up in a reference cycle (e.g. an environment holding a function that closes over it).
+ */
+pub enum Tracked {
+    Environment(Weak<RefCell<Environment>>),
+    Instance(Weak<LoxInstance>),
+    List(Weak<RefCell<Vec<Object>>>),
+    Map(Weak<RefCell<LoxMap>>),
+    VmClass(Weak<VmClass>),
+    VmInstance(Weak<VmInstance>),
+    Upvalue(Weak<RefCell<Upvalue>>),
+}
+
+impl Tracked {
+    fn is_alive(&self) -> bool {
+        match self {
+            Tracked::Environment(weak) => weak.strong_count() > 0,
+            Tracked::Instance(weak) => weak.strong_count() > 0,
+            Tracked::List(weak) => weak.strong_count() > 0,
+            Tracked::Map(weak) => weak.strong_count() > 0,
+            Tracked::VmClass(weak) => weak.strong_count() > 0,
+            Tracked::VmInstance(weak) => weak.strong_count() > 0,
+            Tracked::Upvalue(weak) => weak.strong_count() > 0,
+        }
+    }
+
+    fn upgrade(&self) -> Option<Node> {
+        match self {
+            Tracked::Environment(weak) => weak.upgrade().map(Node::Environment),
+            Tracked::Instance(weak) => weak.upgrade().map(Node::Instance),
+            Tracked::List(weak) => weak.upgrade().map(Node::List),
+            Tracked::Map(weak) => weak.upgrade().map(Node::Map),
+            Tracked::VmClass(weak) => weak.upgrade().map(Node::VmClass),
+            Tracked::VmInstance(weak) => weak.upgrade().map(Node::VmInstance),
+            Tracked::Upvalue(weak) => weak.upgrade().map(Node::Upvalue),
+        }
+    }
+}
+
+impl From<&Rc<RefCell<Environment>>> for Tracked {
+    fn from(env: &Rc<RefCell<Environment>>) -> Self {
+        Tracked::Environment(Rc::downgrade(env))
+    }
+}
+
+impl From<&Rc<LoxInstance>> for Tracked {
+    fn from(instance: &Rc<LoxInstance>) -> Self {
+        Tracked::Instance(Rc::downgrade(instance))
+    }
+}
+
+impl From<&Rc<RefCell<Vec<Object>>>> for Tracked {
+    fn from(list: &Rc<RefCell<Vec<Object>>>) -> Self {
+        Tracked::List(Rc::downgrade(list))
+    }
+}
+
+impl From<&Rc<RefCell<LoxMap>>> for Tracked {
+    fn from(map: &Rc<RefCell<LoxMap>>) -> Self {
+        Tracked::Map(Rc::downgrade(map))
+    }
+}
+
+impl From<&Rc<VmClass>> for Tracked {
+    fn from(class: &Rc<VmClass>) -> Self {
+        Tracked::VmClass(Rc::downgrade(class))
+    }
+}
+
+impl From<&Rc<VmInstance>> for Tracked {
+    fn from(instance: &Rc<VmInstance>) -> Self {
+        Tracked::VmInstance(Rc::downgrade(instance))
+    }
+}
+
+impl From<&Rc<RefCell<Upvalue>>> for Tracked {
+    fn from(upvalue: &Rc<RefCell<Upvalue>>) -> Self {
+        Tracked::Upvalue(Rc::downgrade(upvalue))
+    }
+}
+
+/**
+ * The registry of the tracked objects of a thread.
+ */
+struct Heap {
+    /// Every tracked object allocated since the last collection, or alive at that time.
+    objects: Vec<Tracked>,
+    /// The number of tracked objects that triggers the next collection.
+    next_collection: usize,
+}
+
+impl Heap {
+    fn new() -> Self {
+        Heap {
+            objects: Vec::new(),
+            next_collection: INITIAL_COLLECTION_THRESHOLD,
+        }
+    }
+}
+
+/**
+ * Registers a newly allocated object so that the collector can free it if it ends up in an
+ * unreachable reference cycle.
+ */
+pub fn track(object: impl Into<Tracked>) {
+    HEAP.with(|heap| heap.borrow_mut().objects.push(object.into()));
+}
+
+/**
+ * Returns the number of tracked objects (environments, instances, lists, maps, classes and
+ * upvalues) still alive in the current thread.
+ */
+pub fn live_objects() -> usize {
+    HEAP.with(|heap| {
+        heap.borrow()
+            .objects
+            .iter()
+            .filter(|object| object.is_alive())
+            .count()
+    })
+}
+
+/**
+ * Runs a collection if enough objects were allocated since the last one.
+ *
+ * Note: Must not be called while a tracked object is borrowed, e.g. at a statement boundary.
+ */
+pub fn maybe_collect() {
+    let is_due = HEAP.with(|heap| {
+        let heap = heap.borrow();
+        heap.objects.len() >= heap.next_collection
+    });
+
+    if is_due {
+        collect();
+    }
+}
+
+/**
+ * Frees the objects that are only kept alive by reference cycles, and returns their number.
+ *
+ * The objects reachable from the tracked ones form a graph. An object referenced more times than
+ * there are edges pointing at it in that graph is also referenced from outside of it (e.g. a
+ * variable of the interpreter or the stack of the vm): it is a root. Every object that can't be
+ * reached from a root is garbage, and its content is cleared to break the cycles it is part of.
+ */
+pub fn collect() -> usize {
+    // Get a reference to each tracked object still alive
+    let tracked = HEAP.with(|heap| {
+        let mut heap = heap.borrow_mut();
+        heap.objects.retain(|object| object.is_alive());
+        heap.objects
+            .iter()
+            .filter_map(|object| object.upgrade())
+            .collect::<Vec<Node>>()
+    });
+
+    // Build the graph of the objects reachable from the tracked ones
+    let mut graph = Graph::default();
+    for node in tracked {
+        graph.add(node);
+    }
+    let mut i = 0;
+    while i < graph.nodes.len() {
+        let mut children = Vec::new();
+        if !graph.nodes[i].children(&mut children) {
+            // The content of the object can't be inspected, consider it in use
+            graph.pinned[i] = true;
+        }
+        for child in children {
+            let j = graph.add(child);
+            graph.references[j] += 1;
+            graph.edges[i].push(j);
+        }
+        i += 1;
+    }
+
+    // Mark every object reachable from the roots. The graph itself holds one reference to each.
+    let mut reachable = vec![false; graph.nodes.len()];
+    let mut stack = (0..graph.nodes.len())
+        .filter(|&i| graph.pinned[i] || graph.nodes[i].strong_count() - 1 > graph.references[i])
+        .collect::<Vec<usize>>();
+    while let Some(i) = stack.pop() {
+        if !reachable[i] {
+            reachable[i] = true;
+            stack.extend(graph.edges[i].iter().filter(|&&j| !reachable[j]));
+        }
+    }
+
+    // Break the cycles of the unreachable objects. They are freed along with the graph.
+    let mut freed = 0;
+    for (node, _) in graph
+        .nodes
+        .iter()
+        .zip(reachable)
+        .filter(|(_, reachable)| !reachable)
+    {
+        node.clear();
+        freed += 1;
+    }
+    drop(graph);
+
+    // Wait for the number of live objects to double before the next collection
+    HEAP.with(|heap| {
+        let mut heap = heap.borrow_mut();
+        heap.objects.retain(|object| object.is_alive());
+        heap.next_collection = INITIAL_COLLECTION_THRESHOLD.max(heap.objects.len() * 2);
+    });
+
+    freed
+}
+
+/**
+ * The objects reachable from the tracked ones, along with the references between them.
+ */
+#[derive(Default)]
+struct Graph {
+    /// The objects of the graph, each one held once.
+    nodes: Vec<Node>,
+    /// The index of each object in `self.nodes`, by address.
+    indexes: HashMap<usize, usize>,
+    /// For each object, the indexes of the objects it references.
+    edges: Vec<Vec<usize>>,
+    /// For each object, the number of references to it from the other objects of the graph.
+    references: Vec<usize>,
+    /// For each object, whether it must be kept no matter what references it.
+    pinned: Vec<bool>,
+}
+
+impl Graph {
+    /**
+     * Adds an object to the graph if it isn't in it already, and returns its index.
+     */
+    fn add(&mut self, node: Node) -> usize {
+        if let Some(&index) = self.indexes.get(&node.address()) {
+            return index;
+        }
+
+        self.indexes.insert(node.address(), self.nodes.len());
+        self.nodes.push(node);
+        self.edges.push(Vec::new());
+        self.references.push(0);
+        self.pinned.push(false);
+        self.nodes.len() - 1
+    }
+}
+
+/**
+ * A heap object that can reference other heap objects.
+ */
+enum Node {
+    Environment(Rc<RefCell<Environment>>),
+    Function(Rc<LoxFunction>),
+    Class(Rc<LoxClass>),
+    Instance(Rc<LoxInstance>),
+    List(Rc<RefCell<Vec<Object>>>),
+    Map(Rc<RefCell<LoxMap>>),
+    Module(Rc<LoxModule>),
+    Closure(Rc<Closure>),
+    BoundMethod(Rc<BoundMethod>),
+    VmClass(Rc<VmClass>),
+    VmInstance(Rc<VmInstance>),
+    Upvalue(Rc<RefCell<Upvalue>>),
+}
+
+/**
+ * Returns the address of the allocation of `rc`, which identifies the object.
+ */
+fn address<T: ?Sized>(rc: &Rc<T>) -> usize {
+    Rc::as_ptr(rc) as *const () as usize
+}
+
+impl Node {
+    fn from_object(object: &Object) -> Option<Node> {
+        match object {
+            Object::Function(function) => Some(Node::Function(Rc::clone(function))),
+            Object::Class(class) => Some(Node::Class(Rc::clone(class))),
+            Object::Instance(instance) => Some(Node::Instance(Rc::clone(instance))),
+            Object::List(list) => Some(Node::List(Rc::clone(list))),
+            Object::Map(map) => Some(Node::Map(Rc::clone(map))),
+            Object::Module(module) => Some(Node::Module(Rc::clone(module))),
+            Object::Closure(closure) => Some(Node::Closure(Rc::clone(closure))),
+            Object::BoundMethod(bound) => Some(Node::BoundMethod(Rc::clone(bound))),
+            Object::VmClass(class) => Some(Node::VmClass(Rc::clone(class))),
+            Object::VmInstance(instance) => Some(Node::VmInstance(Rc::clone(instance))),
+            _ => None,
+        }
+    }
+
+    fn address(&self) -> usize {
+        match self {
+            Node::Environment(rc) => address(rc),
+            Node::Function(rc) => address(rc),
+            Node::Class(rc) => address(rc),
+            Node::Instance(rc) => address(rc),
+            Node::List(rc) => address(rc),
+            Node::Map(rc) => address(rc),
+            Node::Module(rc) => address(rc),
+            Node::Closure(rc) => address(rc),
+            Node::BoundMethod(rc) => address(rc),
+            Node::VmClass(rc) => address(rc),
+            Node::VmInstance(rc) => address(rc),
+            Node::Upvalue(rc) => address(rc),
+        }
+    }
+
+    fn strong_count(&self) -> usize {
+        match self {
+            Node::Environment(rc) => Rc::strong_count(rc),
+            Node::Function(rc) => Rc::strong_count(rc),
+            Node::Class(rc) => Rc::strong_count(rc),
+            Node::Instance(rc) => Rc::strong_count(rc),
+            Node::List(rc) => Rc::strong_count(rc),
+            Node::Map(rc) => Rc::strong_count(rc),
+            Node::Module(rc) => Rc::strong_count(rc),
+            Node::Closure(rc) => Rc::strong_count(rc),
+            Node::BoundMethod(rc) => Rc::strong_count(rc),
+            Node::VmClass(rc) => Rc::strong_count(rc),
+            Node::VmInstance(rc) => Rc::strong_count(rc),
+            Node::Upvalue(rc) => Rc::strong_count(rc),
+        }
+    }
+
+    /**
+     * Pushes the objects referenced by this one into `children`.
+     *
+     * Note: Returns `false` if the content of the object is currently borrowed.
+     */
+    fn children(&self, children: &mut Vec<Node>) -> bool {
+        let objects = |children: &mut Vec<Node>, objects: &mut dyn Iterator<Item = &Object>| {
+            children.extend(objects.filter_map(Node::from_object))
+        };
+
+        match self {
+            Node::Environment(env) => {
+                let Ok(env) = env.try_borrow() else {
+                    return false;
+                };
+                if let Some(enclosing) = &env.enclosing {
+                    children.push(Node::Environment(Rc::clone(enclosing)));
+                }
+                objects(children, &mut env.objects());
+            }
+            Node::Function(function) => {
+                children.push(Node::Environment(Rc::clone(&function.closure)));
+            }
+            Node::Class(class) => {
+                if let Some(superclass) = &class.superclass {
+                    children.push(Node::Class(Rc::clone(superclass)));
+                }
+                for method in class.methods.values().chain(class.class_methods.values()) {
+                    children.push(Node::Environment(Rc::clone(&method.closure)));
+                }
+            }
+            Node::Instance(instance) => {
+                let Ok(fields) = instance.fields.try_borrow() else {
+                    return false;
+                };
+                children.push(Node::Class(Rc::clone(&instance.class)));
+                objects(children, &mut fields.values());
+            }
+            Node::List(list) => {
+                let Ok(list) = list.try_borrow() else {
+                    return false;
+                };
+                objects(children, &mut list.iter());
+            }
+            Node::Map(map) => {
+                let Ok(map) = map.try_borrow() else {
+                    return false;
+                };
+                objects(children, &mut map.values().iter());
+            }
+            Node::Module(module) => {
+                children.push(Node::Environment(Rc::clone(&module.globals)));
+            }
+            Node::Closure(closure) => {
+                for upvalue in &closure.upvalues {
+                    children.push(Node::Upvalue(Rc::clone(upvalue)));
+                }
+            }
+            Node::BoundMethod(bound) => {
+                children.extend(Node::from_object(&bound.receiver));
+                children.push(Node::Closure(Rc::clone(&bound.method)));
+            }
+            Node::VmClass(class) => {
+                let (Ok(methods), Ok(class_methods)) =
+                    (class.methods.try_borrow(), class.class_methods.try_borrow())
+                else {
+                    return false;
+                };
+                if let Some(superclass) = &class.superclass {
+                    children.push(Node::VmClass(Rc::clone(superclass)));
+                }
+                for method in methods.values().chain(class_methods.values()) {
+                    children.push(Node::Closure(Rc::clone(method)));
+                }
+            }
+            Node::VmInstance(instance) => {
+                let Ok(fields) = instance.fields.try_borrow() else {
+                    return false;
+                };
+                children.push(Node::VmClass(Rc::clone(&instance.class)));
+                objects(children, &mut fields.values());
+            }
+            Node::Upvalue(upvalue) => {
+                let Ok(upvalue) = upvalue.try_borrow() else {
+                    return false;
+                };
+                if let Upvalue::Closed(value) = &*upvalue {
+                    children.extend(Node::from_object(value));
+                }
+            }
+        }
+
+        true
+    }
+
+    /**
+     * Drops the content of the object, releasing the references it holds.
+     *
+     * Note: Objects that can't change after their creation are freed along with the tracked
+     * objects referencing them, there is nothing to clear.
+     */
+    fn clear(&self) {
+        match self {
+            Node::Environment(env) => drop(env.take()),
+            Node::Instance(instance) => drop(instance.fields.take()),
+            Node::List(list) => drop(list.take()),
+            Node::Map(map) => drop(map.take()),
+            Node::VmClass(class) => {
+                drop(class.methods.take());
+                drop(class.class_methods.take());
+            }
+            Node::VmInstance(instance) => drop(instance.fields.take()),
+            Node::Upvalue(upvalue) => drop(upvalue.replace(Upvalue::Closed(Object::Nil))),
+            Node::Function(_)
+            | Node::Class(_)
+            | Node::Module(_)
+            | Node::Closure(_)
+            | Node::BoundMethod(_) => {}
+        }
+    }
+}
@@ -1,20 +1,41 @@
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
+use crate::budget::Budget;
+use crate::debugger::{self, Debugger};
+use crate::diagnostic::{SourceFile, SourceMap};
 use crate::environment::Environment;
-use crate::errors::{LoxResult, RuntimeErrorType};
+use crate::errors::{CallFrame, LoxResult, RuntimeErrorType};
 use crate::expr::*;
+use crate::heap;
 use crate::lox_callable::LoxCallable;
 use crate::lox_class::LoxClass;
 use crate::lox_function::LoxFunction;
+use crate::lox_instance::LoxInstance;
+use crate::lox_map::{map_key, LoxMap};
+use crate::lox_module::LoxModule;
 use crate::lox_native::NativeFunction;
-use crate::native_functions::NativeClock;
-use crate::object::Object;
+use crate::native_functions::{native_functions, NativeModule};
+use crate::object::{list_index, Object};
+use crate::output::{OutputSink, StderrSink, StdoutSink};
+use crate::parser::Parser;
+use crate::resolver::Resolver;
+use crate::scanner::Scanner;
 use crate::stmt::*;
-use crate::token::Token;
+use crate::token::{SourceId, Token};
 use crate::token_type::TokenType;
 
+/// The default maximum number of nested calls of Lox functions.
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 256;
+
+/// The default amount of native stack, in bytes, the nested calls of Lox functions may take. Each
+/// of them takes up to 25 KiB in a debug build, this keeps a run within the 2 MiB of a spawned
+/// thread, leaving the rest to the host and to the code run in between two calls.
+pub const DEFAULT_MAX_STACK_SIZE: usize = 1 << 20;
+
 /**
  * The Interpreter will run through the scanned tokens and interpret it as source code.
  */
@@ -23,9 +44,42 @@ pub struct Interpreter {
     environment: Rc<RefCell<Environment>>,
     /// The top-level global environment of the source code being ran.
     pub env_globals: Rc<RefCell<Environment>>,
-    /// The local variables of the source code being ran.
-    /// TODO: Refactor into an Environment and references to Tokens, no ?
-    locals: HashMap<Token, usize>,
+    /// The `(depth, slot)` of the local variable each resolved expression refers to, keyed by
+    /// the id of the expression.
+    locals: HashMap<usize, (usize, usize)>,
+    /// Where the outcome of `print` statements is written.
+    output: Box<dyn OutputSink>,
+    /// Where the runtime diagnostics are written.
+    diagnostics: Box<dyn OutputSink>,
+    /// The name and source code of the program, which the runtime diagnostics are rendered
+    /// against once known.
+    source_file: Option<(String, String)>,
+    /// The debugger attached to the interpreter, if any.
+    debugger: Option<Debugger>,
+    /// The path of the file being run, modules are imported relative to its directory.
+    script: Option<PathBuf>,
+    /// The modules imported so far, by canonical path.
+    modules: HashMap<PathBuf, Rc<LoxModule>>,
+    /// The modules being imported, innermost last, to detect circular imports.
+    importing: Vec<PathBuf>,
+    /// The source code of the imported modules, which their errors point at.
+    sources: SourceMap,
+    /// The calls of Lox functions being run, innermost last.
+    call_stack: Vec<CallFrame>,
+    /// The maximum length of the call stack, past which calls raise a stack overflow error.
+    max_call_depth: usize,
+    /// The maximum amount of native stack the calls may take, past which they raise a stack
+    /// overflow error.
+    max_stack_size: usize,
+    /// The address of the native stack where the last run started, 0 before the first one.
+    stack_base: usize,
+    /// How much each run may execute, unlimited by default.
+    budget: Budget,
+    /// The number of `try` statements with a catch clause whose body is being run. The errors
+    /// of expression statements go to them rather than being reported.
+    catching: usize,
+    /// The native modules defined in the globals of the program and of its modules.
+    natives: Vec<NativeModule>,
 }
 
 impl Default for Interpreter {
@@ -34,21 +88,55 @@ impl Default for Interpreter {
     }
 }
 
-impl Interpreter {
+/**
+ * Configures an interpreter before creating it, e.g. a sandboxed one whose programs can't touch
+ * the filesystem or the process:
+ * `Interpreter::builder().natives(&NativeModule::SANDBOXED).build()`.
+ */
+pub struct InterpreterBuilder {
+    natives: Vec<NativeModule>,
+    max_call_depth: usize,
+    max_stack_size: usize,
+    budget: Budget,
+}
+
+impl Default for InterpreterBuilder {
+    fn default() -> Self {
+        InterpreterBuilder {
+            natives: NativeModule::ALL.to_vec(),
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            max_stack_size: DEFAULT_MAX_STACK_SIZE,
+            budget: Budget::new(),
+        }
+    }
+}
+
+impl InterpreterBuilder {
     /**
-     * Note: This is where `Object::Native` functions are defined in `self.env_globals`.
+     * Only defines the native functions of the given modules, all of them by default.
      */
-    pub fn new() -> Self {
-        // Instanciate a new empty environment
-        let globals = Rc::new(RefCell::new(Environment::new()));
+    pub fn natives(mut self, modules: &[NativeModule]) -> Self {
+        self.natives = modules.to_vec();
+        self
+    }
+
+    pub fn max_call_depth(mut self, max_call_depth: usize) -> Self {
+        self.max_call_depth = max_call_depth;
+        self
+    }
+
+    pub fn max_stack_size(mut self, max_stack_size: usize) -> Self {
+        self.max_stack_size = max_stack_size;
+        self
+    }
+
+    pub fn budget(mut self, budget: Budget) -> Self {
+        self.budget = budget;
+        self
+    }
 
-        // Define the `clock()` function as a native one.
-        globals.borrow_mut().define(
-            "clock".to_string(),
-            Object::Native(Rc::new(NativeFunction {
-                function: Rc::new(NativeClock {}),
-            })),
-        );
+    pub fn build(self) -> Interpreter {
+        let globals = Interpreter::new_globals(&self.natives);
 
         // Return a new Interpreter instance
         // NOTE: Shouldn't the global env be enclosed in the env ?
@@ -56,6 +144,181 @@ impl Interpreter {
             environment: Rc::clone(&globals),
             env_globals: Rc::clone(&globals),
             locals: HashMap::new(),
+            output: Box::new(StdoutSink),
+            diagnostics: Box::new(StderrSink),
+            source_file: None,
+            debugger: None,
+            script: None,
+            modules: HashMap::new(),
+            importing: Vec::new(),
+            sources: SourceMap::new(),
+            call_stack: Vec::new(),
+            max_call_depth: self.max_call_depth,
+            max_stack_size: self.max_stack_size,
+            stack_base: 0,
+            budget: self.budget,
+            catching: 0,
+            natives: self.natives,
+        }
+    }
+}
+
+impl Interpreter {
+    /**
+     * Creates an interpreter with every native function defined.
+     */
+    pub fn new() -> Self {
+        Interpreter::builder().build()
+    }
+
+    pub fn builder() -> InterpreterBuilder {
+        InterpreterBuilder::default()
+    }
+
+    /**
+     * Instanciates a global environment, for a program or a module.
+     *
+     * Note: This is where `Object::Native` functions are defined.
+     */
+    fn new_globals(natives: &[NativeModule]) -> Rc<RefCell<Environment>> {
+        let globals = Rc::new(RefCell::new(Environment::new()));
+        for (name, function) in native_functions(natives) {
+            globals
+                .borrow_mut()
+                .define(name, Object::Native(Rc::new(NativeFunction { function })));
+        }
+
+        globals
+    }
+
+    /**
+     * Tells the path of the file being run, so that it can import modules relative to its
+     * directory (the current directory otherwise).
+     */
+    pub fn set_script(&mut self, path: &str) {
+        self.script = Some(PathBuf::from(path));
+    }
+
+    /**
+     * Returns the native modules defined in the globals of the programs.
+     */
+    pub fn natives(&self) -> &[NativeModule] {
+        &self.natives
+    }
+
+    /**
+     * Returns the source code of the modules imported so far, to render their errors.
+     */
+    pub fn sources(&self) -> &SourceMap {
+        &self.sources
+    }
+
+    /**
+     * Makes `globals` the global environment, and returns the previous one.
+     */
+    pub fn swap_globals(&mut self, globals: Rc<RefCell<Environment>>) -> Rc<RefCell<Environment>> {
+        std::mem::replace(&mut self.env_globals, globals)
+    }
+
+    /**
+     * Replaces the sink `print` statements write to (stdout by default).
+     */
+    pub fn set_output(&mut self, output: Box<dyn OutputSink>) {
+        self.output = output;
+    }
+
+    /**
+     * Replaces the sink runtime diagnostics are written to (stderr by default).
+     */
+    pub fn set_diagnostics(&mut self, diagnostics: Box<dyn OutputSink>) {
+        self.diagnostics = diagnostics;
+    }
+
+    /**
+     * Renders the runtime diagnostics against the given program, along with the traceback of
+     * their calls, the way `SourceFile::render` does. They are written on a single line
+     * otherwise.
+     */
+    pub fn set_source_file(&mut self, name: &str, source: &str) {
+        self.source_file = Some((name.to_string(), source.to_string()));
+    }
+
+    /**
+     * Sets the maximum number of nested calls of Lox functions, `DEFAULT_MAX_CALL_DEPTH` by
+     * default.
+     *
+     * Note: The calls are also limited by the native stack they take, see
+     * `set_max_stack_size`.
+     */
+    pub fn set_max_call_depth(&mut self, max_call_depth: usize) {
+        self.max_call_depth = max_call_depth;
+    }
+
+    /**
+     * Sets the maximum amount of native stack, in bytes, the nested calls of Lox functions may
+     * take, `DEFAULT_MAX_STACK_SIZE` by default.
+     *
+     * Note: Change it along with the stack size of the thread running the interpreter, keeping
+     * about half of it to the host (e.g. 4 MiB on the 8 MiB of a main thread).
+     */
+    pub fn set_max_stack_size(&mut self, max_stack_size: usize) {
+        self.max_stack_size = max_stack_size;
+    }
+
+    /**
+     * Limits how much each following run may execute.
+     */
+    pub fn set_budget(&mut self, budget: Budget) {
+        self.budget = budget;
+    }
+
+    /**
+     * Attaches a debugger, which pauses the program on its first statement.
+     */
+    pub fn set_debugger(&mut self, debugger: Debugger) {
+        self.debugger = Some(debugger);
+    }
+
+    /**
+     * Pushes the call of the function `name` at `call` on the call stack, and tells the
+     * debugger, if any, that the function is being entered.
+     *
+     * Note: Returns a `RuntimeErrorType::StackOverflow` error rather than entering the function
+     * if the call stack is already full, or if the calls already take too much of the native
+     * stack.
+     */
+    pub fn enter_function(&mut self, name: &str, call: &Token) -> Result<(), LoxResult> {
+        let stack_size = match self.stack_base {
+            0 => 0,
+            base => base.abs_diff(stack_address()),
+        };
+        if self.call_stack.len() >= self.max_call_depth || stack_size > self.max_stack_size {
+            return Err(LoxResult::Runtime {
+                token: call.clone(),
+                error_type: RuntimeErrorType::StackOverflow,
+                trace: Vec::new(),
+            });
+        }
+
+        self.call_stack.push(CallFrame {
+            function: name.to_string(),
+            call: call.clone(),
+        });
+        if let Some(debugger) = &mut self.debugger {
+            debugger.enter_function(name);
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Pops the current function off the call stack, and tells the debugger, if any, that it is
+     * being left.
+     */
+    pub fn exit_function(&mut self) {
+        self.call_stack.pop();
+        if let Some(debugger) = &mut self.debugger {
+            debugger.exit_function();
         }
     }
 
@@ -67,7 +330,12 @@ impl Interpreter {
         !(obj == Object::Nil || obj == Object::False)
     }
 
+    /**
+     * Runs a program, with the whole execution budget.
+     */
     pub fn interpret(&mut self, statements: &[Stmt]) -> Result<(), LoxResult> {
+        self.budget.start();
+        self.stack_base = stack_address();
         for statement in statements {
             self.execute(statement)?;
         }
@@ -75,10 +343,51 @@ impl Interpreter {
         Ok(())
     }
 
+    /**
+     * Executes a statement.
+     *
+     * Note: Nothing is borrowed in between two statements, this is where the garbage collector
+     * runs when enough objects were allocated, where the debugger can pause the program and
+     * where it is stopped once out of its execution budget.
+     */
     pub fn execute(&mut self, stmt: &Stmt) -> Result<(), LoxResult> {
+        self.before_statement(stmt)?;
         stmt.accept(self)
     }
 
+    /**
+     * Executes an expression statement and returns its value (e.g. the trailing statement of a
+     * snippet, whose value the session returns), as `execute` would run it. Its error is
+     * returned rather than reported.
+     */
+    pub fn execute_expression(&mut self, stmt: &Stmt) -> Result<Object, LoxResult> {
+        let Stmt::Expression { expression } = stmt else {
+            unreachable!()
+        };
+        self.before_statement(stmt)?;
+        self.evaluate(expression)
+    }
+
+    /**
+     * Runs the garbage collector, the debugger and the budget check that come before each
+     * statement, see `execute`.
+     */
+    fn before_statement(&mut self, stmt: &Stmt) -> Result<(), LoxResult> {
+        self.budget.check(true)?;
+        heap::maybe_collect();
+        if let Some(debugger) = &mut self.debugger {
+            // The statements of the program are in its script, the other ones in a module
+            let source = debugger::stmt_token(stmt).map_or(SourceId::MAIN, |token| token.src_id);
+            let file = match source {
+                SourceId::MAIN => self.script.as_deref(),
+                _ => self.sources.get(source).map(|file| Path::new(file.name)),
+            };
+            debugger.before_statement(stmt, &self.environment, file)?;
+        }
+
+        Ok(())
+    }
+
     pub fn execute_block(
         &mut self,
         stmts: &[Stmt],
@@ -94,20 +403,49 @@ impl Interpreter {
             Ok(())
         };
 
-        let result = steps();
+        let mut result = steps();
         self.environment = prev_env;
+
+        // Trace back the calls that led to a runtime error, from the innermost block it leaves
+        if let Err(LoxResult::Runtime { trace, .. } | LoxResult::Thrown { trace, .. }) = &mut result
+        {
+            if trace.is_empty() {
+                trace.clone_from(&self.call_stack);
+            }
+        }
+
         result
     }
 
-    pub fn look_up_variable(&self, name: &Token) -> Result<Object, LoxResult> {
-        // TODO: Sort out this `self.locals` mess. There must be something strange about it.
-        // Try to get it from the environment
-        if let Ok(obj) = self.environment.borrow().get(name) {
-            return Ok(obj);
+    /**
+     * Writes an error that doesn't stop the program to the diagnostics sink, traced back to the
+     * calls that led to it.
+     */
+    fn report(&mut self, mut error: LoxResult) {
+        if let LoxResult::Runtime { trace, .. } = &mut error {
+            if trace.is_empty() {
+                trace.clone_from(&self.call_stack);
+            }
         }
+
+        let line = match &self.source_file {
+            Some((name, source)) => {
+                let file = SourceFile::new(name, source).with_sources(&self.sources);
+                format!("{}\n", file.render(&error))
+            }
+            None => error.to_string(),
+        };
+        self.diagnostics.write_line(&line);
+    }
+
+    /**
+     * Gets the value of the variable the expression `id` refers to: a local variable if it was
+     * resolved, a global one otherwise.
+     */
+    pub fn look_up_variable(&self, id: usize, name: &Token) -> Result<Object, LoxResult> {
         // Try to get it from locals
-        if let Some(distance) = self.locals.get(name) {
-            Ok(self.environment.borrow().get_at(*distance, name)?)
+        if let Some((distance, slot)) = self.locals.get(&id) {
+            self.environment.borrow().get_at(*distance, *slot, name)
         }
         // Try to get it from globals
         else {
@@ -116,12 +454,133 @@ impl Interpreter {
     }
 
     /**
-     * Tells the interpreter that there is the variable `name` that is defined at
-     * a the specific `depth`.
+     * Interprets a class method declaration into a `LoxFunction` closing over the current
+     * environment.
+     */
+    fn method(&self, method: &Stmt, is_getter: bool) -> LoxFunction {
+        // Extract the name, body and param of the method
+        if let Stmt::Function { name, params, body } = method {
+            LoxFunction {
+                name: Some(name.clone()),
+                params: params.clone(),
+                body: body.clone(),
+                closure: Rc::clone(&self.environment),
+                is_init_function: name.lexeme == "init" && !is_getter,
+                is_getter,
+            }
+        } else {
+            unreachable!()
+        }
+    }
+
+    /**
+     * Imports the module at `path` (a string token), running it the first time.
+     *
+     * Note: Returns a `RuntimeErrorType::ImportNotAllowed` error if the interpreter doesn't
+     * grant access to files (the `NativeModule::Fs` module), a
+     * `RuntimeErrorType::ModuleNotFound` error if it can't be read, a
+     * `RuntimeErrorType::CircularImport` error if it is already being imported, and a
+     * `LoxResult::Import` error wrapping any error of the module itself.
      */
-    pub fn resolve(&mut self, name: &Token, depth: usize) {
-        // Insert the entry (name, depth) in the `self.locals` hashmap
-        self.locals.insert(name.clone(), depth);
+    fn import(&mut self, path: &Token) -> Result<Rc<LoxModule>, LoxResult> {
+        let Some(Object::Str(relative)) = &path.literal else {
+            unreachable!()
+        };
+        let error = |error_type| LoxResult::Runtime {
+            token: path.clone(),
+            error_type,
+            trace: Vec::new(),
+        };
+        let not_found = || error(RuntimeErrorType::ModuleNotFound);
+
+        // A sandboxed program can't read files, modules included
+        if !self.natives.contains(&NativeModule::Fs) {
+            return Err(error(RuntimeErrorType::ImportNotAllowed));
+        }
+
+        // Modules are found relative to the file importing them
+        let directory = self
+            .script
+            .as_deref()
+            .and_then(Path::parent)
+            .unwrap_or(Path::new(""));
+        let joined = directory.join(relative);
+        let file = fs::canonicalize(&joined).map_err(|_| not_found())?;
+        if self.importing.contains(&file) {
+            return Err(error(RuntimeErrorType::CircularImport));
+        }
+        if let Some(module) = self.modules.get(&file) {
+            return Ok(Rc::clone(module));
+        }
+        let source = fs::read_to_string(&file).map_err(|_| not_found())?;
+        let source_id = self.sources.add(&joined.to_string_lossy(), &source);
+
+        // Run the module in its own global environment, then restore the importing one
+        let globals = Interpreter::new_globals(&self.natives);
+        let script = self.script.replace(joined);
+        let enclosing_globals = self.swap_globals(Rc::clone(&globals));
+        let environment = std::mem::replace(&mut self.environment, Rc::clone(&globals));
+        self.importing.push(file.clone());
+
+        let result = self.run_module(&source, source_id);
+
+        self.importing.pop();
+        self.environment = environment;
+        self.env_globals = enclosing_globals;
+        self.script = script;
+
+        match result {
+            // The debugger or the budget stopping the program isn't an error of the module
+            Err(e @ (LoxResult::Stop | LoxResult::Interrupted { .. })) => return Err(e),
+            Err(error) => {
+                return Err(LoxResult::Import {
+                    token: path.clone(),
+                    error: Box::new(error),
+                })
+            }
+            Ok(()) => {}
+        }
+
+        let module = Rc::new(LoxModule {
+            name: file
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            path: file.clone(),
+            globals,
+        });
+        self.modules.insert(file, Rc::clone(&module));
+        Ok(module)
+    }
+
+    /**
+     * Runs the source code of a module through the whole pipeline, in the current environment.
+     */
+    fn run_module(&mut self, source: &str, source_id: SourceId) -> Result<(), LoxResult> {
+        let mut scanner = Scanner::with_source_id(source, source_id);
+        let tokens = scanner.scan_tokens()?;
+
+        let (statements, errors) = Parser::new(tokens).parse();
+        if !errors.is_empty() {
+            return Err(LoxResult::Multiple { errors });
+        }
+
+        Resolver::new(self).resolve_stmts(&statements)?;
+
+        // The module runs within the budget of the importing program
+        for statement in &statements {
+            self.execute(statement)?;
+        }
+        Ok(())
+    }
+
+    /**
+     * Tells the interpreter that the expression `id` refers to the local variable stored in
+     * `slot` of the environment at the specific `depth`.
+     */
+    pub fn resolve(&mut self, id: usize, depth: usize, slot: usize) {
+        // Insert the entry (id, (depth, slot)) in the `self.locals` hashmap
+        self.locals.insert(id, (depth, slot));
     }
 }
 
@@ -132,7 +591,11 @@ impl ExprVisitor<Object> for Interpreter {
     /**
      * A literal expression is a value: f64, true, false, nil.
      */
-    fn visit_literal_expr(&mut self, value: &Option<Object>) -> Result<Object, LoxResult> {
+    fn visit_literal_expr(
+        &mut self,
+        _token: &Token,
+        value: &Option<Object>,
+    ) -> Result<Object, LoxResult> {
         Ok(value.clone().unwrap())
     }
 
@@ -155,6 +618,7 @@ impl ExprVisitor<Object> for Interpreter {
                     Err(LoxResult::Runtime {
                         token: operator.clone(),
                         error_type: RuntimeErrorType::ExpectedNumberOperand,
+                        trace: Vec::new(),
                     })
                 }
             }
@@ -166,6 +630,7 @@ impl ExprVisitor<Object> for Interpreter {
             _ => Err(LoxResult::Runtime {
                 token: operator.clone(),
                 error_type: RuntimeErrorType::UnreachableCode,
+                trace: Vec::new(),
             }),
         }
     }
@@ -177,20 +642,25 @@ impl ExprVisitor<Object> for Interpreter {
      * Note: The `self.env_globals` can throw a `EnvironmentErrorType::UnknownVariable`
      * error here.
      */
-    fn visit_assign_expr(&mut self, name: &Token, value: &Expr) -> Result<Object, LoxResult> {
+    fn visit_assign_expr(
+        &mut self,
+        id: &usize,
+        name: &Token,
+        value: &Expr,
+    ) -> Result<Object, LoxResult> {
         // Evaluate the value
         let value = self.evaluate(value)?;
 
         // Try to get the known variable from the locally defined ones.
-        if let Some(distance) = self.locals.get(name) {
+        if let Some((distance, slot)) = self.locals.get(id) {
             // If we found it, reassign it to the evaluated value
             self.environment
                 .borrow_mut()
-                .assign_at(*distance, name, value.clone())?;
+                .assign_at(*distance, *slot, name, value.clone())?;
         }
         // Else, try to assign it in the globally known variables
         else {
-            self.environment.borrow_mut().assign(name, value.clone())?;
+            self.env_globals.borrow_mut().assign(name, value.clone())?;
         }
 
         Ok(value)
@@ -231,6 +701,7 @@ impl ExprVisitor<Object> for Interpreter {
                 Err(LoxResult::Runtime {
                     token: operator.clone(),
                     error_type: RuntimeErrorType::ExpectedNumberOperands,
+                    trace: Vec::new(),
                 })
             }
 
@@ -246,6 +717,7 @@ impl ExprVisitor<Object> for Interpreter {
                 Err(LoxResult::Runtime {
                     token: operator.clone(),
                     error_type: RuntimeErrorType::ExpectedNumberOperands,
+                    trace: Vec::new(),
                 })
             }
 
@@ -261,6 +733,7 @@ impl ExprVisitor<Object> for Interpreter {
                 Err(LoxResult::Runtime {
                     token: operator.clone(),
                     error_type: RuntimeErrorType::ExpectedNumberOperands,
+                    trace: Vec::new(),
                 })
             }
 
@@ -285,6 +758,7 @@ impl ExprVisitor<Object> for Interpreter {
                 Err(LoxResult::Runtime {
                     token: operator.clone(),
                     error_type: RuntimeErrorType::ExpectedAddableOperands,
+                    trace: Vec::new(),
                 })
             }
 
@@ -301,6 +775,7 @@ impl ExprVisitor<Object> for Interpreter {
                 Err(LoxResult::Runtime {
                     token: operator.clone(),
                     error_type: RuntimeErrorType::ExpectedNumberOperands,
+                    trace: Vec::new(),
                 })
             }
 
@@ -316,6 +791,7 @@ impl ExprVisitor<Object> for Interpreter {
                 Err(LoxResult::Runtime {
                     token: operator.clone(),
                     error_type: RuntimeErrorType::ExpectedNumberOperands,
+                    trace: Vec::new(),
                 })
             }
 
@@ -331,6 +807,7 @@ impl ExprVisitor<Object> for Interpreter {
                 Err(LoxResult::Runtime {
                     token: operator.clone(),
                     error_type: RuntimeErrorType::ExpectedNumberOperands,
+                    trace: Vec::new(),
                 })
             }
 
@@ -346,6 +823,7 @@ impl ExprVisitor<Object> for Interpreter {
                 Err(LoxResult::Runtime {
                     token: operator.clone(),
                     error_type: RuntimeErrorType::ExpectedNumberOperands,
+                    trace: Vec::new(),
                 })
             }
 
@@ -359,6 +837,7 @@ impl ExprVisitor<Object> for Interpreter {
             _ => Err(LoxResult::Runtime {
                 token: operator.clone(),
                 error_type: RuntimeErrorType::UnreachableCode,
+                trace: Vec::new(),
             }),
         }
     }
@@ -369,9 +848,9 @@ impl ExprVisitor<Object> for Interpreter {
      * Note: Calls `self.look_up_variable()` which can throw a
      * `EnvironmentErrorType::UnknownVariable` error.
      */
-    fn visit_variable_expr(&mut self, name: &Token) -> Result<Object, LoxResult> {
+    fn visit_variable_expr(&mut self, id: &usize, name: &Token) -> Result<Object, LoxResult> {
         // Try getting the variable's value
-        self.look_up_variable(name)
+        self.look_up_variable(*id, name)
     }
 
     /**
@@ -432,7 +911,7 @@ impl ExprVisitor<Object> for Interpreter {
         let (called_function, called_class): (Rc<dyn LoxCallable>, Option<Rc<LoxClass>>) =
             match callee {
                 // Check for native function
-                Object::Native(native) => (native.function.clone(), None),
+                Object::Native(native) => (native, None),
                 // Check for defined function
                 Object::Function(function) => (function, None),
                 // Check for define classes
@@ -445,6 +924,7 @@ impl ExprVisitor<Object> for Interpreter {
                     return Err(LoxResult::Runtime {
                         token: paren.clone(),
                         error_type: RuntimeErrorType::InvalidCallObjectType,
+                        trace: Vec::new(),
                     });
                 }
             };
@@ -454,11 +934,12 @@ impl ExprVisitor<Object> for Interpreter {
             return Err(LoxResult::Runtime {
                 token: paren.clone(),
                 error_type: RuntimeErrorType::InvalidArgsCount,
+                trace: Vec::new(),
             });
         }
 
         // Return the function's call result
-        called_function.call(self, call_args, called_class)
+        called_function.call(self, call_args, called_class, paren)
     }
 
     /**
@@ -472,13 +953,36 @@ impl ExprVisitor<Object> for Interpreter {
         let obj = self.evaluate(object)?;
         // Check that its evaluation gave an instance object
         if let Object::Instance(ref instance) = obj {
-            // If so, returns the attempt of getting a member from it.
-            instance.get(name, &obj)
+            // If so, attempt to get a member from it
+            let member = instance.get(name, &obj)?;
+            // A getter is run as soon as it is accessed
+            match member {
+                Object::Function(ref function) if function.is_getter => {
+                    function.call(self, Vec::new(), None, name)
+                }
+                _ => Ok(member),
+            }
+        }
+        // Check if it is a module, in which case we look for a top-level definition
+        else if let Object::Module(ref module) = obj {
+            module.get(name)
+        }
+        // Check if it is a class, in which case we look for a static method
+        else if let Object::Class(ref class) = obj {
+            match class.find_class_method(&name.lexeme) {
+                Some(method) => Ok(Object::Function(Rc::new(method.bind(&obj)))),
+                None => Err(LoxResult::Runtime {
+                    token: name.clone(),
+                    error_type: RuntimeErrorType::UndefinedProperty,
+                    trace: Vec::new(),
+                }),
+            }
         } else {
             // If it was not an instance, return an error
             Err(LoxResult::Runtime {
                 token: name.clone(),
                 error_type: RuntimeErrorType::InvalidObjectProperty,
+                trace: Vec::new(),
             })
         }
     }
@@ -503,42 +1007,209 @@ impl ExprVisitor<Object> for Interpreter {
             let val = self.evaluate(value)?;
             instance.set(name, val.clone());
             Ok(val)
+        } else if let Object::Module(_) = obj {
+            // The definitions of a module are only changed by its own code
+            Err(LoxResult::Runtime {
+                token: name.clone(),
+                error_type: RuntimeErrorType::ReadOnlyModule,
+                trace: Vec::new(),
+            })
         } else {
             // If it was not an instance, return an error
             Err(LoxResult::Runtime {
                 token: name.clone(),
                 error_type: RuntimeErrorType::InvalidObjectProperty,
+                trace: Vec::new(),
             })
         }
     }
 
+    /**
+     * Creates an anonymous function: fun (a, b) { ... }. Just like a declared function, it
+     * captures the current environment as its closure.
+     */
+    fn visit_lambda_expr(
+        &mut self,
+        _keyword: &Token,
+        params: &[Token],
+        body: &[Stmt],
+    ) -> Result<Object, LoxResult> {
+        Ok(Object::Function(Rc::new(LoxFunction {
+            name: None,
+            params: params.to_vec(),
+            body: body.to_vec(),
+            closure: Rc::clone(&self.environment),
+            is_init_function: false,
+            is_getter: false,
+        })))
+    }
+
+    /**
+     * Builds a new list from a list literal: [1, 2, 3].
+     */
+    fn visit_list_expr(
+        &mut self,
+        _bracket: &Token,
+        elements: &[Expr],
+    ) -> Result<Object, LoxResult> {
+        // Evaluate each element of the list in order
+        let mut values = Vec::new();
+        for element in elements {
+            values.push(self.evaluate(element)?);
+        }
+
+        Ok(Object::from(values))
+    }
+
+    /**
+     * Builds a new map from a map literal: {"key": value}.
+     */
+    fn visit_map_expr(
+        &mut self,
+        brace: &Token,
+        keys: &[Expr],
+        values: &[Expr],
+    ) -> Result<Object, LoxResult> {
+        // Evaluate each entry of the map in order
+        let mut map = LoxMap::new();
+        for (key, value) in keys.iter().zip(values) {
+            let key = self.evaluate(key)?;
+            let value = self.evaluate(value)?;
+            map.insert(map_key(&key, brace)?, key, value);
+        }
+
+        Ok(Object::from(map))
+    }
+
+    /**
+     * Attempts to get the element of a list at the given index (list[0]) or the value of a map
+     * at the given key (map["key"]).
+     *
+     * Note: If the `object` expression does not evaluate into an `Object::List` or an
+     * `Object::Map`, this function will return a `RuntimeErrorType::InvalidIndexedObject` error.
+     */
+    fn visit_index_expr(
+        &mut self,
+        object: &Expr,
+        bracket: &Token,
+        index: &Expr,
+    ) -> Result<Object, LoxResult> {
+        // Evaluate the indexed object and the index
+        let obj = self.evaluate(object)?;
+        let index = self.evaluate(index)?;
+
+        match obj {
+            Object::List(elements) => {
+                let elements = elements.borrow();
+                let i = list_index(&index, elements.len(), bracket)?;
+                Ok(elements[i].clone())
+            }
+            Object::Map(map) => {
+                let key = map_key(&index, bracket)?;
+                map.borrow().get(&key).ok_or_else(|| LoxResult::Runtime {
+                    token: bracket.clone(),
+                    error_type: RuntimeErrorType::UndefinedKey,
+                    trace: Vec::new(),
+                })
+            }
+            _ => Err(LoxResult::Runtime {
+                token: bracket.clone(),
+                error_type: RuntimeErrorType::InvalidIndexedObject,
+                trace: Vec::new(),
+            }),
+        }
+    }
+
+    /**
+     * Attempts to replace the element of a list at the given index (list[0] = value) or to set
+     * the value of a map at the given key (map["key"] = value).
+     *
+     * Note: If the `object` expression does not evaluate into an `Object::List` or an
+     * `Object::Map`, this function will return a `RuntimeErrorType::InvalidIndexedObject` error.
+     */
+    fn visit_setindex_expr(
+        &mut self,
+        object: &Expr,
+        bracket: &Token,
+        index: &Expr,
+        value: &Expr,
+    ) -> Result<Object, LoxResult> {
+        // Evaluate the indexed object, the index and the value to set
+        let obj = self.evaluate(object)?;
+        let index = self.evaluate(index)?;
+        let value = self.evaluate(value)?;
+
+        match obj {
+            Object::List(elements) => {
+                let mut elements = elements.borrow_mut();
+                let i = list_index(&index, elements.len(), bracket)?;
+                elements[i] = value.clone();
+                Ok(value)
+            }
+            Object::Map(map) => {
+                let key = map_key(&index, bracket)?;
+                map.borrow_mut().insert(key, index, value.clone());
+                Ok(value)
+            }
+            _ => Err(LoxResult::Runtime {
+                token: bracket.clone(),
+                error_type: RuntimeErrorType::InvalidIndexedObject,
+                trace: Vec::new(),
+            }),
+        }
+    }
+
     /**
      * Function called when trying to access `this` variable.
      */
-    fn visit_this_expr(&mut self, keyword: &Token) -> Result<Object, LoxResult> {
+    fn visit_this_expr(&mut self, id: &usize, keyword: &Token) -> Result<Object, LoxResult> {
         // Simply lookup a `this` variable as it should currently be defined locally
-        self.look_up_variable(keyword)
+        self.look_up_variable(*id, keyword)
     }
 
-    fn visit_super_expr(&mut self, keyword: &Token, method: &Token) -> Result<Object, LoxResult> {
-        let distance = self.locals.get(keyword).unwrap();
-        let superclass = self.environment.borrow_mut().get_at(*distance, keyword)?;
+    /**
+     * Gets a method of the superclass bound to `this`: super.method. In a class method, it is a
+     * static method of the superclass.
+     *
+     * Note: `super` is always resolved, and `this` lives in the environment right inside it.
+     */
+    fn visit_super_expr(
+        &mut self,
+        id: &usize,
+        keyword: &Token,
+        method: &Token,
+    ) -> Result<Object, LoxResult> {
+        let (distance, slot) = self.locals[id];
+        let superclass = self.environment.borrow().get_at(distance, slot, keyword)?;
 
-        let object = self.environment.borrow_mut().get_at(
+        let object = self.environment.borrow().get_at(
             distance - 1,
+            0,
             &Token {
+                ttype: TokenType::This,
                 lexeme: "this".to_string(),
                 ..Default::default()
             },
         )?;
 
         if let Object::Class(superclass) = superclass {
-            if let Some(class_method) = superclass.find_method(&method.lexeme) {
-                return Ok(Object::Function(Rc::new(class_method.bind(&object))));
+            // In a class method, `this` is the class whose static methods are found
+            let found = match object {
+                Object::Class(_) => superclass.find_class_method(&method.lexeme),
+                _ => superclass.find_method(&method.lexeme),
+            };
+            if let Some(class_method) = found {
+                let class_method = class_method.bind(&object);
+                // A getter is run as soon as it is accessed, as in `visit_get_expr`
+                if class_method.is_getter {
+                    return class_method.call(self, Vec::new(), None, method);
+                }
+                return Ok(Object::Function(Rc::new(class_method)));
             } else {
                 return Err(LoxResult::Runtime {
                     token: method.to_owned(),
                     error_type: RuntimeErrorType::UndefinedProperty,
+                    trace: Vec::new(),
                 });
             }
         }
@@ -554,11 +1225,17 @@ impl StmtVisitor<()> for Interpreter {
     /**
      * Evaluates an expression.
      *
-     * Note: If it evaluated to an error, print it.
+     * Note: If it evaluated to an error, write it to the diagnostics sink. Only the debugger
+     * stopping the program goes through.
      */
     fn visit_expression_stmt(&mut self, expression: &Expr) -> Result<(), LoxResult> {
-        if let Err(e) = self.evaluate(expression) {
-            eprintln!("{}", e);
+        match self.evaluate(expression) {
+            Err(e @ (LoxResult::Stop | LoxResult::Interrupted { .. })) => return Err(e),
+            // Thrown values and the errors a catch clause is waiting for go on
+            Err(e @ LoxResult::Thrown { .. }) => return Err(e),
+            Err(e) if self.catching > 0 => return Err(e),
+            Err(e) => self.report(e),
+            Ok(_) => {}
         }
 
         Ok(())
@@ -567,9 +1244,9 @@ impl StmtVisitor<()> for Interpreter {
     /**
      * Evaluate a print expression and prints its outcome.
      */
-    fn visit_print_stmt(&mut self, expression: &Expr) -> Result<(), LoxResult> {
+    fn visit_print_stmt(&mut self, _keyword: &Token, expression: &Expr) -> Result<(), LoxResult> {
         let value = self.evaluate(expression)?;
-        println!("{}", value);
+        self.output.write_line(&value.to_string());
 
         Ok(())
     }
@@ -591,9 +1268,24 @@ impl StmtVisitor<()> for Interpreter {
             value = self.evaluate(initializer.as_ref().unwrap())?;
         }
         // Define the newly declared variable in the current environment
+        self.environment.borrow_mut().define(&name.lexeme, value);
+
+        Ok(())
+    }
+
+    /**
+     * Imports a module and defines its namespace in the current environment.
+     */
+    fn visit_import_stmt(
+        &mut self,
+        _keyword: &Token,
+        name: &Token,
+        path: &Token,
+    ) -> Result<(), LoxResult> {
+        let module = self.import(path)?;
         self.environment
             .borrow_mut()
-            .define(name.lexeme.clone(), value);
+            .define(&name.lexeme, Object::Module(module));
 
         Ok(())
     }
@@ -603,9 +1295,12 @@ impl StmtVisitor<()> for Interpreter {
      */
     fn visit_block_stmt(&mut self, statements: &[Stmt]) -> Result<(), LoxResult> {
         // Create a new environment for the scope
-        let env = Environment::from_enclosing(Rc::clone(&self.environment));
+        let env = Rc::new(RefCell::new(Environment::from_enclosing(Rc::clone(
+            &self.environment,
+        ))));
+        heap::track(&env);
         // Execute the statements of the block in the new environment
-        self.execute_block(statements, Rc::new(RefCell::new(env)))
+        self.execute_block(statements, env)
     }
 
     /**
@@ -655,6 +1350,62 @@ impl StmtVisitor<()> for Interpreter {
         }
     }
 
+    /**
+     * Throws the value of the given expression, to be caught by the nearest enclosing `try`
+     * statement.
+     */
+    fn visit_throw_stmt(&mut self, keyword: &Token, value: &Expr) -> Result<(), LoxResult> {
+        Err(LoxResult::Thrown {
+            span: keyword.span(),
+            value: self.evaluate(value)?,
+            trace: Vec::new(),
+        })
+    }
+
+    /**
+     * Executes a try statement: its body, then its catch clause if the body threw a value or
+     * raised a runtime error, then its finally clause whatever happened.
+     *
+     * Note: An error or a jump (e.g. `return`) out of the finally clause replaces the outcome of
+     * the body and the catch clause. The debugger or the execution budget stopping the program
+     * skips the finally clause.
+     */
+    fn visit_try_stmt(
+        &mut self,
+        _keyword: &Token,
+        body: &[Stmt],
+        catch_name: &Option<Token>,
+        catch_body: &[Stmt],
+        finally_body: &[Stmt],
+    ) -> Result<(), LoxResult> {
+        let catching = usize::from(catch_name.is_some());
+        self.catching += catching;
+        let mut result = self.visit_block_stmt(body);
+        self.catching -= catching;
+
+        // Catch the value thrown by the body, if any
+        if let (Err(error), Some(name)) = (&mut result, catch_name) {
+            let error = std::mem::replace(error, LoxResult::Stop);
+            result = match caught_value(error) {
+                Ok(value) => {
+                    let env = Rc::new(RefCell::new(Environment::from_enclosing(Rc::clone(
+                        &self.environment,
+                    ))));
+                    heap::track(&env);
+                    env.borrow_mut().define(&name.lexeme, value);
+                    self.execute_block(catch_body, env)
+                }
+                Err(error) => Err(error),
+            };
+        }
+
+        if matches!(result, Err(LoxResult::Stop | LoxResult::Interrupted { .. })) {
+            return result;
+        }
+        self.visit_block_stmt(finally_body)?;
+        result
+    }
+
     /**
      * Exectute a while statement containing a condition and a body.
      * Note: we have to transform the given `while {}`
@@ -669,12 +1420,24 @@ impl StmtVisitor<()> for Interpreter {
      * loop {
      *    if !<condition> { break; }
      *    <body>
+     *    <increment>
      * }
      * `
      * here.
+     *
+     * Note: The body can be left early with the `LoxResult::Break` and `LoxResult::Continue`
+     * variants. The increment (of a desugared for loop) still runs after a `continue`.
      */
-    fn visit_while_stmt(&mut self, condition: &Expr, body: &Stmt) -> Result<(), LoxResult> {
+    fn visit_while_stmt(
+        &mut self,
+        condition: &Expr,
+        body: &Stmt,
+        increment: &Option<Expr>,
+    ) -> Result<(), LoxResult> {
         loop {
+            // Stop a runaway loop once out of the execution budget
+            self.budget.check(false)?;
+
             // Evaluate the condition
             let condition_value = self.evaluate(condition)?;
             // If the evaluated condition is false, break out of the loop
@@ -683,12 +1446,35 @@ impl StmtVisitor<()> for Interpreter {
             }
 
             // Execute the body
-            self.execute(body)?;
+            match self.execute(body) {
+                Ok(()) | Err(LoxResult::Continue) => {}
+                Err(LoxResult::Break) => break,
+                Err(e) => return Err(e),
+            }
+
+            // Execute the increment if any
+            if let Some(increment) = increment {
+                self.evaluate(increment)?;
+            }
         }
 
         Ok(())
     }
 
+    /**
+     * Leaves the enclosing loop using the `LoxResult::Break` variant.
+     */
+    fn visit_break_stmt(&mut self, _keyword: &Token) -> Result<(), LoxResult> {
+        Err(LoxResult::Break)
+    }
+
+    /**
+     * Skips to the next iteration of the enclosing loop using the `LoxResult::Continue` variant.
+     */
+    fn visit_continue_stmt(&mut self, _keyword: &Token) -> Result<(), LoxResult> {
+        Err(LoxResult::Continue)
+    }
+
     /**
      * Defines a new function in the current environment. A function is composed of
      * a name, an array of parameters and an array of statements that compose its body.
@@ -701,17 +1487,16 @@ impl StmtVisitor<()> for Interpreter {
     ) -> Result<(), LoxResult> {
         // Instanciate a new function object using its statement
         let function = Object::Function(Rc::new(LoxFunction {
-            name: name.clone(),
+            name: Some(name.clone()),
             params: params.to_vec(),
             body: body.to_vec(),
             closure: Rc::clone(&self.environment),
             is_init_function: false,
+            is_getter: false,
         }));
 
         // Define the function in the current environment
-        self.environment
-            .borrow_mut()
-            .define(name.lexeme.clone(), function);
+        self.environment.borrow_mut().define(&name.lexeme, function);
 
         Ok(())
     }
@@ -724,6 +1509,8 @@ impl StmtVisitor<()> for Interpreter {
         name: &Token,
         superclass: &Option<Expr>,
         methods: &[Stmt],
+        class_methods: &[Stmt],
+        getters: &[Stmt],
     ) -> Result<(), LoxResult> {
         let superclass_obj: Option<Rc<LoxClass>> = match superclass {
             Some(superclass) => {
@@ -733,61 +1520,99 @@ impl StmtVisitor<()> for Interpreter {
                     return Err(LoxResult::Runtime {
                         token: name.to_owned(),
                         error_type: RuntimeErrorType::SuperclassNotClass,
+                        trace: Vec::new(),
                     });
                 }
             }
             None => None,
         };
 
-        // Define the class in the environment as a null object for now
-        self.environment
-            .borrow_mut()
-            .define(name.lexeme.clone(), Object::Nil);
-
         // If we have a superclass, define a new environment here
-        if superclass_obj.is_some() {
+        if let Some(superclass_obj) = &superclass_obj {
             self.environment = Rc::new(RefCell::new(Environment::from_enclosing(Rc::clone(
                 &self.environment,
             ))));
-            self.environment.borrow_mut().define(
-                "super".to_owned(),
-                Object::Class(Rc::clone(superclass_obj.as_ref().unwrap())),
-            );
+            heap::track(&self.environment);
+            self.environment
+                .borrow_mut()
+                .define("super", Object::Class(Rc::clone(superclass_obj)));
         }
 
         // Interpret each defined class method into a `LoxFunction` object
-        let mut class_methods: HashMap<String, LoxFunction> = HashMap::new();
+        let mut instance_methods: HashMap<String, LoxFunction> = HashMap::new();
         for method in methods {
-            // Extract the name, body and param of the method
-            if let Stmt::Function { name, params, body } = method {
-                let function = LoxFunction {
-                    name: name.clone(),
-                    params: params.clone(),
-                    body: body.clone(),
-                    closure: Rc::clone(&self.environment),
-                    is_init_function: name.lexeme == "init",
-                };
-
-                // Put a `LoxFunction` struct into the hashmap
-                class_methods.insert(name.lexeme.clone(), function);
-            } else {
-                unreachable!()
-            };
+            let function = self.method(method, false);
+            instance_methods.insert(function.name.as_ref().unwrap().lexeme.clone(), function);
+        }
+        // Getters live alongside the other methods, flagged to be run on access
+        for getter in getters {
+            let function = self.method(getter, true);
+            instance_methods.insert(function.name.as_ref().unwrap().lexeme.clone(), function);
+        }
+        // Interpret each static method into a `LoxFunction` object
+        let mut static_methods: HashMap<String, LoxFunction> = HashMap::new();
+        for method in class_methods {
+            let mut function = self.method(method, false);
+            // Only the instance method 'init()' is an initializer
+            function.is_init_function = false;
+            static_methods.insert(function.name.as_ref().unwrap().lexeme.clone(), function);
         }
         if superclass_obj.is_some() {
-            let enclosed = self.environment.borrow_mut().enclosing.as_ref().unwrap();
-            self.environment = Rc::clone(&enclosed);
+            let enclosed = Rc::clone(self.environment.borrow().enclosing.as_ref().unwrap());
+            self.environment = enclosed;
         }
         // Instanciate a new `Object::Class` containing the name of the classs and its methods
         let class = Object::Class(Rc::new(LoxClass {
             name: name.lexeme.clone(),
-            methods: class_methods,
+            methods: instance_methods,
+            class_methods: static_methods,
             superclass: superclass_obj,
         }));
 
-        // Set the previously declared object in the environment as the newly created class object.
-        self.environment.borrow_mut().assign(name, class)?;
+        // Define the class in the environment. Its methods only look it up once called, so it
+        // doesn't need to be defined before they are created.
+        self.environment.borrow_mut().define(&name.lexeme, class);
 
         Ok(())
     }
 }
+
+/**
+ * Returns the value a catch clause gets for the given error: the thrown value, or an `Error`
+ * instance with the `message` and the `kind` of a runtime error (e.g. `UndefinedProperty`).
+ *
+ * Note: Returns the error back if it can't be caught, e.g. a `return` or the debugger stopping
+ * the program.
+ */
+fn caught_value(error: LoxResult) -> Result<Object, LoxResult> {
+    let kind = match error {
+        LoxResult::Thrown { value, .. } => return Ok(value),
+        LoxResult::Runtime { ref error_type, .. } => format!("{error_type:?}"),
+        LoxResult::Environment { ref error_type, .. } => format!("{error_type:?}"),
+        _ => return Err(error),
+    };
+
+    let class = Rc::new(LoxClass {
+        name: "Error".to_string(),
+        methods: HashMap::new(),
+        class_methods: HashMap::new(),
+        superclass: None,
+    });
+    let instance = Rc::new(LoxInstance::new(&class));
+    heap::track(&instance);
+    let mut fields = instance.fields.borrow_mut();
+    fields.insert("message".to_string(), Object::Str(error.message()));
+    fields.insert("kind".to_string(), Object::Str(kind));
+    drop(fields);
+
+    Ok(Object::Instance(instance))
+}
+
+/**
+ * Returns the current address of the native stack, to measure how much of it is used.
+ */
+#[inline(never)]
+fn stack_address() -> usize {
+    let marker = 0u8;
+    std::ptr::addr_of!(marker) as usize
+}
@@ -1,3 +1,16 @@
+pub mod budget;
+
+pub mod chunk;
+
+pub mod compiler;
+
+pub mod dap;
+
+pub mod debugger;
+pub mod debugger_cli;
+
+pub mod diagnostic;
+
 pub mod environment;
 
 pub mod errors;
@@ -5,23 +18,44 @@ pub mod errors;
 pub mod expr;
 pub mod stmt;
 
+pub mod formatter;
+
+pub mod heap;
+
 pub mod interpreter;
 
+pub mod lint;
+
+pub mod lox;
+
+pub mod lsp;
+
 pub mod lox_callable;
 pub mod lox_class;
 pub mod lox_function;
 pub mod lox_instance;
+pub mod lox_map;
+pub mod lox_module;
 pub mod lox_native;
 
 pub mod native_functions;
 
 pub mod object;
 
+pub mod output;
+
 pub mod parser;
 
+pub mod protocol;
+
 pub mod resolver;
 
 pub mod scanner;
 
+pub mod symbols;
+
 pub mod token;
 pub mod token_type;
+
+pub mod vm;
+pub mod vm_object;
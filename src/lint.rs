@@ -0,0 +1,863 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::errors::{LoxResult, Severity};
+use crate::expr::*;
+use crate::native_functions::{native_functions, NativeModule};
+use crate::object::Object;
+use crate::stmt::*;
+use crate::token::{Comment, Span, Token};
+
+/**
+ * The kind of a lint, named by a stable code (e.g. `unused_variable`) that can be allowed for a
+ * whole file with a `// lint: allow(unused_variable, shadowed_variable)` comment.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintCode {
+    /// A local variable, function or class that is never read.
+    UnusedVariable,
+    /// A parameter that is never read.
+    UnusedParameter,
+    /// A statement following a `return`, `break` or `continue`.
+    UnreachableCode,
+    /// A local variable declared with the name of a variable of an enclosing scope.
+    ShadowedVariable,
+    /// An assignment to a variable that is declared nowhere.
+    UndeclaredAssignment,
+    /// A call of a known function or class with the wrong number of arguments.
+    WrongArity,
+    /// A variable that is declared nowhere, e.g. a native function the interpreter lacks.
+    UndeclaredVariable,
+}
+
+impl LintCode {
+    const ALL: [LintCode; 7] = [
+        LintCode::UnusedVariable,
+        LintCode::UnusedParameter,
+        LintCode::UnreachableCode,
+        LintCode::ShadowedVariable,
+        LintCode::UndeclaredAssignment,
+        LintCode::WrongArity,
+        LintCode::UndeclaredVariable,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LintCode::UnusedVariable => "unused_variable",
+            LintCode::UnusedParameter => "unused_parameter",
+            LintCode::UnreachableCode => "unreachable_code",
+            LintCode::ShadowedVariable => "shadowed_variable",
+            LintCode::UndeclaredAssignment => "undeclared_assignment",
+            LintCode::WrongArity => "wrong_arity",
+            LintCode::UndeclaredVariable => "undeclared_variable",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<LintCode> {
+        LintCode::ALL.into_iter().find(|code| code.name() == name)
+    }
+
+    /**
+     * Returns the stable code of the lint in diagnostics, e.g. `W0001`.
+     */
+    pub fn code(&self) -> &'static str {
+        match self {
+            LintCode::UnusedVariable => "W0001",
+            LintCode::UnusedParameter => "W0002",
+            LintCode::UnreachableCode => "W0003",
+            LintCode::ShadowedVariable => "W0004",
+            LintCode::UndeclaredAssignment => "W0005",
+            LintCode::WrongArity => "W0006",
+            LintCode::UndeclaredVariable => "W0007",
+        }
+    }
+
+    /**
+     * Shadowing a variable is often on purpose, so it is only a note.
+     */
+    pub fn severity(&self) -> Severity {
+        match self {
+            LintCode::ShadowedVariable => Severity::Note,
+            _ => Severity::Warning,
+        }
+    }
+
+    pub fn help(&self) -> Option<&'static str> {
+        match self {
+            LintCode::UnusedVariable | LintCode::UnusedParameter => {
+                Some("Start the name with '_' if it is meant to be unused.")
+            }
+            LintCode::UnreachableCode => Some("Remove it, or move it before the jump."),
+            LintCode::ShadowedVariable => None,
+            LintCode::UndeclaredAssignment => {
+                Some("Declare the variable with 'var' before assigning it.")
+            }
+            LintCode::WrongArity => None,
+            LintCode::UndeclaredVariable => {
+                Some("Declare the variable with 'var' before using it.")
+            }
+        }
+    }
+}
+
+/**
+ * A warning about code that is valid but most likely wrong.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lint {
+    pub code: LintCode,
+    /// The location of the offending code.
+    pub span: Span,
+    pub message: String,
+}
+
+/**
+ * Returns the lints of a program, in the order of the source, leaving out the codes allowed by
+ * its comments.
+ *
+ * Note: Only the locals can be unused or shadowed, the globals may be used by code run later on.
+ */
+pub fn lint(statements: &[Stmt], comments: &[Comment]) -> Vec<Lint> {
+    let natives = native_functions(&NativeModule::ALL)
+        .into_iter()
+        .map(|(name, function)| (name.to_string(), Some(function.arity())))
+        .collect::<Vec<(String, Option<usize>)>>();
+    lint_with_globals(statements, comments, &natives)
+}
+
+/**
+ * Same as `lint`, for a program run along with the given globals (e.g. the natives and the
+ * variables of a session), each with its number of arguments if it is a function.
+ */
+pub fn lint_with_globals(
+    statements: &[Stmt],
+    comments: &[Comment],
+    globals: &[(String, Option<usize>)],
+) -> Vec<Lint> {
+    let mut linter = Linter {
+        lints: Vec::new(),
+        declarations: Vec::new(),
+        scopes: Vec::new(),
+        globals: HashMap::new(),
+        declared_globals: HashSet::new(),
+        calls: Vec::new(),
+    };
+
+    // Globals can be used before being declared, so they are all known beforehand
+    for (name, arity) in globals {
+        let name = Token {
+            lexeme: name.clone(),
+            ..Token::default()
+        };
+        let kind = match arity {
+            Some(_) => DeclarationKind::Function,
+            None => DeclarationKind::Variable,
+        };
+        linter.add_global(&name, kind, *arity);
+        linter.declared_globals.insert(name.lexeme);
+    }
+    for statement in statements {
+        match statement {
+            Stmt::Var { name, .. } | Stmt::Import { name, .. } => {
+                linter.add_global(name, DeclarationKind::Variable, None)
+            }
+            Stmt::Function { name, params, .. } => {
+                linter.add_global(name, DeclarationKind::Function, Some(params.len()))
+            }
+            Stmt::Class {
+                name,
+                superclass,
+                methods,
+                ..
+            } => linter.add_global(
+                name,
+                DeclarationKind::Class,
+                class_arity(superclass, methods),
+            ),
+            _ => {}
+        }
+    }
+
+    linter.statements(statements);
+    linter.check_calls();
+
+    let allowed = allowed_codes(comments);
+    let mut lints = linter
+        .lints
+        .into_iter()
+        .filter(|lint| !allowed.contains(&lint.code))
+        .collect::<Vec<Lint>>();
+    lints.sort_by_key(|lint| lint.span.start);
+
+    lints
+}
+
+/**
+ * Returns the codes allowed by the `// lint: allow(...)` comments of a file.
+ */
+fn allowed_codes(comments: &[Comment]) -> Vec<LintCode> {
+    comments
+        .iter()
+        .filter_map(|comment| {
+            comment.text[2..]
+                .trim()
+                .strip_prefix("lint:")?
+                .trim()
+                .strip_prefix("allow(")?
+                .strip_suffix(')')
+        })
+        .flat_map(|names| names.split(','))
+        .filter_map(|name| LintCode::from_name(name.trim()))
+        .collect()
+}
+
+/**
+ * Returns the number of arguments a class is called with, if it is known.
+ */
+fn class_arity(superclass: &Option<Expr>, methods: &[Stmt]) -> Option<usize> {
+    let init = methods.iter().find_map(|method| match method {
+        Stmt::Function { name, params, .. } if name.lexeme == "init" => Some(params.len()),
+        _ => None,
+    });
+
+    // Without an initializer, a subclass takes the arguments of its superclass
+    match (init, superclass) {
+        (Some(arity), _) => Some(arity),
+        (None, None) => Some(0),
+        (None, Some(_)) => None,
+    }
+}
+
+/**
+ * Tells if a statement never lets the execution go on to the next one.
+ */
+fn diverges(statement: &Stmt) -> bool {
+    match statement {
+        Stmt::Return { .. } | Stmt::Break { .. } | Stmt::Continue { .. } | Stmt::Throw { .. } => {
+            true
+        }
+        Stmt::Block { statements } => statements.iter().any(diverges),
+        // The catch clause goes on after the body, the finally clause after both
+        Stmt::Try {
+            body,
+            catch_name,
+            catch_body,
+            finally_body,
+            ..
+        } => {
+            (body.iter().any(diverges) && (catch_name.is_none() || catch_body.iter().any(diverges)))
+                || finally_body.iter().any(diverges)
+        }
+        Stmt::If {
+            then_branch,
+            else_branch,
+            ..
+        } => match else_branch.as_ref() {
+            Some(else_branch) => diverges(then_branch) && diverges(else_branch),
+            None => false,
+        },
+        _ => false,
+    }
+}
+
+/**
+ * Returns the location of the first token of a statement, if it has one.
+ */
+fn statement_span(statement: &Stmt) -> Option<Span> {
+    match statement {
+        Stmt::Block { statements } => statements.first().and_then(statement_span),
+        Stmt::Break { keyword }
+        | Stmt::Continue { keyword }
+        | Stmt::Import { keyword, .. }
+        | Stmt::Print { keyword, .. }
+        | Stmt::Return { keyword, .. }
+        | Stmt::Throw { keyword, .. }
+        | Stmt::Try { keyword, .. } => Some(keyword.span()),
+        Stmt::Class { name, .. } | Stmt::Function { name, .. } | Stmt::Var { name, .. } => {
+            Some(name.span())
+        }
+        Stmt::Expression { expression } => expression_span(expression),
+        Stmt::If { condition, .. } | Stmt::While { condition, .. } => expression_span(condition),
+    }
+}
+
+/**
+ * Returns the location of the first token of an expression, if it has one.
+ */
+fn expression_span(expression: &Expr) -> Option<Span> {
+    match expression {
+        Expr::Assign { name, .. } | Expr::Variable { name, .. } => Some(name.span()),
+        Expr::Binary { left, .. } | Expr::Logical { left, .. } => expression_span(left),
+        Expr::Call { callee, .. } => expression_span(callee),
+        Expr::Get { object, .. }
+        | Expr::Index { object, .. }
+        | Expr::Set { object, .. }
+        | Expr::SetIndex { object, .. } => expression_span(object),
+        Expr::Grouping { expression } => expression_span(expression),
+        Expr::Lambda { keyword, .. } | Expr::Super { keyword, .. } | Expr::This { keyword, .. } => {
+            Some(keyword.span())
+        }
+        Expr::List { bracket, .. } => Some(bracket.span()),
+        Expr::Map { brace, .. } => Some(brace.span()),
+        Expr::Unary { operator, .. } => Some(operator.span()),
+        Expr::Literal { token, .. } => Some(token.span()),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum DeclarationKind {
+    Variable,
+    Parameter,
+    Function,
+    Class,
+}
+
+/**
+ * A declared name, along with how the code uses it.
+ */
+struct Declaration {
+    name: Token,
+    kind: DeclarationKind,
+    /// The number of arguments of a function or class, if it is known.
+    arity: Option<usize>,
+    /// Whether the value of the name is read.
+    used: bool,
+    /// Whether the name is assigned another value, which may have another arity.
+    assigned: bool,
+}
+
+/**
+ * A call of a variable, checked once every assignment is known.
+ */
+struct Call {
+    callee: Token,
+    declaration: usize,
+    arguments: usize,
+}
+
+/**
+ * Walks a program to find its lints.
+ */
+struct Linter {
+    lints: Vec<Lint>,
+    declarations: Vec<Declaration>,
+    /// The local scopes, each mapping a name to its declaration.
+    scopes: Vec<HashMap<String, usize>>,
+    /// The declarations of each global name.
+    globals: HashMap<String, Vec<usize>>,
+    /// The global names declared so far in the walk, only those can be shadowed by a local.
+    declared_globals: HashSet<String>,
+    calls: Vec<Call>,
+}
+
+impl Linter {
+    fn report(&mut self, code: LintCode, span: Span, message: String) {
+        self.lints.push(Lint {
+            code,
+            span,
+            message,
+        });
+    }
+
+    fn add_declaration(
+        &mut self,
+        name: &Token,
+        kind: DeclarationKind,
+        arity: Option<usize>,
+    ) -> usize {
+        self.declarations.push(Declaration {
+            name: name.clone(),
+            kind,
+            arity,
+            used: false,
+            assigned: false,
+        });
+        self.declarations.len() - 1
+    }
+
+    fn add_global(&mut self, name: &Token, kind: DeclarationKind, arity: Option<usize>) {
+        let declaration = self.add_declaration(name, kind, arity);
+        self.globals
+            .entry(name.lexeme.clone())
+            .or_default()
+            .push(declaration);
+    }
+
+    /**
+     * Declares a name in the current scope. Globals were added beforehand, they are only marked
+     * as declared once the walk reaches them.
+     */
+    fn declare(&mut self, name: &Token, kind: DeclarationKind, arity: Option<usize>) {
+        if self.scopes.is_empty() {
+            self.declared_globals.insert(name.lexeme.clone());
+            return;
+        }
+
+        let shadowed = self.scopes[..self.scopes.len() - 1]
+            .iter()
+            .any(|scope| scope.contains_key(&name.lexeme))
+            || self.declared_globals.contains(&name.lexeme);
+        if shadowed {
+            self.report(
+                LintCode::ShadowedVariable,
+                name.span(),
+                format!(
+                    "'{}' shadows a variable of an enclosing scope.",
+                    name.lexeme
+                ),
+            );
+        }
+
+        let declaration = self.add_declaration(name, kind, arity);
+        self.scopes
+            .last_mut()
+            .unwrap()
+            .insert(name.lexeme.clone(), declaration);
+    }
+
+    fn begin_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    /**
+     * Leaves a scope, reporting the names it declared that were never read. Names starting
+     * with `_` are meant to be unused.
+     */
+    fn end_scope(&mut self) {
+        let scope = self.scopes.pop().unwrap();
+        let mut unused = scope
+            .into_values()
+            .filter(|&declaration| {
+                let declaration = &self.declarations[declaration];
+                !declaration.used && !declaration.name.lexeme.starts_with('_')
+            })
+            .collect::<Vec<usize>>();
+        unused.sort();
+
+        for declaration in unused {
+            let Declaration { name, kind, .. } = &self.declarations[declaration];
+            let (code, message) = match kind {
+                DeclarationKind::Parameter => (
+                    LintCode::UnusedParameter,
+                    format!("Unused parameter '{}'.", name.lexeme),
+                ),
+                _ => (
+                    LintCode::UnusedVariable,
+                    format!("Unused variable '{}'.", name.lexeme),
+                ),
+            };
+            let span = name.span();
+            self.report(code, span, message);
+        }
+    }
+
+    /**
+     * Returns the declaration a variable stands for: the innermost local declaring it or, failing
+     * that, the global declared only once with that name.
+     */
+    fn lookup(&self, name: &Token) -> Option<usize> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(&name.lexeme).copied())
+            .or_else(|| match self.globals.get(&name.lexeme) {
+                Some(declarations) if declarations.len() == 1 => Some(declarations[0]),
+                _ => None,
+            })
+    }
+
+    /**
+     * Visits a list of statements, reporting the first one that can't be reached.
+     */
+    fn statements(&mut self, statements: &[Stmt]) {
+        let mut reported = false;
+        for (i, statement) in statements.iter().enumerate() {
+            if !reported && i > 0 && diverges(&statements[i - 1]) {
+                let span = statement_span(statement).or_else(|| statement_span(&statements[i - 1]));
+                if let Some(span) = span {
+                    self.report(
+                        LintCode::UnreachableCode,
+                        span,
+                        "Unreachable code.".to_string(),
+                    );
+                }
+                reported = true;
+            }
+            // Linting never fails
+            let _ = statement.accept(self);
+        }
+    }
+
+    fn expression(&mut self, expression: &Expr) {
+        let _ = expression.accept(self);
+    }
+
+    /**
+     * Visits the parameters and body of a function in their own scope.
+     */
+    fn function(&mut self, params: &[Token], body: &[Stmt]) {
+        self.begin_scope();
+        for param in params {
+            self.declare(param, DeclarationKind::Parameter, None);
+        }
+        self.statements(body);
+        self.end_scope();
+    }
+
+    /**
+     * Reports the calls with the wrong number of arguments, once every assignment is known.
+     */
+    fn check_calls(&mut self) {
+        for call in std::mem::take(&mut self.calls) {
+            let declaration = &self.declarations[call.declaration];
+            let Some(arity) = declaration.arity else {
+                continue;
+            };
+            if declaration.assigned || arity == call.arguments {
+                continue;
+            }
+
+            let what = match declaration.kind {
+                DeclarationKind::Class => "Class",
+                _ => "Function",
+            };
+            self.report(
+                LintCode::WrongArity,
+                call.callee.span(),
+                format!(
+                    "{what} '{}' takes {arity} argument(s) but is called with {}.",
+                    call.callee.lexeme, call.arguments
+                ),
+            );
+        }
+    }
+}
+
+impl StmtVisitor<()> for Linter {
+    fn visit_block_stmt(&mut self, statements: &[Stmt]) -> Result<(), LoxResult> {
+        self.begin_scope();
+        self.statements(statements);
+        self.end_scope();
+        Ok(())
+    }
+
+    fn visit_break_stmt(&mut self, _keyword: &Token) -> Result<(), LoxResult> {
+        Ok(())
+    }
+
+    fn visit_class_stmt(
+        &mut self,
+        name: &Token,
+        superclass: &Option<Expr>,
+        methods: &[Stmt],
+        class_methods: &[Stmt],
+        getters: &[Stmt],
+    ) -> Result<(), LoxResult> {
+        let arity = class_arity(superclass, methods);
+        self.declare(name, DeclarationKind::Class, arity);
+        if let Some(superclass) = superclass {
+            self.expression(superclass);
+        }
+
+        for method in methods.iter().chain(class_methods).chain(getters) {
+            if let Stmt::Function { params, body, .. } = method {
+                self.function(params, body);
+            } else {
+                unreachable!()
+            }
+        }
+
+        Ok(())
+    }
+
+    fn visit_continue_stmt(&mut self, _keyword: &Token) -> Result<(), LoxResult> {
+        Ok(())
+    }
+
+    fn visit_expression_stmt(&mut self, expression: &Expr) -> Result<(), LoxResult> {
+        self.expression(expression);
+        Ok(())
+    }
+
+    fn visit_function_stmt(
+        &mut self,
+        name: &Token,
+        params: &[Token],
+        body: &[Stmt],
+    ) -> Result<(), LoxResult> {
+        self.declare(name, DeclarationKind::Function, Some(params.len()));
+        self.function(params, body);
+        Ok(())
+    }
+
+    fn visit_if_stmt(
+        &mut self,
+        condition: &Expr,
+        then_branch: &Stmt,
+        else_branch: &Option<Stmt>,
+    ) -> Result<(), LoxResult> {
+        self.expression(condition);
+        then_branch.accept(self)?;
+        if let Some(else_branch) = else_branch {
+            else_branch.accept(self)?;
+        }
+        Ok(())
+    }
+
+    fn visit_import_stmt(
+        &mut self,
+        _keyword: &Token,
+        name: &Token,
+        _path: &Token,
+    ) -> Result<(), LoxResult> {
+        self.declare(name, DeclarationKind::Variable, None);
+        Ok(())
+    }
+
+    fn visit_print_stmt(&mut self, _keyword: &Token, expression: &Expr) -> Result<(), LoxResult> {
+        self.expression(expression);
+        Ok(())
+    }
+
+    fn visit_return_stmt(
+        &mut self,
+        _keyword: &Token,
+        value: &Option<Expr>,
+    ) -> Result<(), LoxResult> {
+        if let Some(value) = value {
+            self.expression(value);
+        }
+        Ok(())
+    }
+
+    fn visit_throw_stmt(&mut self, _keyword: &Token, value: &Expr) -> Result<(), LoxResult> {
+        self.expression(value);
+        Ok(())
+    }
+
+    fn visit_try_stmt(
+        &mut self,
+        _keyword: &Token,
+        body: &[Stmt],
+        catch_name: &Option<Token>,
+        catch_body: &[Stmt],
+        finally_body: &[Stmt],
+    ) -> Result<(), LoxResult> {
+        self.visit_block_stmt(body)?;
+        if let Some(name) = catch_name {
+            self.begin_scope();
+            self.declare(name, DeclarationKind::Variable, None);
+            self.statements(catch_body);
+            self.end_scope();
+        }
+        self.visit_block_stmt(finally_body)
+    }
+
+    fn visit_var_stmt(
+        &mut self,
+        name: &Token,
+        initializer: &Option<Expr>,
+    ) -> Result<(), LoxResult> {
+        if let Some(initializer) = initializer {
+            self.expression(initializer);
+        }
+        self.declare(name, DeclarationKind::Variable, None);
+        Ok(())
+    }
+
+    fn visit_while_stmt(
+        &mut self,
+        condition: &Expr,
+        body: &Stmt,
+        increment: &Option<Expr>,
+    ) -> Result<(), LoxResult> {
+        self.expression(condition);
+        body.accept(self)?;
+        if let Some(increment) = increment {
+            self.expression(increment);
+        }
+        Ok(())
+    }
+}
+
+impl ExprVisitor<()> for Linter {
+    fn visit_assign_expr(
+        &mut self,
+        _id: &usize,
+        name: &Token,
+        value: &Expr,
+    ) -> Result<(), LoxResult> {
+        self.expression(value);
+        match self.lookup(name) {
+            Some(declaration) => self.declarations[declaration].assigned = true,
+            None if self.globals.contains_key(&name.lexeme) => {
+                // A global declared more than once
+                for &declaration in &self.globals[&name.lexeme] {
+                    self.declarations[declaration].assigned = true;
+                }
+            }
+            None => self.report(
+                LintCode::UndeclaredAssignment,
+                name.span(),
+                format!("Assignment to the undeclared variable '{}'.", name.lexeme),
+            ),
+        }
+        Ok(())
+    }
+
+    fn visit_binary_expr(
+        &mut self,
+        left: &Expr,
+        _operator: &Token,
+        right: &Expr,
+    ) -> Result<(), LoxResult> {
+        self.expression(left);
+        self.expression(right);
+        Ok(())
+    }
+
+    fn visit_call_expr(
+        &mut self,
+        callee: &Expr,
+        _paren: &Token,
+        arguments: &[Expr],
+    ) -> Result<(), LoxResult> {
+        self.expression(callee);
+        arguments
+            .iter()
+            .for_each(|argument| self.expression(argument));
+
+        if let Expr::Variable { name, .. } = callee {
+            if let Some(declaration) = self.lookup(name) {
+                self.calls.push(Call {
+                    callee: name.clone(),
+                    declaration,
+                    arguments: arguments.len(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_get_expr(&mut self, object: &Expr, _name: &Token) -> Result<(), LoxResult> {
+        self.expression(object);
+        Ok(())
+    }
+
+    fn visit_lambda_expr(
+        &mut self,
+        _keyword: &Token,
+        params: &[Token],
+        body: &[Stmt],
+    ) -> Result<(), LoxResult> {
+        self.function(params, body);
+        Ok(())
+    }
+
+    fn visit_index_expr(
+        &mut self,
+        object: &Expr,
+        _bracket: &Token,
+        index: &Expr,
+    ) -> Result<(), LoxResult> {
+        self.expression(object);
+        self.expression(index);
+        Ok(())
+    }
+
+    fn visit_list_expr(&mut self, _bracket: &Token, elements: &[Expr]) -> Result<(), LoxResult> {
+        elements.iter().for_each(|element| self.expression(element));
+        Ok(())
+    }
+
+    fn visit_logical_expr(
+        &mut self,
+        left: &Expr,
+        _operator: &Token,
+        right: &Expr,
+    ) -> Result<(), LoxResult> {
+        self.expression(left);
+        self.expression(right);
+        Ok(())
+    }
+
+    fn visit_map_expr(
+        &mut self,
+        _brace: &Token,
+        keys: &[Expr],
+        values: &[Expr],
+    ) -> Result<(), LoxResult> {
+        keys.iter()
+            .chain(values)
+            .for_each(|expression| self.expression(expression));
+        Ok(())
+    }
+
+    fn visit_set_expr(
+        &mut self,
+        object: &Expr,
+        _name: &Token,
+        value: &Expr,
+    ) -> Result<(), LoxResult> {
+        self.expression(value);
+        self.expression(object);
+        Ok(())
+    }
+
+    fn visit_setindex_expr(
+        &mut self,
+        object: &Expr,
+        _bracket: &Token,
+        index: &Expr,
+        value: &Expr,
+    ) -> Result<(), LoxResult> {
+        self.expression(value);
+        self.expression(object);
+        self.expression(index);
+        Ok(())
+    }
+
+    fn visit_super_expr(
+        &mut self,
+        _id: &usize,
+        _keyword: &Token,
+        _method: &Token,
+    ) -> Result<(), LoxResult> {
+        Ok(())
+    }
+
+    fn visit_this_expr(&mut self, _id: &usize, _keyword: &Token) -> Result<(), LoxResult> {
+        Ok(())
+    }
+
+    fn visit_unary_expr(&mut self, _operator: &Token, right: &Expr) -> Result<(), LoxResult> {
+        self.expression(right);
+        Ok(())
+    }
+
+    fn visit_grouping_expr(&mut self, expression: &Expr) -> Result<(), LoxResult> {
+        self.expression(expression);
+        Ok(())
+    }
+
+    fn visit_literal_expr(
+        &mut self,
+        _token: &Token,
+        _value: &Option<Object>,
+    ) -> Result<(), LoxResult> {
+        Ok(())
+    }
+
+    fn visit_variable_expr(&mut self, _id: &usize, name: &Token) -> Result<(), LoxResult> {
+        match self.lookup(name) {
+            Some(declaration) => self.declarations[declaration].used = true,
+            // A global declared more than once
+            None if self.globals.contains_key(&name.lexeme) => {}
+            None => self.report(
+                LintCode::UndeclaredVariable,
+                name.span(),
+                format!("Use of the undeclared variable '{}'.", name.lexeme),
+            ),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,305 @@
+use std::fs;
+
+use crate::compiler::Compiler;
+use crate::diagnostic::SourceMap;
+use crate::errors::{EvalError, LoxResult, Severity};
+use crate::heap;
+use crate::interpreter::Interpreter;
+use crate::lint;
+use crate::lox_callable::LoxCallable;
+use crate::object::Object;
+use crate::parser::Parser;
+use crate::resolver::Resolver;
+use crate::scanner::Scanner;
+use crate::stmt::Stmt;
+use crate::token::Token;
+use crate::token_type::TokenType;
+use crate::vm::Vm;
+
+/**
+ * The way a `Lox` session runs the code once it is parsed and resolved.
+ */
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// Walk the syntax tree with the `Interpreter`.
+    #[default]
+    TreeWalk,
+    /// Compile the syntax tree to bytecode and run it on the `Vm`.
+    ///
+    /// Note: Imports and exceptions (`throw`, `try`) are compiler errors on this backend, and
+    /// the execution budget, the debugger, the sources of the imported modules and the
+    /// tracebacks of the runtime errors are only those of the tree-walking backend.
+    Vm,
+}
+
+/**
+ * A Lox session. It runs source code through the whole
+ * scanner -> parser -> resolver -> interpreter (or compiler -> vm) pipeline.
+ *
+ * Note: The global environment is kept between evaluations, so a variable defined by a
+ * snippet can be used by the next one.
+ */
+pub struct Lox {
+    /// The interpreter holding the state of the session on the tree-walking backend.
+    interpreter: Interpreter,
+    /// The vm holding the state of the session on the bytecode backend.
+    vm: Vm,
+    /// The backend running the code.
+    backend: Backend,
+    /// Whether the code is linted before being run.
+    lint: bool,
+    /// Whether warnings stop the code from being run, as errors do.
+    deny_warnings: bool,
+    /// The warnings and notes of the last code run.
+    warnings: Vec<LoxResult>,
+}
+
+impl Default for Lox {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Lox {
+    /**
+     * Instanciates a new session with a fresh interpreter.
+     */
+    pub fn new() -> Self {
+        Self::with_backend(Backend::TreeWalk)
+    }
+
+    /**
+     * Instanciates a new session running the code on the given backend. Only the tree-walking
+     * one supports every feature of the language, see `Backend::Vm`.
+     */
+    pub fn with_backend(backend: Backend) -> Self {
+        Self::with_interpreter(Interpreter::new(), backend)
+    }
+
+    /**
+     * Instanciates a new session running the code on the given backend, with the given
+     * interpreter (e.g. one built with a sandboxed set of native functions). The vm defines the
+     * same native functions as the interpreter.
+     */
+    pub fn with_interpreter(interpreter: Interpreter, backend: Backend) -> Self {
+        Lox {
+            vm: Vm::with_natives(interpreter.natives()),
+            interpreter,
+            backend,
+            lint: false,
+            deny_warnings: false,
+            warnings: Vec::new(),
+        }
+    }
+
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+
+    /**
+     * Enables or disables the lints of the code (disabled by default, the command line enables
+     * them to run a script). Each snippet is linted along with the globals of the session.
+     */
+    pub fn set_lint(&mut self, lint: bool) {
+        self.lint = lint;
+    }
+
+    /**
+     * Makes the warnings of the code stop it from being run, as errors do. Notes never do.
+     *
+     * Note: Only the code being linted has warnings, see `set_lint`.
+     */
+    pub fn set_deny_warnings(&mut self, deny_warnings: bool) {
+        self.deny_warnings = deny_warnings;
+    }
+
+    /**
+     * Returns the warnings and notes of the last code run, unless they were already taken.
+     */
+    pub fn take_warnings(&mut self) -> Vec<LoxResult> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /**
+     * Renders the runtime errors the code reports without stopping (e.g. those of expression
+     * statements) against the given source file, as `SourceFile::render` does, on both backends.
+     */
+    pub fn set_source_file(&mut self, name: &str, source: &str) {
+        self.interpreter.set_source_file(name, source);
+        self.vm.set_source_file(name, source);
+    }
+
+    /**
+     * Returns the interpreter used by this session on the tree-walking backend.
+     */
+    pub fn interpreter(&mut self) -> &mut Interpreter {
+        &mut self.interpreter
+    }
+
+    /**
+     * Returns the source code of the modules the session imported, which errors may point at
+     * besides the code it was given (see `SourceFile::with_sources`).
+     */
+    pub fn sources(&self) -> &SourceMap {
+        self.interpreter.sources()
+    }
+
+    /**
+     * Returns the vm used by this session on the bytecode backend.
+     */
+    pub fn vm(&mut self) -> &mut Vm {
+        &mut self.vm
+    }
+
+    /**
+     * Runs the given source code.
+     *
+     * Returns the value of the last statement if it is an expression statement (e.g. `1 + 2;`),
+     * `Object::Nil` otherwise.
+     */
+    pub fn eval_source(&mut self, source: &str) -> Result<Object, EvalError> {
+        // Scan the source code into tokens
+        let mut scanner = Scanner::new(source);
+        let tokens = scanner.scan_tokens().map_err(EvalError::Scanner)?;
+
+        // Parse the tokens and stop if there was any syntax error
+        let mut parser = Parser::new(tokens);
+        let (mut statements, errors) = parser.parse();
+        if !errors.is_empty() {
+            return Err(EvalError::Parser(errors));
+        }
+
+        // Lint the program, its warnings only stop it when they are denied
+        self.warnings.clear();
+        if self.lint {
+            let warnings = lint::lint_with_globals(&statements, &scanner.comments, &self.globals())
+                .into_iter()
+                .map(|lint| LoxResult::Warning { lint })
+                .collect::<Vec<LoxResult>>();
+            if self.deny_warnings
+                && warnings
+                    .iter()
+                    .any(|warning| warning.severity() >= Severity::Warning)
+            {
+                return Err(EvalError::Warnings(warnings));
+            }
+            self.warnings = warnings;
+        }
+
+        // The bytecode backend only needs the resolver to check the program
+        if self.backend == Backend::Vm {
+            Resolver::checker()
+                .resolve_stmts(&statements)
+                .map_err(EvalError::Resolver)?;
+
+            let function = Compiler::new()
+                .compile(&statements)
+                .map_err(EvalError::Compiler)?;
+            return self.vm.interpret(function).map_err(EvalError::Runtime);
+        }
+
+        // Resolve the variables of the program
+        let mut resolver = Resolver::new(&mut self.interpreter);
+        resolver
+            .resolve_stmts(&statements)
+            .map_err(EvalError::Resolver)?;
+
+        // Keep the trailing expression statement apart so that its value can be returned
+        let last_expression = match statements.last() {
+            Some(Stmt::Expression { .. }) => statements.pop(),
+            _ => None,
+        };
+
+        self.interpreter
+            .interpret(&statements)
+            .map_err(EvalError::Runtime)?;
+
+        match last_expression {
+            Some(statement) => self
+                .interpreter
+                .execute_expression(&statement)
+                .map_err(EvalError::Runtime),
+            None => Ok(Object::Nil),
+        }
+    }
+
+    /**
+     * Reads the file at `path` and runs it as source code. Its imports are relative to its
+     * directory.
+     */
+    pub fn eval_file(&mut self, path: &str) -> Result<Object, EvalError> {
+        let source = fs::read_to_string(path).map_err(|_| EvalError::Io(LoxResult::IOError))?;
+        self.interpreter.set_script(path);
+        self.eval_source(&source)
+    }
+
+    /**
+     * Defines (or redefines) the global variable `name` so that scripts can use it.
+     */
+    pub fn define_global(&mut self, name: &str, value: Object) {
+        if self.backend == Backend::Vm {
+            return self.vm.define_global(name, value);
+        }
+
+        self.interpreter
+            .env_globals
+            .borrow_mut()
+            .define(name, value);
+    }
+
+    /**
+     * Returns the value of the global variable `name`, or `None` if it isn't defined.
+     */
+    pub fn get_global(&self, name: &str) -> Option<Object> {
+        if self.backend == Backend::Vm {
+            return self.vm.get_global(name);
+        }
+
+        let token = Token::identifier(0, 0, 0, TokenType::Identifier, name);
+        self.interpreter.env_globals.borrow().get(&token).ok()
+    }
+
+    /**
+     * Returns the names of the global variables of the session, along with their number of
+     * arguments if they are functions.
+     */
+    fn globals(&self) -> Vec<(String, Option<usize>)> {
+        let globals = match self.backend {
+            Backend::TreeWalk => self.interpreter.env_globals.borrow().variables(),
+            Backend::Vm => self.vm.globals(),
+        };
+
+        globals
+            .into_iter()
+            .map(|(name, value)| {
+                let arity = match value {
+                    Object::Function(function) => Some(function.arity()),
+                    Object::Native(function) => Some(function.arity()),
+                    Object::Class(class) => Some(class.arity()),
+                    Object::Closure(closure) => Some(closure.function.arity()),
+                    _ => None,
+                };
+                (name, arity)
+            })
+            .collect()
+    }
+
+    /**
+     * Returns the number of heap objects (environments, instances, lists, maps, classes and
+     * captured variables) still alive.
+     *
+     * Note: The heap is shared by every session of the current thread.
+     */
+    pub fn live_objects(&self) -> usize {
+        heap::live_objects()
+    }
+
+    /**
+     * Frees the heap objects only kept alive by reference cycles, and returns their number.
+     *
+     * Note: Collections also run on their own as objects get allocated.
+     */
+    pub fn collect_garbage(&mut self) -> usize {
+        heap::collect()
+    }
+}
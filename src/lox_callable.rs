@@ -4,13 +4,19 @@ use crate::errors::LoxResult;
 use crate::interpreter::Interpreter;
 use crate::lox_class::LoxClass;
 use crate::object::Object;
+use crate::token::Token;
 
 pub trait LoxCallable {
+    /**
+     * Calls the object with the given arguments. `paren` is the closing parenthesis of the call
+     * expression, used to report errors.
+     */
     fn call(
         &self,
         interpreter: &mut Interpreter,
         arguments: Vec<Object>,
         class: Option<Rc<LoxClass>>,
+        paren: &Token,
     ) -> Result<Object, LoxResult>;
     fn arity(&self) -> usize;
 }
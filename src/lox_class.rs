@@ -3,11 +3,13 @@ use std::fmt;
 use std::rc::Rc;
 
 use crate::errors::LoxResult;
+use crate::heap;
 use crate::interpreter::Interpreter;
 use crate::lox_callable::LoxCallable;
 use crate::lox_function::LoxFunction;
 use crate::lox_instance::LoxInstance;
 use crate::object::Object;
+use crate::token::Token;
 
 /**
  * This structure represents a Lox class. It contains the name of the class as well as the list of
@@ -19,6 +21,8 @@ pub struct LoxClass {
     pub name: String,
     /// A map of defined functions for this class.
     pub methods: HashMap<String, LoxFunction>,
+    /// A map of static functions, called on the class itself (e.g. `Math.square(2)`).
+    pub class_methods: HashMap<String, LoxFunction>,
     /// Optional superclass
     pub superclass: Option<Rc<LoxClass>>,
 }
@@ -43,15 +47,38 @@ impl LoxClass {
         // Return None because the method was not found
         None
     }
+
+    /**
+     * Function used in order to retrieve a static method of the current class.
+     *
+     * Note: Returns `None` if not found.
+     */
+    pub fn find_class_method(&self, name: &str) -> Option<LoxFunction> {
+        // Try getting the static method from the current class
+        if let Some(method) = self.class_methods.get(name) {
+            return Some(method.clone());
+        }
+
+        // Try getting the static method from the superclass if there is any
+        if let Some(sc) = &self.superclass {
+            return sc.find_class_method(name);
+        }
+
+        // Return None because the static method was not found
+        None
+    }
 }
 
 impl fmt::Display for LoxClass {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(f, "<class {}", self.name)?;
-        if !self.methods.is_empty() {
+        if !self.methods.is_empty() || !self.class_methods.is_empty() {
             for (name, obj) in &self.methods {
                 writeln!(f, "- this.{} = {}", name, obj)?;
             }
+            for (name, obj) in &self.class_methods {
+                writeln!(f, "- {}.{} = {}", self.name, name, obj)?;
+            }
         } else {
             writeln!(f, "Methods: None")?;
         }
@@ -70,14 +97,16 @@ impl LoxCallable for LoxClass {
         interpreter: &mut Interpreter,
         arguments: Vec<Object>,
         class: Option<Rc<LoxClass>>,
+        paren: &Token,
     ) -> Result<Object, LoxResult> {
         // Create a new instance from the class declaration
         let instance = Rc::new(LoxInstance::new(class.as_ref().unwrap()));
+        heap::track(&instance);
         // If we have a declared init function, bind it to the instance and run it
         if let Some(init_function) = self.find_method("init") {
             init_function
                 .bind(&Object::Instance(Rc::clone(&instance)))
-                .call(interpreter, arguments, class)?;
+                .call(interpreter, arguments, class, paren)?;
         }
         // Return the instance
         Ok(Object::Instance(instance))
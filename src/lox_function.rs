@@ -4,6 +4,7 @@ use std::rc::Rc;
 
 use crate::environment::Environment;
 use crate::errors::LoxResult;
+use crate::heap;
 use crate::interpreter::Interpreter;
 use crate::lox_callable::LoxCallable;
 use crate::lox_class::LoxClass;
@@ -14,7 +15,8 @@ use crate::token_type::TokenType;
 
 #[derive(Clone)]
 pub struct LoxFunction {
-    pub name: Token,
+    /// The name of the function, `None` for an anonymous function.
+    pub name: Option<Token>,
     // TODO: Refactor into Rc<Vec<Token>>
     /// Contains the list of parameters/arguments of the function.
     pub params: Vec<Token>,
@@ -26,6 +28,8 @@ pub struct LoxFunction {
     pub closure: Rc<RefCell<Environment>>,
     /// Tells if this is a class's `init()` function
     pub is_init_function: bool,
+    /// Tells if this is a getter method, called as soon as it is accessed on an instance.
+    pub is_getter: bool,
 }
 
 impl LoxFunction {
@@ -34,20 +38,22 @@ impl LoxFunction {
      */
     pub fn bind(&self, instance: &Object) -> LoxFunction {
         // Create a new environment that contains the current function's one
-        let new_env = RefCell::new(Environment::from_enclosing(Rc::clone(&self.closure)));
+        let new_env = Rc::new(RefCell::new(Environment::from_enclosing(Rc::clone(
+            &self.closure,
+        ))));
+        heap::track(&new_env);
 
         // Define `this` in that new environment
-        new_env
-            .borrow_mut()
-            .define("this".to_string(), instance.clone());
+        new_env.borrow_mut().define("this", instance.clone());
 
         // Return a new `LoxFunction` that just have this environment changed
         Self {
             name: self.name.clone(),
             params: self.params.clone(),
             body: self.body.clone(),
-            closure: Rc::new(new_env),
+            closure: new_env,
             is_init_function: self.is_init_function,
+            is_getter: self.is_getter,
         }
     }
 }
@@ -64,22 +70,40 @@ impl LoxCallable for LoxFunction {
         interpreter: &mut Interpreter,
         arguments: Vec<Object>,
         _class: Option<Rc<LoxClass>>,
+        paren: &Token,
     ) -> Result<Object, LoxResult> {
         // Create a new environment for the function's scope
         let mut env = Environment::from_enclosing(Rc::clone(&self.closure));
 
         // Define the function's arguments in the function's env
         for (param, arg) in self.params.iter().zip(arguments.iter()) {
-            env.define(param.lexeme.clone(), arg.clone());
+            env.define(&param.lexeme, arg.clone());
         }
 
+        let env = Rc::new(RefCell::new(env));
+        heap::track(&env);
+
+        // Run the body, keeping the call on the call stack and letting the debugger know about it
+        interpreter.enter_function(
+            self.name
+                .as_ref()
+                .map_or("anonymous", |name| name.lexeme.as_str()),
+            paren,
+        )?;
+        // A function imported from a module looks up the globals of that module
+        let globals = interpreter.swap_globals(Environment::root(&self.closure));
+        let result = interpreter.execute_block(&self.body, env);
+        interpreter.swap_globals(globals);
+        interpreter.exit_function();
+
         // Handle the execution's return
-        match interpreter.execute_block(&self.body, Rc::new(RefCell::new(env))) {
+        match result {
             // Returned a value
             Err(LoxResult::ReturnValue { value }) => {
                 // If we're in a class's init() function, return `this`
                 if self.is_init_function {
-                    return self.closure.borrow_mut().get_at(
+                    return self.closure.borrow().get_at(
+                        0,
                         0,
                         &Token {
                             ttype: TokenType::This,
@@ -97,7 +121,8 @@ impl LoxCallable for LoxFunction {
             // `this` in an init() flass function.
             Ok(_) => {
                 if self.is_init_function {
-                    return self.closure.borrow_mut().get_at(
+                    return self.closure.borrow().get_at(
+                        0,
                         0,
                         &Token {
                             ttype: TokenType::This,
@@ -121,7 +146,9 @@ impl fmt::Display for LoxFunction {
         write!(
             f,
             "<fn {}({})>",
-            self.name.lexeme,
+            self.name
+                .as_ref()
+                .map_or("anonymous", |name| name.lexeme.as_str()),
             self.params
                 .iter()
                 .map(|x| x.lexeme.clone())
@@ -19,7 +19,7 @@ pub struct LoxInstance {
     /// The class this instance comes from.
     pub class: Rc<LoxClass>,
     /// The fields declared for this instance.
-    fields: RefCell<HashMap<String, Object>>,
+    pub fields: RefCell<HashMap<String, Object>>,
 }
 
 impl LoxInstance {
@@ -52,6 +52,7 @@ impl LoxInstance {
             Err(LoxResult::Runtime {
                 token: name.clone(),
                 error_type: RuntimeErrorType::UndefinedProperty,
+                trace: Vec::new(),
             })
         }
     }
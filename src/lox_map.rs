@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::errors::{LoxResult, RuntimeErrorType};
+use crate::object::{display_once, Object};
+use crate::token::Token;
+
+/**
+ * The hashable form of an `Object` used as a map key. Only strings, numbers, booleans and nil
+ * can be used as keys.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MapKey {
+    Str(String),
+    /// The bits of the number, with `-0` stored as `0` so that both are the same key.
+    Num(u64),
+    Bool(bool),
+    Nil,
+}
+
+impl MapKey {
+    /**
+     * Returns the key corresponding to the given object, or `None` if it isn't hashable.
+     */
+    pub fn from_object(obj: &Object) -> Option<MapKey> {
+        match obj {
+            Object::Str(s) => Some(MapKey::Str(s.clone())),
+            Object::Num(n) if n.is_nan() => None,
+            Object::Num(n) => {
+                let n = if *n == 0.0 { 0.0 } else { *n };
+                Some(MapKey::Num(n.to_bits()))
+            }
+            Object::True => Some(MapKey::Bool(true)),
+            Object::False => Some(MapKey::Bool(false)),
+            Object::Nil => Some(MapKey::Nil),
+            _ => None,
+        }
+    }
+}
+
+/**
+ * Returns the key corresponding to the given object, or an error pointing at `token` if it isn't
+ * hashable: `RuntimeErrorType::NanKey` for NaN, `RuntimeErrorType::UnhashableKey` otherwise.
+ */
+pub fn map_key(obj: &Object, token: &Token) -> Result<MapKey, LoxResult> {
+    MapKey::from_object(obj).ok_or_else(|| LoxResult::Runtime {
+        token: token.clone(),
+        error_type: match obj {
+            Object::Num(n) if n.is_nan() => RuntimeErrorType::NanKey,
+            _ => RuntimeErrorType::UnhashableKey,
+        },
+        trace: Vec::new(),
+    })
+}
+
+/**
+ * This structure represents a Lox map. Its entries are kept in insertion order.
+ */
+#[derive(Debug, Default)]
+pub struct LoxMap {
+    /// The position of each key in `self.entries`.
+    indexes: HashMap<MapKey, usize>,
+    /// The (key, value) pairs of the map, in insertion order.
+    entries: Vec<(Object, Object)>,
+}
+
+impl LoxMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /**
+     * Returns the value associated with `key`, if any.
+     */
+    pub fn get(&self, key: &MapKey) -> Option<Object> {
+        self.indexes
+            .get(key)
+            .map(|&index| self.entries[index].1.clone())
+    }
+
+    /**
+     * Associates `value` with `key`. An existing key keeps its position.
+     */
+    pub fn insert(&mut self, key: MapKey, key_obj: Object, value: Object) {
+        match self.indexes.get(&key) {
+            Some(&index) => self.entries[index].1 = value,
+            None => {
+                self.indexes.insert(key, self.entries.len());
+                self.entries.push((key_obj, value));
+            }
+        }
+    }
+
+    /**
+     * Removes `key` from the map and returns its value, if any.
+     */
+    pub fn remove(&mut self, key: &MapKey) -> Option<Object> {
+        let index = self.indexes.remove(key)?;
+        let (_, value) = self.entries.remove(index);
+
+        // The entries after the removed one moved back by one position
+        for position in self.indexes.values_mut() {
+            if *position > index {
+                *position -= 1;
+            }
+        }
+
+        Some(value)
+    }
+
+    pub fn contains_key(&self, key: &MapKey) -> bool {
+        self.indexes.contains_key(key)
+    }
+
+    /**
+     * Returns the keys of the map, in insertion order.
+     */
+    pub fn keys(&self) -> Vec<Object> {
+        self.entries.iter().map(|(key, _)| key.clone()).collect()
+    }
+
+    /**
+     * Returns the values of the map, in insertion order.
+     */
+    pub fn values(&self) -> Vec<Object> {
+        self.entries
+            .iter()
+            .map(|(_, value)| value.clone())
+            .collect()
+    }
+}
+
+impl fmt::Display for LoxMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        display_once(f, self as *const Self as usize, "{...}", |f| {
+            write!(f, "{{")?;
+            for (i, (key, value)) in self.entries.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{key}: {value}")?;
+            }
+            write!(f, "}}")
+        })
+    }
+}
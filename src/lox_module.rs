@@ -0,0 +1,49 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use crate::environment::Environment;
+use crate::errors::{LoxResult, RuntimeErrorType};
+use crate::object::Object;
+use crate::token::Token;
+
+/**
+ * A Lox file brought in by an `import` statement. Its top-level definitions are read as the
+ * properties of the module (e.g. `geometry.area`).
+ *
+ * Note: A module is only run the first time it is imported, every later import shares it.
+ */
+#[derive(Debug)]
+pub struct LoxModule {
+    /// The name of the module file, without its extension.
+    pub name: String,
+    /// The canonical path of the module file.
+    pub path: PathBuf,
+    /// The global environment the module ran in, holding its top-level definitions.
+    pub globals: Rc<RefCell<Environment>>,
+}
+
+impl LoxModule {
+    /**
+     * Returns the top-level definition `name` of the module.
+     *
+     * Note: Returns a `RuntimeErrorType::UndefinedProperty` error if the module doesn't define it.
+     */
+    pub fn get(&self, name: &Token) -> Result<Object, LoxResult> {
+        self.globals
+            .borrow()
+            .get(name)
+            .map_err(|_| LoxResult::Runtime {
+                token: name.clone(),
+                error_type: RuntimeErrorType::UndefinedProperty,
+                trace: Vec::new(),
+            })
+    }
+}
+
+impl fmt::Display for LoxModule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<module {}>", self.name)
+    }
+}
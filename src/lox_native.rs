@@ -1,10 +1,44 @@
 use std::fmt;
 use std::rc::Rc;
 
+use crate::errors::LoxResult;
+use crate::interpreter::Interpreter;
 use crate::lox_callable::LoxCallable;
+use crate::lox_class::LoxClass;
+use crate::object::Object;
+use crate::token::Token;
+
+/**
+ * A function implemented in Rust. Unlike a `LoxCallable`, it doesn't need an interpreter to be
+ * called so that every backend can share it.
+ */
+pub trait NativeCallable {
+    /**
+     * Calls the native function with the given arguments. `paren` is the closing parenthesis of
+     * the call expression, used to report errors.
+     */
+    fn call(&self, arguments: Vec<Object>, paren: &Token) -> Result<Object, LoxResult>;
+    fn arity(&self) -> usize;
+}
 
 pub struct NativeFunction {
-    pub function: Rc<dyn LoxCallable>,
+    pub function: Rc<dyn NativeCallable>,
+}
+
+impl LoxCallable for NativeFunction {
+    fn call(
+        &self,
+        _: &mut Interpreter,
+        arguments: Vec<Object>,
+        _class: Option<Rc<LoxClass>>,
+        paren: &Token,
+    ) -> Result<Object, LoxResult> {
+        self.function.call(arguments, paren)
+    }
+
+    fn arity(&self) -> usize {
+        self.function.arity()
+    }
 }
 
 impl PartialEq for NativeFunction {
@@ -0,0 +1,361 @@
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+
+use serde_json::{json, Value};
+
+use crate::errors::{LoxResult, Severity};
+use crate::lint;
+use crate::parser::Parser;
+use crate::protocol;
+use crate::resolver::Resolver;
+use crate::scanner::Scanner;
+use crate::symbols::{SymbolIndex, SymbolKind};
+use crate::token::Span;
+
+/// The JSON-RPC error code of an unknown method.
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/**
+ * Converts the character offsets of the tokens into the `(line, character)` positions of the
+ * protocol, where characters are counted in UTF-16 code units.
+ */
+struct LineIndex {
+    chars: Vec<char>,
+    /// The offset of the first character of each line.
+    line_starts: Vec<usize>,
+}
+
+impl LineIndex {
+    fn new(text: &str) -> Self {
+        let chars = text.chars().collect::<Vec<char>>();
+        let line_starts = std::iter::once(0)
+            .chain(
+                chars
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, &c)| c == '\n')
+                    .map(|(i, _)| i + 1),
+            )
+            .collect();
+
+        LineIndex { chars, line_starts }
+    }
+
+    fn position(&self, offset: usize) -> Value {
+        let offset = offset.min(self.chars.len());
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let character = self.chars[self.line_starts[line]..offset]
+            .iter()
+            .map(|c| c.len_utf16())
+            .sum::<usize>();
+
+        json!({ "line": line, "character": character })
+    }
+
+    fn range(&self, span: Span) -> Value {
+        json!({ "start": self.position(span.start), "end": self.position(span.end) })
+    }
+
+    fn offset(&self, position: &Value) -> usize {
+        let line = position["line"].as_u64().unwrap_or(0) as usize;
+        let character = position["character"].as_u64().unwrap_or(0) as usize;
+        let Some(&start) = self.line_starts.get(line) else {
+            return self.chars.len();
+        };
+
+        // Walk the line up to the character, without going past its end
+        let mut offset = start;
+        let mut units = 0;
+        while offset < self.chars.len() && self.chars[offset] != '\n' && units < character {
+            units += self.chars[offset].len_utf16();
+            offset += 1;
+        }
+
+        offset
+    }
+}
+
+/**
+ * An open source file, analyzed each time it changes.
+ */
+struct Document {
+    lines: LineIndex,
+    /// Every error found by the scanner, the parser and the resolver, then the lints.
+    errors: Vec<LoxResult>,
+    symbols: SymbolIndex,
+}
+
+impl Document {
+    fn new(text: &str) -> Self {
+        let lines = LineIndex::new(text);
+
+        // The scanner stops at the first error, leaving nothing to parse
+        let mut scanner = Scanner::new(text);
+        let tokens = match scanner.scan_tokens() {
+            Ok(tokens) => tokens,
+            Err(error) => {
+                return Document {
+                    lines,
+                    errors: vec![error],
+                    symbols: SymbolIndex::default(),
+                }
+            }
+        };
+
+        // The parser skips the statements with syntax errors, the others can still be checked
+        let (statements, mut errors) = Parser::new(tokens).parse();
+        // Lints about code missing statements would be misleading
+        let lints = match errors.is_empty() {
+            true => lint::lint(&statements, &scanner.comments),
+            false => Vec::new(),
+        };
+        errors.extend(Resolver::check_all(&statements));
+        errors.extend(lints.into_iter().map(|lint| LoxResult::Warning { lint }));
+
+        Document {
+            lines,
+            errors,
+            symbols: SymbolIndex::new(&statements),
+        }
+    }
+
+    fn diagnostics(&self) -> Vec<Value> {
+        self.errors
+            .iter()
+            .filter_map(|error| {
+                let severity = match error.severity() {
+                    Severity::Error => 1,
+                    Severity::Warning => 2,
+                    Severity::Note => 3,
+                };
+                let message = match error.help() {
+                    Some(help) => format!("{}\nhelp: {help}", error.message()),
+                    None => error.message(),
+                };
+                Some(json!({
+                    "range": self.lines.range(error.span()?),
+                    "severity": severity,
+                    "source": "lox",
+                    "code": error.code()?,
+                    "message": message,
+                }))
+            })
+            .collect()
+    }
+
+    fn hover(&self, offset: usize) -> Value {
+        let Some(reference) = self.symbols.reference_at(offset) else {
+            return Value::Null;
+        };
+        let signatures = reference
+            .symbols
+            .iter()
+            .map(|&symbol| self.symbols.symbols[symbol].signature.as_str())
+            .collect::<Vec<&str>>();
+
+        json!({
+            "contents": {
+                "kind": "markdown",
+                "value": format!("```lox\n{}\n```", signatures.join("\n")),
+            },
+            "range": self.lines.range(reference.span),
+        })
+    }
+
+    /**
+     * Returns the classes, each with its methods as children.
+     */
+    fn document_symbols(&self) -> Vec<Value> {
+        let symbols = &self.symbols.symbols;
+        let describe = |symbol: usize, kind: u64, children: Vec<Value>| {
+            let range = self.lines.range(symbols[symbol].span);
+            json!({
+                "name": symbols[symbol].name,
+                "detail": symbols[symbol].signature,
+                "kind": kind,
+                "range": range,
+                "selectionRange": range,
+                "children": children,
+            })
+        };
+
+        (0..symbols.len())
+            .filter(|&class| symbols[class].kind == SymbolKind::Class)
+            .map(|class| {
+                let methods = (0..symbols.len())
+                    .filter(|&method| symbols[method].class == Some(class))
+                    .map(|method| {
+                        // Getters are reported as properties, the other methods as methods
+                        let kind = match symbols[method].kind {
+                            SymbolKind::Getter => 7,
+                            _ => 6,
+                        };
+                        describe(method, kind, Vec::new())
+                    })
+                    .collect();
+                describe(class, 5, methods)
+            })
+            .collect()
+    }
+}
+
+/**
+ * A Language Server Protocol server for Lox source files. It publishes the errors and lints of
+ * each open file, and offers go-to-definition, find-references, hover and document symbols.
+ *
+ * Note: Files are synchronized in full on each change, and analyzed on their own.
+ */
+pub struct LspServer {
+    /// Where the messages are read from.
+    input: Box<dyn BufRead>,
+    /// Where the messages are written to.
+    output: Box<dyn Write>,
+    /// The open files, by uri.
+    documents: HashMap<String, Document>,
+}
+
+impl LspServer {
+    pub fn new(input: impl BufRead + 'static, output: impl Write + 'static) -> Self {
+        LspServer {
+            input: Box::new(input),
+            output: Box::new(output),
+            documents: HashMap::new(),
+        }
+    }
+
+    fn send(&mut self, mut message: Value) {
+        message["jsonrpc"] = json!("2.0");
+        // A client that went away also closed the input, which ends the session
+        let _ = protocol::write_message(&mut self.output, &message);
+    }
+
+    /**
+     * Serves a client until it sends `exit` or closes the input.
+     */
+    pub fn run(&mut self) {
+        while let Some(message) = protocol::read_message(&mut self.input) {
+            let method = message["method"].as_str().unwrap_or_default().to_string();
+            if method == "exit" {
+                return;
+            }
+
+            let params = &message["params"];
+            let uri = params["textDocument"]["uri"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string();
+
+            // Keep the open files up to date, and publish their errors
+            match method.as_str() {
+                "textDocument/didOpen" => {
+                    let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                    self.update(&uri, Some(text));
+                }
+                "textDocument/didChange" => {
+                    let changes = params["contentChanges"].as_array();
+                    if let Some(text) = changes
+                        .and_then(|changes| changes.last())
+                        .and_then(|change| change["text"].as_str())
+                    {
+                        self.update(&uri, Some(text));
+                    }
+                }
+                "textDocument/didClose" => self.update(&uri, None),
+                _ => {}
+            }
+
+            // Answer the requests, notifications have no id
+            let id = &message["id"];
+            if id.is_null() {
+                continue;
+            }
+            match self.answer(&method, &uri, params) {
+                Some(result) => self.send(json!({ "id": id, "result": result })),
+                None => self.send(json!({
+                    "id": id,
+                    "error": {
+                        "code": METHOD_NOT_FOUND,
+                        "message": format!("Unsupported method '{method}'."),
+                    },
+                })),
+            }
+        }
+    }
+
+    /**
+     * Analyzes the new text of a file, or forgets it once closed, and publishes its errors.
+     */
+    fn update(&mut self, uri: &str, text: Option<&str>) {
+        let diagnostics = match text {
+            Some(text) => {
+                let document = Document::new(text);
+                let diagnostics = document.diagnostics();
+                self.documents.insert(uri.to_string(), document);
+                diagnostics
+            }
+            None => {
+                self.documents.remove(uri);
+                Vec::new()
+            }
+        };
+
+        self.send(json!({
+            "method": "textDocument/publishDiagnostics",
+            "params": { "uri": uri, "diagnostics": diagnostics },
+        }));
+    }
+
+    /**
+     * Returns the result of a request, or `None` if the method isn't supported.
+     */
+    fn answer(&self, method: &str, uri: &str, params: &Value) -> Option<Value> {
+        if method == "initialize" {
+            return Some(json!({
+                "capabilities": {
+                    "textDocumentSync": 1,
+                    "definitionProvider": true,
+                    "referencesProvider": true,
+                    "hoverProvider": true,
+                    "documentSymbolProvider": true,
+                },
+                "serverInfo": { "name": "lox-lsp" },
+            }));
+        }
+        if method == "shutdown" {
+            return Some(Value::Null);
+        }
+
+        let document = self.documents.get(uri);
+        let offset = document.map_or(0, |document| document.lines.offset(&params["position"]));
+        let location = |span| json!({ "uri": uri, "range": document.unwrap().lines.range(span) });
+
+        let result = match method {
+            "textDocument/definition" => {
+                let definitions = document
+                    .map(|document| document.symbols.definitions_at(offset))
+                    .unwrap_or_default();
+                definitions
+                    .iter()
+                    .map(|symbol| location(symbol.span))
+                    .collect()
+            }
+            "textDocument/references" => {
+                let include_declaration = params["context"]["includeDeclaration"]
+                    .as_bool()
+                    .unwrap_or(true);
+                let references = document
+                    .map(|document| document.symbols.references_at(offset, include_declaration))
+                    .unwrap_or_default();
+                references.into_iter().map(location).collect()
+            }
+            "textDocument/hover" => document.map_or(Value::Null, |document| document.hover(offset)),
+            "textDocument/documentSymbol" => document
+                .map(|document| document.document_symbols())
+                .unwrap_or_default()
+                .into(),
+            _ => return None,
+        };
+
+        Some(result)
+    }
+}
@@ -1,82 +1,257 @@
-use rs_lox_tw::errors::LoxResult;
+use rs_lox_tw::dap::DapServer;
+use rs_lox_tw::debugger::Debugger;
+use rs_lox_tw::debugger_cli::CliDebugger;
+use rs_lox_tw::diagnostic::SourceFile;
+use rs_lox_tw::errors::{EvalError, LoxResult, Severity};
+use rs_lox_tw::formatter;
 use rs_lox_tw::interpreter::Interpreter;
+use rs_lox_tw::lint;
+use rs_lox_tw::lox::{Backend, Lox};
+use rs_lox_tw::lsp::LspServer;
+use rs_lox_tw::native_functions::NativeModule;
 use rs_lox_tw::parser::Parser;
-use rs_lox_tw::resolver::Resolver;
 use rs_lox_tw::scanner::Scanner;
 
 use std::io::{self, BufRead, Write};
 use std::{env, fs};
 
-struct Lox {
-    interpreter: Interpreter,
+/**
+ * Removes `flag` from the command line arguments and tells if it was there.
+ */
+fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    match args.iter().position(|arg| arg == flag) {
+        Some(position) => {
+            args.remove(position);
+            true
+        }
+        None => false,
+    }
 }
 
-impl Lox {
-    fn new() -> Self {
-        Lox {
-            interpreter: Interpreter::new(),
+/**
+ * Runs the given script. Returns the exit code: 65 if it has a static error (or a warning when
+ * they are denied), 66 if it can't be read and 70 if it raised a runtime error.
+ */
+fn run_file(lox: &mut Lox, path: &str, debug: bool) -> i32 {
+    let Ok(source) = fs::read_to_string(path) else {
+        eprintln!("{}", LoxResult::IOError);
+        return 66;
+    };
+
+    // Import modules relative to the script, and render the errors against it
+    lox.interpreter().set_script(path);
+    lox.set_source_file(path, &source);
+
+    // Let the user step through the script from the command line
+    if debug {
+        let frontend = CliDebugger::new(&source, io::stdin().lock(), io::stdout());
+        lox.interpreter()
+            .set_debugger(Debugger::new(Box::new(frontend)));
+    }
+
+    lox.set_lint(true);
+    let result = lox.eval_source(&source);
+    let warnings = lox.take_warnings();
+    // Errors may also point at the modules the script imported
+    let file = SourceFile::new(path, &source).with_sources(lox.sources());
+    for warning in warnings {
+        eprintln!("{}\n", file.render(&warning));
+    }
+
+    match result {
+        // The program ran to the end or was stopped from the debugger
+        Ok(_) | Err(EvalError::Runtime(LoxResult::Stop)) => 0,
+        // The program didn't run, so that scripts checking it fail
+        Err(EvalError::Warnings(warnings)) => {
+            eprintln!(
+                "{}\n",
+                file.render(&LoxResult::Multiple { errors: warnings })
+            );
+            eprintln!("error: aborting because warnings are denied (`--deny-warnings`)");
+            65
+        }
+        // Render the error along with the source code it points at
+        Err(EvalError::Runtime(e)) => {
+            eprintln!("{}", file.render(&e));
+            70
+        }
+        Err(e) => {
+            eprintln!("{}", file.render(&e.into()));
+            65
         }
     }
+}
 
-    fn run_file(&mut self, path: &str) -> Result<(), LoxResult> {
-        let file_content = fs::read_to_string(path).expect("Unable to read file.");
-        self.run(file_content)
+/**
+ * Formats the given scripts in place or, in `--check` mode, lists the ones that aren't
+ * formatted. Returns the exit code: 1 if a script isn't formatted in `--check` mode, 65 if a
+ * script can't be scanned and 66 if it can't be read.
+ */
+fn run_fmt(mut paths: Vec<String>) -> i32 {
+    let check = take_flag(&mut paths, "--check");
+    if paths.is_empty() {
+        eprintln!("Usage: ./rs-lox-tw fmt [--check] <script>...");
+        return 64;
     }
 
-    fn run_prompt(&mut self) -> Result<(), LoxResult> {
-        // Get an handle on stdin
-        let stdin = io::stdin();
+    let mut code = 0;
+    for path in &paths {
+        let Ok(source) = fs::read_to_string(path) else {
+            eprintln!("{}", LoxResult::IOError);
+            code = 66;
+            continue;
+        };
 
-        // Print the prompt
-        print!("> ");
-        std::io::stdout().flush().expect("Unable to flush stdout.");
-        for line in stdin.lock().lines() {
-            // Specialy convert an IO error into a `LoxResult::IOError`
-            match line {
-                Ok(line) => {
-                    // If error running the code, print it and continue
-                    if let Err(e) = self.run(line) {
-                        eprintln!("{e}");
-                    }
+        match formatter::format(&source) {
+            Ok(formatted) if formatted == source => {}
+            Ok(_) if check => {
+                println!("Would reformat {path}");
+                code = code.max(1);
+            }
+            Ok(formatted) => {
+                if fs::write(path, formatted).is_err() {
+                    eprintln!("{}", LoxResult::IOError);
+                    code = 66;
                 }
-                Err(_) => return Err(LoxResult::IOError),
-            };
-            // Print the prompt
-            print!("> ");
-            std::io::stdout().flush().expect("Unable to flush stdout.");
+            }
+            Err(e) => {
+                eprintln!("{}", SourceFile::new(path, &source).render(&e));
+                code = 65;
+            }
         }
+    }
+
+    code
+}
 
-        Ok(())
+/**
+ * Prints the lints of the given scripts. Returns the exit code: 1 if there is any warning (notes
+ * don't count), 65 if a script can't be parsed and 66 if it can't be read.
+ */
+fn run_lint(paths: Vec<String>) -> i32 {
+    if paths.is_empty() {
+        eprintln!("Usage: ./rs-lox-tw lint <script>...");
+        return 64;
     }
 
-    fn run(&mut self, source: String) -> Result<(), LoxResult> {
+    let mut code = 0;
+    for path in &paths {
+        let Ok(source) = fs::read_to_string(path) else {
+            eprintln!("{}", LoxResult::IOError);
+            code = 66;
+            continue;
+        };
+        let file = SourceFile::new(path, &source);
+
         let mut scanner = Scanner::new(&source);
+        let statements = match scanner.scan_tokens() {
+            Ok(tokens) => match Parser::new(tokens).parse() {
+                (statements, errors) if errors.is_empty() => statements,
+                (_, errors) => {
+                    eprintln!("{}", file.render(&LoxResult::Multiple { errors }));
+                    code = 65;
+                    continue;
+                }
+            },
+            Err(e) => {
+                eprintln!("{}", file.render(&e));
+                code = 65;
+                continue;
+            }
+        };
+
+        for lint in lint::lint(&statements, &scanner.comments) {
+            let warning = LoxResult::Warning { lint };
+            println!("{}\n", file.render(&warning));
+            if warning.severity() >= Severity::Warning {
+                code = code.max(1);
+            }
+        }
+    }
 
-        let tokens = scanner.scan_tokens()?;
-        let mut parser = Parser::new(tokens);
+    code
+}
 
-        let statements = parser.parse()?;
-        let mut resolver = Resolver::new(&mut self.interpreter);
-        resolver.resolve_stmts(&statements)?;
+fn run_prompt(lox: &mut Lox) -> Result<(), LoxResult> {
+    // Get an handle on stdin
+    let stdin = io::stdin();
 
-        self.interpreter.interpret(&statements)?;
-        Ok(())
+    // Print the prompt
+    print!("> ");
+    std::io::stdout().flush().expect("Unable to flush stdout.");
+    for line in stdin.lock().lines() {
+        // Specialy convert an IO error into a `LoxResult::IOError`
+        match line {
+            Ok(line) => {
+                // If error running the code, print it and continue
+                lox.set_source_file("<stdin>", &line);
+                if let Err(e) = lox.eval_source(&line) {
+                    eprintln!("{}", SourceFile::new("<stdin>", &line).render(&e.into()));
+                }
+            }
+            Err(_) => return Err(LoxResult::IOError),
+        };
+        // Print the prompt
+        print!("> ");
+        std::io::stdout().flush().expect("Unable to flush stdout.");
     }
+
+    Ok(())
 }
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+
+    // Format scripts rather than running them
+    if args.get(1).is_some_and(|arg| arg == "fmt") {
+        std::process::exit(run_fmt(args.split_off(2)));
+    }
+    // Report the lints of scripts rather than running them
+    if args.get(1).is_some_and(|arg| arg == "lint") {
+        std::process::exit(run_lint(args.split_off(2)));
+    }
 
-    let mut lox = Lox::new();
+    // Run on the bytecode vm instead of the tree-walking interpreter
+    let backend = match take_flag(&mut args, "--vm") {
+        true => Backend::Vm,
+        false => Backend::TreeWalk,
+    };
+    // Run the script in the debugger (tree-walking interpreter only)
+    let debug = take_flag(&mut args, "--debug");
+    // Serve the Debug Adapter Protocol over stdio, the editor launches the script
+    let dap = take_flag(&mut args, "--dap");
+    // Serve the Language Server Protocol over stdio, for editors to check and navigate scripts
+    let lsp = take_flag(&mut args, "--lsp");
+    // The debugger reads its commands from the standard input, which `input()` can't take
+    let mut lox = match debug {
+        true => {
+            let interpreter = Interpreter::builder()
+                .natives(&NativeModule::DEBUGGED)
+                .build();
+            Lox::with_interpreter(interpreter, backend)
+        }
+        false => Lox::with_backend(backend),
+    };
+    // Don't run scripts with warnings
+    lox.set_deny_warnings(take_flag(&mut args, "--deny-warnings"));
 
-    if args.is_empty() || args.len() > 2 {
-        eprintln!("Usage: ./rs-lox-tw [script]");
+    if args.is_empty()
+        || args.len() > 2
+        || (debug && (args.len() != 2 || backend == Backend::Vm))
+        || (dap && (args.len() != 1 || backend == Backend::Vm || debug))
+        || (lsp && (args.len() != 1 || backend == Backend::Vm || debug || dap))
+    {
+        eprintln!("Usage: ./rs-lox-tw [--vm | --debug | --dap | --lsp] [--deny-warnings] [script]");
+        eprintln!("       ./rs-lox-tw fmt [--check] <script>...");
+        eprintln!("       ./rs-lox-tw lint <script>...");
         std::process::exit(64);
+    } else if dap {
+        DapServer::new(io::stdin().lock(), io::stdout()).run();
+    } else if lsp {
+        LspServer::new(io::stdin().lock(), io::stdout()).run();
     } else if args.len() == 2 {
-        if let Err(e) = lox.run_file(&args[1]) {
-            eprintln!("{}", e);
-        }
-    } else if let Err(e) = lox.run_prompt() {
+        std::process::exit(run_file(&mut lox, &args[1], debug));
+    } else if let Err(e) = run_prompt(&mut lox) {
         eprintln!("{}", e);
     }
 }
@@ -1,20 +1,98 @@
+use std::fs;
+use std::io;
 use std::rc::Rc;
 
-use crate::errors::LoxResult;
-use crate::interpreter::Interpreter;
-use crate::lox_callable::LoxCallable;
-use crate::lox_class::LoxClass;
+use crate::errors::{LoxResult, RuntimeErrorType};
+use crate::lox_map::map_key;
+use crate::lox_native::NativeCallable;
 use crate::object::Object;
+use crate::token::Token;
+
+/**
+ * A group of native functions installed together, so that an embedder can choose what a program
+ * may reach outside of itself.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NativeModule {
+    /// Functions of their arguments only: `len`, `push`, `pop`, `keys`, `values`, `has` and
+    /// `remove`.
+    Pure,
+    /// `clock`.
+    Time,
+    /// `input`, reading the standard input of the process.
+    Io,
+    /// `readFile` and `writeFile`.
+    Fs,
+}
+
+impl NativeModule {
+    /// Every module, installed by default.
+    pub const ALL: [NativeModule; 4] = [
+        NativeModule::Pure,
+        NativeModule::Time,
+        NativeModule::Io,
+        NativeModule::Fs,
+    ];
+
+    /// The modules of a sandboxed program, which can't touch the filesystem or the process.
+    pub const SANDBOXED: [NativeModule; 2] = [NativeModule::Pure, NativeModule::Time];
+
+    /// The modules of a program run in a debugger, whose front end (e.g. the command line or the
+    /// Debug Adapter Protocol) reads the standard input.
+    pub const DEBUGGED: [NativeModule; 3] =
+        [NativeModule::Pure, NativeModule::Time, NativeModule::Fs];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            NativeModule::Pure => "pure",
+            NativeModule::Time => "time",
+            NativeModule::Io => "io",
+            NativeModule::Fs => "fs",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<NativeModule> {
+        NativeModule::ALL
+            .into_iter()
+            .find(|module| module.name() == name)
+    }
+
+    /**
+     * Returns the native functions of the module along with the global name each is defined as.
+     */
+    pub fn functions(&self) -> Vec<(&'static str, Rc<dyn NativeCallable>)> {
+        match self {
+            NativeModule::Pure => vec![
+                ("len", Rc::new(NativeLen {})),
+                ("push", Rc::new(NativePush {})),
+                ("pop", Rc::new(NativePop {})),
+                ("keys", Rc::new(NativeKeys {})),
+                ("values", Rc::new(NativeValues {})),
+                ("has", Rc::new(NativeHas {})),
+                ("remove", Rc::new(NativeRemove {})),
+            ],
+            NativeModule::Time => vec![("clock", Rc::new(NativeClock {}))],
+            NativeModule::Io => vec![("input", Rc::new(NativeInput {}))],
+            NativeModule::Fs => vec![
+                ("readFile", Rc::new(NativeReadFile {})),
+                ("writeFile", Rc::new(NativeWriteFile {})),
+            ],
+        }
+    }
+}
+
+/**
+ * Returns the native functions of the given modules along with the global name each is defined
+ * as.
+ */
+pub fn native_functions(modules: &[NativeModule]) -> Vec<(&'static str, Rc<dyn NativeCallable>)> {
+    modules.iter().flat_map(NativeModule::functions).collect()
+}
 
 pub struct NativeClock;
 
-impl LoxCallable for NativeClock {
-    fn call(
-        &self,
-        _: &mut Interpreter,
-        _: Vec<Object>,
-        _class: Option<Rc<LoxClass>>,
-    ) -> Result<Object, LoxResult> {
+impl NativeCallable for NativeClock {
+    fn call(&self, _: Vec<Object>, _paren: &Token) -> Result<Object, LoxResult> {
         Ok(Object::Num(
             chrono::offset::Local::now().timestamp_millis() as f64 / 1000.0,
         ))
@@ -24,3 +102,255 @@ impl LoxCallable for NativeClock {
         0
     }
 }
+
+/**
+ * `len(x)`: Returns the number of elements of a list or a map, or the number of characters of a
+ * string.
+ */
+pub struct NativeLen;
+
+impl NativeCallable for NativeLen {
+    fn call(&self, arguments: Vec<Object>, paren: &Token) -> Result<Object, LoxResult> {
+        match &arguments[0] {
+            Object::List(elements) => Ok(Object::Num(elements.borrow().len() as f64)),
+            Object::Map(map) => Ok(Object::Num(map.borrow().len() as f64)),
+            Object::Str(s) => Ok(Object::Num(s.chars().count() as f64)),
+            _ => Err(LoxResult::Runtime {
+                token: paren.clone(),
+                error_type: RuntimeErrorType::InvalidArgumentType,
+                trace: Vec::new(),
+            }),
+        }
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+}
+
+/**
+ * `push(list, value)`: Appends `value` at the end of `list`. Returns `nil`.
+ */
+pub struct NativePush;
+
+impl NativeCallable for NativePush {
+    fn call(&self, arguments: Vec<Object>, paren: &Token) -> Result<Object, LoxResult> {
+        if let Object::List(elements) = &arguments[0] {
+            elements.borrow_mut().push(arguments[1].clone());
+            Ok(Object::Nil)
+        } else {
+            Err(LoxResult::Runtime {
+                token: paren.clone(),
+                error_type: RuntimeErrorType::InvalidArgumentType,
+                trace: Vec::new(),
+            })
+        }
+    }
+
+    fn arity(&self) -> usize {
+        2
+    }
+}
+
+/**
+ * `pop(list)`: Removes the last element of `list` and returns it.
+ */
+pub struct NativePop;
+
+impl NativeCallable for NativePop {
+    fn call(&self, arguments: Vec<Object>, paren: &Token) -> Result<Object, LoxResult> {
+        if let Object::List(elements) = &arguments[0] {
+            elements
+                .borrow_mut()
+                .pop()
+                .ok_or_else(|| LoxResult::Runtime {
+                    token: paren.clone(),
+                    error_type: RuntimeErrorType::PopFromEmptyList,
+                    trace: Vec::new(),
+                })
+        } else {
+            Err(LoxResult::Runtime {
+                token: paren.clone(),
+                error_type: RuntimeErrorType::InvalidArgumentType,
+                trace: Vec::new(),
+            })
+        }
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+}
+
+/**
+ * `keys(map)`: Returns the list of the keys of `map`, in insertion order.
+ */
+pub struct NativeKeys;
+
+impl NativeCallable for NativeKeys {
+    fn call(&self, arguments: Vec<Object>, paren: &Token) -> Result<Object, LoxResult> {
+        if let Object::Map(map) = &arguments[0] {
+            Ok(Object::from(map.borrow().keys()))
+        } else {
+            Err(LoxResult::Runtime {
+                token: paren.clone(),
+                error_type: RuntimeErrorType::InvalidArgumentType,
+                trace: Vec::new(),
+            })
+        }
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+}
+
+/**
+ * `values(map)`: Returns the list of the values of `map`, in insertion order.
+ */
+pub struct NativeValues;
+
+impl NativeCallable for NativeValues {
+    fn call(&self, arguments: Vec<Object>, paren: &Token) -> Result<Object, LoxResult> {
+        if let Object::Map(map) = &arguments[0] {
+            Ok(Object::from(map.borrow().values()))
+        } else {
+            Err(LoxResult::Runtime {
+                token: paren.clone(),
+                error_type: RuntimeErrorType::InvalidArgumentType,
+                trace: Vec::new(),
+            })
+        }
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+}
+
+/**
+ * `has(map, key)`: Returns true if `key` is a key of `map`.
+ */
+pub struct NativeHas;
+
+impl NativeCallable for NativeHas {
+    fn call(&self, arguments: Vec<Object>, paren: &Token) -> Result<Object, LoxResult> {
+        if let Object::Map(map) = &arguments[0] {
+            let key = map_key(&arguments[1], paren)?;
+            Ok(Object::from(map.borrow().contains_key(&key)))
+        } else {
+            Err(LoxResult::Runtime {
+                token: paren.clone(),
+                error_type: RuntimeErrorType::InvalidArgumentType,
+                trace: Vec::new(),
+            })
+        }
+    }
+
+    fn arity(&self) -> usize {
+        2
+    }
+}
+
+/**
+ * `remove(map, key)`: Removes `key` from `map` and returns its value, or `nil` if it wasn't a
+ * key of the map.
+ */
+pub struct NativeRemove;
+
+impl NativeCallable for NativeRemove {
+    fn call(&self, arguments: Vec<Object>, paren: &Token) -> Result<Object, LoxResult> {
+        if let Object::Map(map) = &arguments[0] {
+            let key = map_key(&arguments[1], paren)?;
+            Ok(map.borrow_mut().remove(&key).unwrap_or(Object::Nil))
+        } else {
+            Err(LoxResult::Runtime {
+                token: paren.clone(),
+                error_type: RuntimeErrorType::InvalidArgumentType,
+                trace: Vec::new(),
+            })
+        }
+    }
+
+    fn arity(&self) -> usize {
+        2
+    }
+}
+
+/**
+ * `input()`: Reads a line of the standard input and returns it without its line ending, or `nil`
+ * at the end of the input.
+ */
+pub struct NativeInput;
+
+impl NativeCallable for NativeInput {
+    fn call(&self, _: Vec<Object>, paren: &Token) -> Result<Object, LoxResult> {
+        let mut line = String::new();
+        match io::stdin().read_line(&mut line) {
+            Ok(0) => Ok(Object::Nil),
+            Ok(_) => Ok(Object::Str(line.trim_end_matches(['\n', '\r']).to_string())),
+            Err(_) => Err(LoxResult::Runtime {
+                token: paren.clone(),
+                error_type: RuntimeErrorType::NativeIoError,
+                trace: Vec::new(),
+            }),
+        }
+    }
+
+    fn arity(&self) -> usize {
+        0
+    }
+}
+
+/**
+ * `readFile(path)`: Returns the contents of the file at `path`.
+ */
+pub struct NativeReadFile;
+
+impl NativeCallable for NativeReadFile {
+    fn call(&self, arguments: Vec<Object>, paren: &Token) -> Result<Object, LoxResult> {
+        let error = |error_type| LoxResult::Runtime {
+            token: paren.clone(),
+            error_type,
+            trace: Vec::new(),
+        };
+
+        match &arguments[0] {
+            Object::Str(path) => fs::read_to_string(path)
+                .map(Object::Str)
+                .map_err(|_| error(RuntimeErrorType::NativeIoError)),
+            _ => Err(error(RuntimeErrorType::InvalidArgumentType)),
+        }
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+}
+
+/**
+ * `writeFile(path, contents)`: Replaces the contents of the file at `path` with the string
+ * `contents`, creating the file if needed. Returns `nil`.
+ */
+pub struct NativeWriteFile;
+
+impl NativeCallable for NativeWriteFile {
+    fn call(&self, arguments: Vec<Object>, paren: &Token) -> Result<Object, LoxResult> {
+        let error = |error_type| LoxResult::Runtime {
+            token: paren.clone(),
+            error_type,
+            trace: Vec::new(),
+        };
+
+        match (&arguments[0], &arguments[1]) {
+            (Object::Str(path), Object::Str(contents)) => fs::write(path, contents)
+                .map(|_| Object::Nil)
+                .map_err(|_| error(RuntimeErrorType::NativeIoError)),
+            _ => Err(error(RuntimeErrorType::InvalidArgumentType)),
+        }
+    }
+
+    fn arity(&self) -> usize {
+        2
+    }
+}
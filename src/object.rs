@@ -1,10 +1,18 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
 use std::fmt;
 use std::rc::Rc;
 
+use crate::errors::{LoxResult, RuntimeErrorType};
+use crate::heap;
 use crate::lox_class::LoxClass;
 use crate::lox_function::LoxFunction;
 use crate::lox_instance::LoxInstance;
+use crate::lox_map::LoxMap;
+use crate::lox_module::LoxModule;
 use crate::lox_native::NativeFunction;
+use crate::token::Token;
+use crate::vm_object::{BoundMethod, Closure, VmClass, VmInstance};
 
 #[derive(Debug, Clone)]
 pub enum Object {
@@ -17,6 +25,21 @@ pub enum Object {
     Native(Rc<NativeFunction>),
     Class(Rc<LoxClass>),
     Instance(Rc<LoxInstance>),
+    /// A list of objects. Its storage is shared between all the references to the list.
+    List(Rc<RefCell<Vec<Object>>>),
+    /// A map from hashable objects to objects. Its storage is shared between all the references
+    /// to the map.
+    Map(Rc<RefCell<LoxMap>>),
+    /// The namespace of an imported module.
+    Module(Rc<LoxModule>),
+    /// A function compiled to bytecode along with its captured variables (VM backend).
+    Closure(Rc<Closure>),
+    /// A method bound to the object it was accessed on (VM backend).
+    BoundMethod(Rc<BoundMethod>),
+    /// A class created by the VM backend.
+    VmClass(Rc<VmClass>),
+    /// An instance of a class created by the VM backend.
+    VmInstance(Rc<VmInstance>),
 }
 
 impl PartialEq for Object {
@@ -27,12 +50,30 @@ impl PartialEq for Object {
             (Object::True, Object::True) => true,
             (Object::False, Object::False) => true,
             (Object::Nil, Object::Nil) => true,
+            (Object::List(a), Object::List(b)) => Rc::ptr_eq(a, b),
+            (Object::Map(a), Object::Map(b)) => Rc::ptr_eq(a, b),
 
             _ => false,
         }
     }
 }
 
+impl From<Vec<Object>> for Object {
+    fn from(elements: Vec<Object>) -> Self {
+        let list = Rc::new(RefCell::new(elements));
+        heap::track(&list);
+        Object::List(list)
+    }
+}
+
+impl From<LoxMap> for Object {
+    fn from(map: LoxMap) -> Self {
+        let map = Rc::new(RefCell::new(map));
+        heap::track(&map);
+        Object::Map(map)
+    }
+}
+
 impl From<bool> for Object {
     fn from(boolean: bool) -> Self {
         match boolean {
@@ -72,6 +113,74 @@ impl fmt::Display for Object {
             Self::Native(fun) => write!(f, "{fun}"),
             Self::Class(class) => write!(f, "{class}"),
             Self::Instance(instance) => write!(f, "{instance}"),
+            Self::List(elements) => display_once(f, elements.as_ptr() as usize, "[...]", |f| {
+                write!(f, "[")?;
+                for (i, element) in elements.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{element}")?;
+                }
+                write!(f, "]")
+            }),
+            Self::Map(map) => write!(f, "{}", map.borrow()),
+            Self::Module(module) => write!(f, "{module}"),
+            Self::Closure(closure) => write!(f, "{closure}"),
+            Self::BoundMethod(method) => write!(f, "{method}"),
+            Self::VmClass(class) => write!(f, "{class}"),
+            Self::VmInstance(instance) => write!(f, "{instance}"),
+        }
+    }
+}
+
+thread_local! {
+    /// The addresses of the lists and maps being displayed, to print a container that contains
+    /// itself only once.
+    static DISPLAYED: RefCell<HashSet<usize>> = RefCell::new(HashSet::new());
+}
+
+/**
+ * Displays the container at `address` with `display`, or writes `placeholder` if it is already
+ * being displayed further up, i.e. if it is reached again through one of its elements.
+ */
+pub(crate) fn display_once(
+    f: &mut fmt::Formatter<'_>,
+    address: usize,
+    placeholder: &str,
+    display: impl FnOnce(&mut fmt::Formatter<'_>) -> fmt::Result,
+) -> fmt::Result {
+    if !DISPLAYED.with(|displayed| displayed.borrow_mut().insert(address)) {
+        return write!(f, "{placeholder}");
+    }
+    let result = display(f);
+    DISPLAYED.with(|displayed| displayed.borrow_mut().remove(&address));
+    result
+}
+
+/**
+ * Converts the evaluated `index` of an index expression into a position in a list of
+ * `len` elements.
+ *
+ * Note: Returns a `RuntimeErrorType::InvalidIndex` error if the index isn't a non-negative
+ * integer and a `RuntimeErrorType::IndexOutOfBounds` error if it is past the end of the list.
+ */
+pub fn list_index(index: &Object, len: usize, bracket: &Token) -> Result<usize, LoxResult> {
+    match index {
+        Object::Num(n) if *n >= 0.0 && n.fract() == 0.0 => {
+            if (*n as usize) < len {
+                Ok(*n as usize)
+            } else {
+                Err(LoxResult::Runtime {
+                    token: bracket.clone(),
+                    error_type: RuntimeErrorType::IndexOutOfBounds,
+                    trace: Vec::new(),
+                })
+            }
         }
+        _ => Err(LoxResult::Runtime {
+            token: bracket.clone(),
+            error_type: RuntimeErrorType::InvalidIndex,
+            trace: Vec::new(),
+        }),
     }
 }
@@ -0,0 +1,95 @@
+use std::cell::RefCell;
+use std::io::Write;
+use std::rc::Rc;
+
+/**
+ * A destination for the lines written by the `Interpreter` (e.g. the outcome of `print`
+ * statements or runtime diagnostics).
+ */
+pub trait OutputSink {
+    fn write_line(&mut self, line: &str);
+}
+
+/**
+ * Writes each line to the standard output. This is the default sink for `print` statements.
+ */
+pub struct StdoutSink;
+
+impl OutputSink for StdoutSink {
+    fn write_line(&mut self, line: &str) {
+        println!("{line}");
+    }
+}
+
+/**
+ * Writes each line to the standard error output. This is the default sink for diagnostics.
+ */
+pub struct StderrSink;
+
+impl OutputSink for StderrSink {
+    fn write_line(&mut self, line: &str) {
+        eprintln!("{line}");
+    }
+}
+
+/**
+ * Writes each line into an in-memory buffer.
+ *
+ * Note: The buffer is shared between clones, so the host can keep a clone of the sink given
+ * to the interpreter and read what was written into it.
+ */
+#[derive(Clone, Default)]
+pub struct BufferSink {
+    buffer: Rc<RefCell<Vec<u8>>>,
+}
+
+impl BufferSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Returns everything that was written so far.
+     */
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.buffer.borrow()).into_owned()
+    }
+
+    /**
+     * Empties the buffer.
+     */
+    pub fn clear(&self) {
+        self.buffer.borrow_mut().clear();
+    }
+}
+
+impl OutputSink for BufferSink {
+    fn write_line(&mut self, line: &str) {
+        // Writing into a `Vec<u8>` cannot fail
+        writeln!(self.buffer.borrow_mut(), "{line}").unwrap();
+    }
+}
+
+/**
+ * Lets the sink be used as a writer too, e.g. as the output of a debugger or a server.
+ */
+impl Write for BufferSink {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buffer.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/**
+ * Calls the wrapped closure with each line.
+ */
+pub struct CallbackSink<F: FnMut(&str)>(pub F);
+
+impl<F: FnMut(&str)> OutputSink for CallbackSink<F> {
+    fn write_line(&mut self, line: &str) {
+        (self.0)(line)
+    }
+}
@@ -1,3 +1,5 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
 use crate::errors::{LoxResult, ParserErrorType};
 use crate::expr::*;
 use crate::object::Object;
@@ -13,99 +15,93 @@ pub struct Parser<'a> {
     tokens: &'a Vec<Token>,
     /// The current index in the array of tokens.
     current: usize,
+    /// The syntax errors encountered so far. The parser recovers from each of them.
+    errors: Vec<LoxResult>,
+}
+
+/// The id given to the next expression referring to a variable. It is shared by every parser so
+/// that the ids stay unique across the sources evaluated by a same interpreter.
+static NEXT_EXPR_ID: AtomicUsize = AtomicUsize::new(0);
+
+/**
+ * Returns a new unique id for an expression referring to a variable (e.g. `Expr::Variable`),
+ * used by the `Resolver` to tell apart two variables with the same name.
+ */
+fn next_expr_id() -> usize {
+    NEXT_EXPR_ID.fetch_add(1, Ordering::Relaxed)
 }
 
 impl<'a> Parser<'a> {
     /**
      * Instanciates a parser from an array of tokens.
      */
-    pub fn new(tokens: &Vec<Token>) -> Parser {
-        Parser { tokens, current: 0 }
+    pub fn new(tokens: &Vec<Token>) -> Parser<'_> {
+        Parser {
+            tokens,
+            current: 0,
+            errors: Vec::new(),
+        }
     }
 
     /**
-     * Main parsing function that transforms the array of tokens into an array of statements
-     * if they are parsable.
+     * Main parsing function that transforms the array of tokens into an array of statements.
+     *
+     * Note: The parser does not stop on the first syntax error. It synchronizes to the next
+     * statement boundary and keeps going, so the returned statements are the partial AST made of
+     * every declaration that could be parsed, alongside every syntax error encountered.
      */
-    pub fn parse(&mut self) -> Result<Vec<Stmt>, LoxResult> {
+    pub fn parse(&mut self) -> (Vec<Stmt>, Vec<LoxResult>) {
         // Output array of parsed statements
         let mut statements = Vec::new();
 
         // Parse until reaching the end of the array of tokens
         while !self.is_at_end() {
-            // Parse the next tokens into a declaration.
-            match self.declaration() {
-                Ok(s) => match s {
-                    // If the parsed declaration is a statement, save it
-                    Some(s) => statements.push(s),
-                    None => {}
-                },
-                // If it is an error, return it
-                Err(e) => {
-                    return Err(e);
-                }
+            // Parse the next tokens into a declaration and save it if it was parsable.
+            if let Some(s) = self.declaration() {
+                statements.push(s);
             }
         }
 
-        // Return the parsed statements
-        Ok(statements)
+        // Return the parsed statements along with the collected errors
+        (statements, std::mem::take(&mut self.errors))
     }
 
     /**
      * Parses the next tokens into a declaration statement.
+     *
+     * Note: If the declaration is not parsable, the error is recorded in `self.errors`, the parser
+     * synchronizes to the next statement and `None` is returned.
      */
-    fn declaration(&mut self) -> Result<Option<Stmt>, LoxResult> {
-        // If the next token is 'class', parse the class declaration
-        if self.matchs_next(&[TokenType::Class]) {
-            match self.class_declaration() {
-                Ok(s) => return Ok(Some(s)),
-                Err(e) => {
-                    eprintln!("{}", e);
-                    self.synchronize();
-                }
-            }
-        }
-
-        // If the next token is 'fun', parse the function definition
-        if self.matchs_next(&[TokenType::Fun]) {
-            match self.function("function") {
-                Ok(s) => return Ok(Some(s)),
-                Err(e) => {
-                    eprintln!("{}", e);
-                    self.synchronize();
-                }
-            }
-        }
-
-        // If the next token is 'var', parse the variable declaration
-        if self.matchs_next(&[TokenType::Var]) {
-            match self.var_declaration() {
-                // Return the parsed variable declaration statement
-                Ok(s) => {
-                    return Ok(Some(s));
-                }
-                // If it was an error, print it and synchronize
-                Err(e) => {
-                    eprintln!("{e}");
-                    self.synchronize();
-                }
-            }
-        }
+    fn declaration(&mut self) -> Option<Stmt> {
+        let declaration = if self.matchs_next(&[TokenType::Class]) {
+            // If the next token is 'class', parse the class declaration
+            self.class_declaration()
+        } else if self.check(TokenType::Fun) && self.check_next(TokenType::Identifier) {
+            // If the next tokens are 'fun <name>', parse the function definition. Otherwise
+            // 'fun' starts an anonymous function expression.
+            self.advance();
+            self.function("function")
+        } else if self.matchs_next(&[TokenType::Var]) {
+            // If the next token is 'var', parse the variable declaration
+            self.var_declaration()
+        } else if self.matchs_next(&[TokenType::Import]) {
+            // If the next token is 'import', parse the import of a module
+            self.import_declaration()
+        } else {
+            // Otherwise, parse it as a statement
+            self.statement()
+        };
 
-        // Otherwise, parse it asa statement
-        match self.statement() {
-            // Return the parsed statement
-            Ok(s) => {
-                return Ok(Some(s));
-            }
-            // If it errored, print it and synchronize
+        match declaration {
+            // Return the parsed declaration
+            Ok(s) => Some(s),
+            // If it errored, save the error and synchronize
             Err(e) => {
-                eprintln!("{e}");
+                self.errors.push(e);
                 self.synchronize();
+                None
             }
         }
-
-        Ok(None)
     }
 
     /**
@@ -120,6 +116,7 @@ impl<'a> Parser<'a> {
             true => {
                 self.consume(TokenType::Identifier, "Expected superclass name after '<'.")?;
                 Some(Expr::Variable {
+                    id: next_expr_id(),
                     name: self.previous(),
                 })
             }
@@ -135,8 +132,18 @@ impl<'a> Parser<'a> {
         // Parse methods until we find the closing '}' or we until we reach the end of the
         // source code
         let mut methods = Vec::new();
+        let mut class_methods = Vec::new();
+        let mut getters = Vec::new();
         while !self.check(TokenType::RightBrace) && !self.is_at_end() {
-            methods.push(self.function("method")?);
+            if self.matchs_next(&[TokenType::Class]) {
+                // A method prefixed with 'class' is a static method
+                class_methods.push(self.function("method")?);
+            } else if self.check(TokenType::Identifier) && self.check_next(TokenType::LeftBrace) {
+                // A method without parameter list is a getter
+                getters.push(self.getter()?);
+            } else {
+                methods.push(self.function("method")?);
+            }
         }
 
         // Parse the closing '}' ending the class body
@@ -153,6 +160,29 @@ impl<'a> Parser<'a> {
             name,
             superclass,
             methods,
+            class_methods,
+            getters,
+        })
+    }
+
+    /**
+     * Parses the next tokens into a `Stmt::Function` statement without parameters, used for
+     * class getters: `area { return this.width * this.height; }`.
+     */
+    fn getter(&mut self) -> Result<Stmt, LoxResult> {
+        // Parse the getter's name
+        let name = self.consume(TokenType::Identifier, "Expected getter name.")?;
+        // Parse the getter's body enclosed in {}
+        self.consume(
+            TokenType::LeftBrace,
+            "Expected opening '{' before getter body.",
+        )?;
+        let body = self.block_statement()?;
+
+        Ok(Stmt::Function {
+            name,
+            params: Vec::new(),
+            body,
         })
     }
 
@@ -168,6 +198,20 @@ impl<'a> Parser<'a> {
             &format!("Expected opening '(' after {} name", kind),
         )?;
 
+        // Parse the function's parameters and body
+        let (params, body) = self.function_params_and_body(kind)?;
+
+        // Return the build Function Stmt
+        Ok(Stmt::Function { name, params, body })
+    }
+
+    /**
+     * Parses the parameters and the body of a function, after its opening '('.
+     */
+    fn function_params_and_body(
+        &mut self,
+        kind: &str,
+    ) -> Result<(Vec<Token>, Vec<Stmt>), LoxResult> {
         // Parse the function's parameters
         let mut params = Vec::new();
         if !self.check(TokenType::RightParen) {
@@ -205,8 +249,7 @@ impl<'a> Parser<'a> {
         // Parse the function's body enclosed in {}
         let body = self.block_statement()?;
 
-        // Return the build Function Stmt
-        Ok(Stmt::Function { name, params, body })
+        Ok((params, body))
     }
 
     /**
@@ -232,6 +275,75 @@ impl<'a> Parser<'a> {
         Ok(Stmt::Var { name, initializer })
     }
 
+    /**
+     * Parses the next tokens into a `Stmt::Import` statement, either `import "path";` or
+     * `import name from "path";`.
+     *
+     * Note: Without a name, the module is named after its file (e.g. `geometry` for
+     * `"lib/geometry.lox"`), which must then be a valid identifier.
+     */
+    fn import_declaration(&mut self) -> Result<Stmt, LoxResult> {
+        let keyword = self.previous();
+
+        // `from` is only a keyword in this position, so it can still name variables
+        let name = match self.check(TokenType::Identifier) {
+            true => {
+                let name = self.advance();
+                let from =
+                    self.consume(TokenType::Identifier, "Expected 'from' after module name.")?;
+                if from.lexeme != "from" {
+                    return Err(LoxResult::Parser {
+                        token: from,
+                        error_type: ParserErrorType::InvalidConsumeType,
+                        msg: "Expected 'from' after module name.".to_string(),
+                    });
+                }
+                Some(name)
+            }
+            false => None,
+        };
+        let path = self.consume(TokenType::String, "Expected module path.")?;
+
+        let name = match name {
+            Some(name) => name,
+            None => {
+                let stem = match &path.literal {
+                    Some(Object::Str(path)) => std::path::Path::new(path)
+                        .file_stem()
+                        .map(|stem| stem.to_string_lossy().into_owned())
+                        .unwrap_or_default(),
+                    _ => String::new(),
+                };
+                let is_identifier = stem.starts_with(|c: char| c.is_alphabetic() || c == '_')
+                    && stem.chars().all(|c| c.is_alphanumeric() || c == '_');
+                if !is_identifier {
+                    return Err(LoxResult::Parser {
+                        token: path,
+                        error_type: ParserErrorType::InvalidModuleName,
+                        msg: String::new(),
+                    });
+                }
+
+                // The name stands where the path is
+                Token::identifier(
+                    path.src_line,
+                    path.src_start,
+                    path.src_end,
+                    TokenType::Identifier,
+                    &stem,
+                )
+            }
+        };
+
+        self.consume(TokenType::Semicolon, "Expected ';' after import.")?;
+
+        Ok(Stmt::Import {
+            keyword,
+            name,
+            path,
+        })
+    }
+
     /**
      * This function is called when the next tokens are expected to contain a top level statement.
      * It will redirect to the correct token-parsing-function based on the first parsed token ]
@@ -259,6 +371,33 @@ impl<'a> Parser<'a> {
             return self.return_statement();
         }
 
+        // Check if the next token is a 'break' statement
+        if self.matchs_next(&[TokenType::Break]) {
+            let keyword = self.previous();
+            self.consume(TokenType::Semicolon, "Expected ';' after 'break'.")?;
+            return Ok(Stmt::Break { keyword });
+        }
+
+        // Check if the next token is a 'continue' statement
+        if self.matchs_next(&[TokenType::Continue]) {
+            let keyword = self.previous();
+            self.consume(TokenType::Semicolon, "Expected ';' after 'continue'.")?;
+            return Ok(Stmt::Continue { keyword });
+        }
+
+        // Check if the next token is a 'throw' statement
+        if self.matchs_next(&[TokenType::Throw]) {
+            let keyword = self.previous();
+            let value = self.expression()?;
+            self.consume(TokenType::Semicolon, "Expected ';' after thrown value.")?;
+            return Ok(Stmt::Throw { keyword, value });
+        }
+
+        // Check if the next token is the start of a try statement
+        if self.matchs_next(&[TokenType::Try]) {
+            return self.try_statement();
+        }
+
         // Check if the next statement is a 'while' loop
         if self.matchs_next(&[TokenType::While]) {
             return self.while_statement();
@@ -293,11 +432,12 @@ impl<'a> Parser<'a> {
      *   var i = 0;
      *   while (i < 10) {
      *     print i;
-     *     i = i + 1;
-     *   }
+     *   } // increment: i = i + 1
      * }
      * `
      *
+     * The increment is kept apart from the body in `Stmt::While`, so that it still runs after a
+     * `continue` statement.
      */
     fn for_statement(&mut self) -> Result<Stmt, LoxResult> {
         // The next token to come after 'for' must be an opening '('
@@ -326,7 +466,7 @@ impl<'a> Parser<'a> {
             condition = Some(self.expression()?);
         }
         // Check that the condition is correctly followed by a ';'
-        self.consume(TokenType::Semicolon, "Expected ';' after loop condition.")?;
+        let semicolon = self.consume(TokenType::Semicolon, "Expected ';' after loop condition.")?;
 
         // Parsing the increment if any
         let mut increment = None;
@@ -345,32 +485,27 @@ impl<'a> Parser<'a> {
         // e.g in the example above: "print i;"
         let mut body = self.statement()?;
 
-        // If there were an increment, write an iteration of it at the end of the body.
-        // e.g in the example above: "i = i + 1"
-        if let Some(i) = increment {
-            body = Stmt::Block {
-                statements: vec![body, Stmt::Expression { expression: i }],
-            }
-        }
-
         // If there weren't any condition, write a true literal expression instead to a perform a
         // while (true) infinite loop.
         if condition.is_none() {
             condition = Some(Expr::Literal {
+                token: semicolon,
                 value: Some(Object::True),
             });
         }
-        // Put the current body into a while expression with its condition
+        // Put the current body into a while expression with its condition and its increment
+        // e.g in the example above: "i = i + 1"
         body = Stmt::While {
             condition: condition.unwrap(),
             body: Box::new(body),
+            increment,
         };
 
         // If there were any initializer, put it at the beggining of the new tranformed code
         // e.g in the example above: "var i = 0;"
-        if initializer.is_some() {
+        if let Some(initializer) = initializer {
             body = Stmt::Block {
-                statements: vec![initializer.unwrap(), body],
+                statements: vec![initializer, body],
             };
         }
 
@@ -413,12 +548,17 @@ impl<'a> Parser<'a> {
      * Parses the next tokens in a print statement.
      */
     fn print_statement(&mut self) -> Result<Stmt, LoxResult> {
+        // Get the 'print' keyword
+        let keyword = self.previous();
         // Parse the value to print as an expression
         let value = self.expression()?;
         // Check the statement ends with a semicolon.
         self.consume(TokenType::Semicolon, "Expected ';' after value.")?;
         // Return the parsed print statement
-        Ok(Stmt::Print { expression: value })
+        Ok(Stmt::Print {
+            keyword,
+            expression: value,
+        })
     }
 
     /**
@@ -440,6 +580,55 @@ impl<'a> Parser<'a> {
         Ok(Stmt::Return { keyword, value })
     }
 
+    /**
+     * Parses the next tokens as part of a try statement, followed by a `catch` clause, a
+     * `finally` clause or both: `try { ... } catch (error) { ... } finally { ... }`.
+     */
+    fn try_statement(&mut self) -> Result<Stmt, LoxResult> {
+        // Get the 'try' keyword
+        let keyword = self.previous();
+
+        // Parse the guarded block
+        self.consume(TokenType::LeftBrace, "Expected '{' after 'try'.")?;
+        let body = self.block_statement()?;
+
+        // Parse the optional 'catch' clause, naming the caught value
+        let mut catch_name = None;
+        let mut catch_body = Vec::new();
+        if self.matchs_next(&[TokenType::Catch]) {
+            self.consume(TokenType::LeftParen, "Expected '(' after 'catch'.")?;
+            catch_name =
+                Some(self.consume(TokenType::Identifier, "Expected name of the caught value.")?);
+            self.consume(
+                TokenType::RightParen,
+                "Expected ')' after caught value name.",
+            )?;
+            self.consume(TokenType::LeftBrace, "Expected '{' before catch body.")?;
+            catch_body = self.block_statement()?;
+        }
+
+        // Parse the optional 'finally' clause, required if there isn't any 'catch' one
+        let mut finally_body = Vec::new();
+        if self.matchs_next(&[TokenType::Finally]) {
+            self.consume(TokenType::LeftBrace, "Expected '{' after 'finally'.")?;
+            finally_body = self.block_statement()?;
+        } else if catch_name.is_none() {
+            return Err(LoxResult::Parser {
+                token: self.peek(),
+                error_type: ParserErrorType::InvalidConsumeType,
+                msg: "Expected 'catch' or 'finally' after try block.".to_string(),
+            });
+        }
+
+        Ok(Stmt::Try {
+            keyword,
+            body,
+            catch_name,
+            catch_body,
+            finally_body,
+        })
+    }
+
     /**
      * Parses the next tokens as part of a while statement.
      */
@@ -461,6 +650,7 @@ impl<'a> Parser<'a> {
         Ok(Stmt::While {
             condition,
             body: Box::new(body),
+            increment: None,
         })
     }
 
@@ -472,7 +662,7 @@ impl<'a> Parser<'a> {
         let mut stmts = Vec::new();
         // Parse a statement until we reach a closing '}' brace or the end of the source code
         while !self.check(TokenType::RightBrace) && !self.is_at_end() {
-            if let Some(s) = self.declaration()? {
+            if let Some(s) = self.declaration() {
                 stmts.push(s);
             }
         }
@@ -521,13 +711,14 @@ impl<'a> Parser<'a> {
             // Get the value after the '=' sign
             let value = self.assignment()?;
             // Check if we are in the case of 'var a = x;'
-            if let Expr::Variable { name } = expr {
+            if let Expr::Variable { id, name } = expr {
                 return Ok(Expr::Assign {
+                    id,
                     name,
                     value: Box::new(value),
                 });
             }
-            // Check if we are in the case 'a = x;'
+            // Check if we are in the case 'a.b = x;'
             else if let Expr::Get { object, name } = expr {
                 return Ok(Expr::Set {
                     object,
@@ -535,6 +726,20 @@ impl<'a> Parser<'a> {
                     value: Box::new(value),
                 });
             }
+            // Check if we are in the case 'a[i] = x;'
+            else if let Expr::Index {
+                object,
+                bracket,
+                index,
+            } = expr
+            {
+                return Ok(Expr::SetIndex {
+                    object,
+                    bracket,
+                    index,
+                    value: Box::new(value),
+                });
+            }
             // Else, we're have an error because x is unknown
             else {
                 return Err(LoxResult::Parser {
@@ -731,6 +936,16 @@ impl<'a> Parser<'a> {
                     object: Box::new(expr),
                     name,
                 };
+            } else if self.matchs_next(&[TokenType::LeftBracket]) {
+                // Parse the index enclosed in []
+                let index = self.expression()?;
+                let bracket =
+                    self.consume(TokenType::RightBracket, "Expected closing ']' after index.")?;
+                expr = Expr::Index {
+                    object: Box::new(expr),
+                    bracket,
+                    index: Box::new(index),
+                };
             } else {
                 break;
             }
@@ -779,6 +994,73 @@ impl<'a> Parser<'a> {
         })
     }
 
+    /**
+     * Parses the elements of a list literal, after its opening '['.
+     */
+    fn list(&mut self) -> Result<Expr, LoxResult> {
+        // The optional elements list
+        let mut elements = Vec::new();
+
+        // If there are elements to parse, do it
+        if !self.check(TokenType::RightBracket) {
+            loop {
+                // Parse and store the next element
+                elements.push(self.expression()?);
+
+                // Stop when the next token is not a comma
+                if !self.matchs_next(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+
+        // Parse the closing ']' after the elements
+        let bracket = self.consume(
+            TokenType::RightBracket,
+            "Expected closing ']' after list elements.",
+        )?;
+
+        Ok(Expr::List { bracket, elements })
+    }
+
+    /**
+     * Parses the entries of a map literal, after its opening '{'.
+     */
+    fn map(&mut self) -> Result<Expr, LoxResult> {
+        let brace = self.previous();
+
+        // The optional entries of the map
+        let mut keys = Vec::new();
+        let mut values = Vec::new();
+
+        // If there are entries to parse, do it
+        if !self.check(TokenType::RightBrace) {
+            loop {
+                // Parse the key, the ':' and the value of the next entry
+                keys.push(self.expression()?);
+                self.consume(TokenType::Colon, "Expected ':' after map key.")?;
+                values.push(self.expression()?);
+
+                // Stop when the next token is not a comma
+                if !self.matchs_next(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+
+        // Parse the closing '}' after the entries
+        self.consume(
+            TokenType::RightBrace,
+            "Expected closing '}' after map entries.",
+        )?;
+
+        Ok(Expr::Map {
+            brace,
+            keys,
+            values,
+        })
+    }
+
     /**
      * Parses the next single token as a primary expression, meaning a string, number, boolean,
      * Nil or an identifier (example: variable/function name).
@@ -787,6 +1069,7 @@ impl<'a> Parser<'a> {
         // Parse False
         if self.matchs_next(&[TokenType::False]) {
             return Ok(Expr::Literal {
+                token: self.previous(),
                 value: Some(Object::False),
             });
         }
@@ -794,6 +1077,7 @@ impl<'a> Parser<'a> {
         // Parse an identifier
         if self.matchs_next(&[TokenType::Identifier]) {
             return Ok(Expr::Variable {
+                id: next_expr_id(),
                 name: self.previous(),
             });
         }
@@ -813,6 +1097,7 @@ impl<'a> Parser<'a> {
         // Parse Nil
         if self.matchs_next(&[TokenType::Nil]) {
             return Ok(Expr::Literal {
+                token: self.previous(),
                 value: Some(Object::Nil),
             });
         }
@@ -820,10 +1105,33 @@ impl<'a> Parser<'a> {
         // Parse a number or a string
         if self.matchs_next(&[TokenType::Number, TokenType::String]) {
             return Ok(Expr::Literal {
+                token: self.previous(),
                 value: self.previous().literal,
             });
         }
 
+        // Parse an anonymous function
+        if self.matchs_next(&[TokenType::Fun]) {
+            let keyword = self.previous();
+            self.consume(TokenType::LeftParen, "Expected opening '(' after 'fun'.")?;
+            let (params, body) = self.function_params_and_body("function")?;
+            return Ok(Expr::Lambda {
+                keyword,
+                params,
+                body,
+            });
+        }
+
+        // Parse a list literal
+        if self.matchs_next(&[TokenType::LeftBracket]) {
+            return self.list();
+        }
+
+        // Parse a map literal
+        if self.matchs_next(&[TokenType::LeftBrace]) {
+            return self.map();
+        }
+
         // Parse the 'super' keyword
         if self.matchs_next(&[TokenType::Super]) {
             // Parse the keyword itself
@@ -836,12 +1144,17 @@ impl<'a> Parser<'a> {
                 "Expected superclass method name after '.'.",
             )?;
             // Return the built `Expr::Super` variant
-            return Ok(Expr::Super { keyword, method });
+            return Ok(Expr::Super {
+                id: next_expr_id(),
+                keyword,
+                method,
+            });
         }
 
         // Parse 'this' keyword
         if self.matchs_next(&[TokenType::This]) {
             return Ok(Expr::This {
+                id: next_expr_id(),
                 keyword: self.previous(),
             });
         }
@@ -849,6 +1162,7 @@ impl<'a> Parser<'a> {
         // Parse True
         if self.matchs_next(&[TokenType::True]) {
             return Ok(Expr::Literal {
+                token: self.previous(),
                 value: Some(Object::True),
             });
         }
@@ -857,7 +1171,7 @@ impl<'a> Parser<'a> {
         Err(LoxResult::Parser {
             token: self.tokens[self.current].clone(),
             error_type: ParserErrorType::ExpectedExpression,
-            msg: "".to_string(),
+            msg: "Expected expression.".to_string(),
         })
     }
 
@@ -914,6 +1228,19 @@ impl<'a> Parser<'a> {
         self.peek().ttype == ttype
     }
 
+    /**
+     * Checks if the token after the next one is of the desired 'ttype' type.
+     */
+    fn check_next(&self, ttype: TokenType) -> bool {
+        // If there is no token after the next one, return false
+        if self.is_at_end() || self.tokens[self.current + 1].ttype == TokenType::Eof {
+            return false;
+        }
+
+        // Return the token type comparison result
+        self.tokens[self.current + 1].ttype == ttype
+    }
+
     /**
      * Returns the next token in the array and increment the current index by one.
      */
@@ -953,36 +1280,48 @@ impl<'a> Parser<'a> {
     }
 
     /**
-     * Advances if the tokens until reaching a ';' that would mark the end of the bad code.
-     * This function allows for the parser to continue process code even after encountering an
-     * error it in.
+     * Advances the tokens until reaching a statement boundary that would mark the end of the bad
+     * code: right after a ';' or right before a keyword starting a new statement. This function
+     * allows for the parser to continue process code even after encountering an error in it.
+     *
+     * Note: The token the error is at is kept if it starts a new statement, as when a ';' is
+     * missing before a declaration.
      */
     fn synchronize(&mut self) {
-        // Parse at least one token
-        self.advance();
+        // Skip the token the error is at, unless the next statement starts there
+        if !self.starts_statement() {
+            self.advance();
+        }
 
         // We can go up to the end of the whole code if there aren't any way to recover before
         while !self.is_at_end() {
-            // If we find a semicolon, we can return
-            if self.previous().ttype == TokenType::Semicolon {
+            // If we find a semicolon or a token starting a new statement, we can return
+            if self.previous().ttype == TokenType::Semicolon || self.starts_statement() {
                 return;
             }
 
-            // Why is that here ?
-            match self.peek().ttype {
-                TokenType::Class => {}
-                TokenType::Fun => {}
-                TokenType::Var => {}
-                TokenType::For => {}
-                TokenType::If => {}
-                TokenType::While => {}
-                TokenType::Print => {}
-                TokenType::Return => {} // TokenType::Class => {}
-                _ => {}
-            }
-
             // Advance by one token
             self.advance();
         }
     }
+
+    /**
+     * Checks if the current token is a keyword starting a new statement.
+     */
+    fn starts_statement(&self) -> bool {
+        matches!(
+            self.peek().ttype,
+            TokenType::Class
+                | TokenType::Fun
+                | TokenType::Var
+                | TokenType::Import
+                | TokenType::For
+                | TokenType::If
+                | TokenType::While
+                | TokenType::Print
+                | TokenType::Return
+                | TokenType::Throw
+                | TokenType::Try
+        )
+    }
 }
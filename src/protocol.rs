@@ -0,0 +1,49 @@
+use std::io::{self, BufRead, Write};
+
+use serde_json::Value;
+
+/**
+ * Reads the next message of the base protocol shared by the Debug Adapter Protocol and the
+ * Language Server Protocol: a JSON object preceded by a `Content-Length` header.
+ *
+ * Returns `None` once the input is closed. Messages that aren't valid JSON are skipped.
+ */
+pub fn read_message(input: &mut dyn BufRead) -> Option<Value> {
+    loop {
+        // Read the headers up to the empty line, only the length of the content matters
+        let mut length = None;
+        loop {
+            let mut header = String::new();
+            if input.read_line(&mut header).ok()? == 0 {
+                return None;
+            }
+            let header = header.trim();
+            if header.is_empty() {
+                break;
+            }
+            if let Some(value) = header.strip_prefix("Content-Length:") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+
+        let Some(length) = length else {
+            continue;
+        };
+        let mut content = vec![0; length];
+        input.read_exact(&mut content).ok()?;
+        if let Ok(message) = serde_json::from_slice(&content) {
+            return Some(message);
+        }
+    }
+}
+
+/**
+ * Writes a message of the base protocol, preceded by its `Content-Length` header.
+ *
+ * Returns an error if the output is closed, e.g. the client went away.
+ */
+pub fn write_message(output: &mut dyn Write, message: &Value) -> io::Result<()> {
+    let content = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{content}", content.len())?;
+    output.flush()
+}
@@ -22,15 +22,35 @@ enum ClassType {
     Class,
 }
 
+#[derive(PartialEq, Clone, Copy)]
+enum LoopType {
+    None,
+    Loop,
+}
+
+/**
+ * A local variable declared in a scope.
+ */
+#[derive(Clone, Copy)]
+struct Local {
+    /// Whether the variable can be used, i.e. its initializer was resolved.
+    defined: bool,
+    /// The slot of the variable in the environment of its scope, given in order of declaration.
+    slot: usize,
+}
+
 pub struct Resolver<'i> {
     /// The resolver will use an interpreter instance in order to check that the code is correct.
-    interpreter: &'i mut Interpreter,
+    /// Without one, the resolver only reports the static errors of the code.
+    interpreter: Option<&'i mut Interpreter>,
     /// This is a stack that will contain declared/defined names.
-    scopes: RefCell<Vec<HashMap<String, bool>>>,
+    scopes: RefCell<Vec<HashMap<String, Local>>>,
     /// The type of the current function.
     current_function: FunctionType,
     /// The type of the current class.
     current_class: ClassType,
+    /// Whether we are currently in the body of a loop.
+    current_loop: LoopType,
 }
 
 impl<'a> StmtVisitor<()> for Resolver<'a> {
@@ -74,7 +94,46 @@ impl<'a> StmtVisitor<()> for Resolver<'a> {
         Ok(())
     }
 
-    fn visit_print_stmt(&mut self, expression: &Expr) -> Result<(), LoxResult> {
+    fn visit_import_stmt(
+        &mut self,
+        _keyword: &Token,
+        name: &Token,
+        _path: &Token,
+    ) -> Result<(), LoxResult> {
+        self.declare(name)?;
+        self.define(name);
+
+        Ok(())
+    }
+
+    fn visit_throw_stmt(&mut self, _keyword: &Token, value: &Expr) -> Result<(), LoxResult> {
+        self.resolve_expr(value)?;
+        Ok(())
+    }
+
+    fn visit_try_stmt(
+        &mut self,
+        _keyword: &Token,
+        body: &[Stmt],
+        catch_name: &Option<Token>,
+        catch_body: &[Stmt],
+        finally_body: &[Stmt],
+    ) -> Result<(), LoxResult> {
+        self.visit_block_stmt(body)?;
+
+        // The caught value is the first local of the catch clause's scope
+        if let Some(name) = catch_name {
+            self.begin_scope();
+            self.declare(name)?;
+            self.define(name);
+            self.resolve_stmts(catch_body)?;
+            self.end_scope();
+        }
+
+        self.visit_block_stmt(finally_body)
+    }
+
+    fn visit_print_stmt(&mut self, _keyword: &Token, expression: &Expr) -> Result<(), LoxResult> {
         self.resolve_expr(expression)?;
         Ok(())
     }
@@ -117,9 +176,45 @@ impl<'a> StmtVisitor<()> for Resolver<'a> {
         Ok(())
     }
 
-    fn visit_while_stmt(&mut self, condition: &Expr, body: &Stmt) -> Result<(), LoxResult> {
+    fn visit_while_stmt(
+        &mut self,
+        condition: &Expr,
+        body: &Stmt,
+        increment: &Option<Expr>,
+    ) -> Result<(), LoxResult> {
         self.resolve_expr(condition)?;
+
+        // Resolve the body knowing that we are in a loop
+        let enclosing_loop = self.current_loop;
+        self.current_loop = LoopType::Loop;
         self.resolve_stmt(body)?;
+        self.current_loop = enclosing_loop;
+
+        if let Some(increment) = increment {
+            self.resolve_expr(increment)?;
+        }
+
+        Ok(())
+    }
+
+    fn visit_break_stmt(&mut self, keyword: &Token) -> Result<(), LoxResult> {
+        if self.current_loop == LoopType::None {
+            return Err(LoxResult::Resolver {
+                token: keyword.to_owned(),
+                error_type: ResolverErrorType::BreakOutsideLoop,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn visit_continue_stmt(&mut self, keyword: &Token) -> Result<(), LoxResult> {
+        if self.current_loop == LoopType::None {
+            return Err(LoxResult::Resolver {
+                token: keyword.to_owned(),
+                error_type: ResolverErrorType::ContinueOutsideLoop,
+            });
+        }
 
         Ok(())
     }
@@ -132,6 +227,8 @@ impl<'a> StmtVisitor<()> for Resolver<'a> {
         name: &Token,
         superclass: &Option<Expr>,
         methods: &[Stmt],
+        class_methods: &[Stmt],
+        getters: &[Stmt],
     ) -> Result<(), LoxResult> {
         // Change `self.current_class` because we are now in a class
         let enclosing_class = self.current_class;
@@ -143,7 +240,10 @@ impl<'a> StmtVisitor<()> for Resolver<'a> {
         // If the current class has a superclass, resolve it
         if let Some(superclass) = superclass {
             // Check for a self inherited class
-            if let Expr::Variable { name: super_name } = superclass {
+            if let Expr::Variable {
+                name: super_name, ..
+            } = superclass
+            {
                 if super_name == name {
                     return Err(LoxResult::Resolver {
                         token: name.to_owned(),
@@ -157,21 +257,13 @@ impl<'a> StmtVisitor<()> for Resolver<'a> {
 
             // Define the `super` keyword in a new scope
             self.begin_scope();
-            self.scopes
-                .borrow_mut()
-                .last_mut()
-                .unwrap()
-                .insert("super".to_string(), true);
+            self.define_keyword("super");
         }
 
         // Start the class scope
         self.begin_scope();
         // Insert the 'this' keyword as it should always be defined
-        self.scopes
-            .borrow_mut()
-            .last_mut()
-            .unwrap()
-            .insert("this".to_string(), true);
+        self.define_keyword("this");
 
         // For each method of the class, resolve it
         for method in methods {
@@ -192,6 +284,16 @@ impl<'a> StmtVisitor<()> for Resolver<'a> {
             };
         }
 
+        // Resolve the static methods and getters. Static methods are bound to the class and
+        // getters to the instance, so they both see a 'this', but neither is an initializer.
+        for method in class_methods.iter().chain(getters) {
+            if let Stmt::Function { params, body, .. } = method {
+                self.resolve_function(params, body, FunctionType::Method)?;
+            } else {
+                unreachable!()
+            };
+        }
+
         // End the class scope
         self.end_scope();
         // If there was a superclass, close its scope
@@ -206,9 +308,14 @@ impl<'a> StmtVisitor<()> for Resolver<'a> {
 }
 
 impl<'a> ExprVisitor<()> for Resolver<'a> {
-    fn visit_assign_expr(&mut self, name: &Token, value: &Expr) -> Result<(), LoxResult> {
+    fn visit_assign_expr(
+        &mut self,
+        id: &usize,
+        name: &Token,
+        value: &Expr,
+    ) -> Result<(), LoxResult> {
         self.resolve_expr(value)?;
-        self.resolve_local(name);
+        self.resolve_local(*id, name);
         Ok(())
     }
 
@@ -259,13 +366,21 @@ impl<'a> ExprVisitor<()> for Resolver<'a> {
         Ok(())
     }
 
-    fn visit_literal_expr(&mut self, _value: &Option<Object>) -> Result<(), LoxResult> {
+    fn visit_literal_expr(
+        &mut self,
+        _token: &Token,
+        _value: &Option<Object>,
+    ) -> Result<(), LoxResult> {
         Ok(())
     }
 
-    fn visit_variable_expr(&mut self, name: &Token) -> Result<(), LoxResult> {
-        if !self.scopes.borrow().is_empty()
-            && self.scopes.borrow().last().unwrap().get(&name.lexeme) == Some(&false)
+    fn visit_variable_expr(&mut self, id: &usize, name: &Token) -> Result<(), LoxResult> {
+        if self
+            .scopes
+            .borrow()
+            .last()
+            .and_then(|scope| scope.get(&name.lexeme))
+            .is_some_and(|local| !local.defined)
         {
             return Err(LoxResult::Resolver {
                 token: name.clone(),
@@ -273,7 +388,7 @@ impl<'a> ExprVisitor<()> for Resolver<'a> {
             });
         }
 
-        self.resolve_local(name);
+        self.resolve_local(*id, name);
 
         Ok(())
     }
@@ -295,19 +410,72 @@ impl<'a> ExprVisitor<()> for Resolver<'a> {
         Ok(())
     }
 
-    fn visit_this_expr(&mut self, keyword: &Token) -> Result<(), LoxResult> {
+    fn visit_lambda_expr(
+        &mut self,
+        _keyword: &Token,
+        params: &[Token],
+        body: &[Stmt],
+    ) -> Result<(), LoxResult> {
+        self.resolve_function(params, body, FunctionType::Function)
+    }
+
+    fn visit_list_expr(&mut self, _bracket: &Token, elements: &[Expr]) -> Result<(), LoxResult> {
+        self.resolve_exprs(elements)
+    }
+
+    fn visit_map_expr(
+        &mut self,
+        _brace: &Token,
+        keys: &[Expr],
+        values: &[Expr],
+    ) -> Result<(), LoxResult> {
+        self.resolve_exprs(keys)?;
+        self.resolve_exprs(values)?;
+        Ok(())
+    }
+
+    fn visit_index_expr(
+        &mut self,
+        object: &Expr,
+        _bracket: &Token,
+        index: &Expr,
+    ) -> Result<(), LoxResult> {
+        self.resolve_expr(object)?;
+        self.resolve_expr(index)?;
+        Ok(())
+    }
+
+    fn visit_setindex_expr(
+        &mut self,
+        object: &Expr,
+        _bracket: &Token,
+        index: &Expr,
+        value: &Expr,
+    ) -> Result<(), LoxResult> {
+        self.resolve_expr(value)?;
+        self.resolve_expr(object)?;
+        self.resolve_expr(index)?;
+        Ok(())
+    }
+
+    fn visit_this_expr(&mut self, id: &usize, keyword: &Token) -> Result<(), LoxResult> {
         if self.current_class == ClassType::None {
             return Err(LoxResult::Resolver {
                 token: keyword.clone(),
                 error_type: ResolverErrorType::ThisOutsideClass,
             });
         }
-        self.resolve_local(keyword);
+        self.resolve_local(*id, keyword);
         Ok(())
     }
 
-    fn visit_super_expr(&mut self, keyword: &Token, method: &Token) -> Result<(), LoxResult> {
-        self.resolve_local(keyword);
+    fn visit_super_expr(
+        &mut self,
+        id: &usize,
+        keyword: &Token,
+        _method: &Token,
+    ) -> Result<(), LoxResult> {
+        self.resolve_local(*id, keyword);
         Ok(())
     }
 }
@@ -315,13 +483,38 @@ impl<'a> ExprVisitor<()> for Resolver<'a> {
 impl<'a> Resolver<'a> {
     pub fn new(interpreter: &'a mut Interpreter) -> Self {
         Self {
-            interpreter,
+            interpreter: Some(interpreter),
+            ..Self::checker()
+        }
+    }
+
+    /**
+     * Instanciates a resolver that only checks the code for static errors (e.g. `return` at
+     * top level), used when the code doesn't run on the tree-walking interpreter.
+     */
+    pub fn checker() -> Self {
+        Self {
+            interpreter: None,
             scopes: RefCell::new(Vec::new()),
             current_function: FunctionType::Void,
             current_class: ClassType::None,
+            current_loop: LoopType::None,
         }
     }
 
+    /**
+     * Checks each top level statement on its own and returns the first static error of each, so
+     * that an editor can report more than the first error of a file.
+     *
+     * Note: Top level statements only share the global scope, which the resolver doesn't track.
+     */
+    pub fn check_all(stmts: &[Stmt]) -> Vec<LoxResult> {
+        stmts
+            .iter()
+            .filter_map(|stmt| Resolver::checker().resolve_stmt(stmt).err())
+            .collect()
+    }
+
     /**
      * Function called when entering a new scope. It simply appends a new HashMap into the
      * `self.scopes` property of the `Resolver`.
@@ -343,38 +536,50 @@ impl<'a> Resolver<'a> {
             return Ok(());
         }
 
-        if self
-            .scopes
-            .borrow_mut()
-            .last_mut()
-            .unwrap()
-            .contains_key(&name.lexeme)
-        {
+        let mut scopes = self.scopes.borrow_mut();
+        let scope = scopes.last_mut().unwrap();
+        if scope.contains_key(&name.lexeme) {
             return Err(LoxResult::Resolver {
                 token: name.clone(),
                 error_type: ResolverErrorType::VariableAlreadyExists,
             });
         }
 
-        self.scopes
-            .borrow_mut()
-            .last_mut()
-            .unwrap()
-            .insert(name.lexeme.clone(), false);
+        // The variable takes the next slot of the scope's environment
+        let slot = scope.len();
+        scope.insert(
+            name.lexeme.clone(),
+            Local {
+                defined: false,
+                slot,
+            },
+        );
 
         Ok(())
     }
 
     fn define(&self, name: &Token) {
-        if self.scopes.borrow().is_empty() {
-            return;
-        }
-
-        self.scopes
+        if let Some(local) = self
+            .scopes
             .borrow_mut()
             .last_mut()
-            .unwrap()
-            .insert(name.lexeme.clone(), true);
+            .and_then(|scope| scope.get_mut(&name.lexeme))
+        {
+            local.defined = true;
+        }
+    }
+
+    /**
+     * Declares and defines a keyword (`this` or `super`) as the only variable of a new scope.
+     */
+    fn define_keyword(&self, keyword: &str) {
+        self.scopes.borrow_mut().last_mut().unwrap().insert(
+            keyword.to_string(),
+            Local {
+                defined: true,
+                slot: 0,
+            },
+        );
     }
 
     pub fn resolve_stmts(&mut self, stmts: &[Stmt]) -> Result<(), LoxResult> {
@@ -394,13 +599,18 @@ impl<'a> Resolver<'a> {
     }
 
     /**
-     * Calls the interpreter's resolve function once the object is found.
+     * Looks for the variable `name` from the innermost scope outward and calls the interpreter's
+     * resolve function once it is found.
+     *
+     * Note: A variable that is not found is global, and is looked up by name at runtime.
      */
-    fn resolve_local(&mut self, name: &Token) {
-        for i in self.scopes.borrow().len()..0 {
-            if self.scopes.borrow()[i].contains_key(&name.lexeme) {
-                self.interpreter
-                    .resolve(name, self.scopes.borrow().len() - 1 - i);
+    fn resolve_local(&mut self, id: usize, name: &Token) {
+        let scopes = self.scopes.borrow();
+        for (depth, scope) in scopes.iter().rev().enumerate() {
+            if let Some(local) = scope.get(&name.lexeme) {
+                if let Some(interpreter) = self.interpreter.as_deref_mut() {
+                    interpreter.resolve(id, depth, local.slot);
+                }
                 break;
             }
         }
@@ -421,6 +631,9 @@ impl<'a> Resolver<'a> {
         let ftype = self.current_function;
         // Set the current function type to the one we're currently declaring
         self.current_function = function_type;
+        // A function body is never in a loop, even if the function is declared in one
+        let enclosing_loop = self.current_loop;
+        self.current_loop = LoopType::None;
 
         // Start a new scope
         self.begin_scope();
@@ -438,6 +651,7 @@ impl<'a> Resolver<'a> {
 
         // Set back the current function type being resolve to that we were before on
         self.current_function = ftype;
+        self.current_loop = enclosing_loop;
 
         Ok(())
     }
@@ -9,19 +9,26 @@ lazy_static! {
     /// An `HashMap` containing the reserved words of the lox language.
     static ref RESERVED_IDENTIFIERS: HashMap<String, TokenType> = HashMap::from([
         ("and".to_string(), TokenType::And),
+        ("break".to_string(), TokenType::Break),
+        ("catch".to_string(), TokenType::Catch),
         ("class".to_string(), TokenType::Class),
+        ("continue".to_string(), TokenType::Continue),
         ("else".to_string(), TokenType::Else),
         ("false".to_string(), TokenType::False),
+        ("finally".to_string(), TokenType::Finally),
         ("for".to_string(), TokenType::For),
         ("fun".to_string(), TokenType::Fun),
         ("if".to_string(), TokenType::If),
+        ("import".to_string(), TokenType::Import),
         ("nil".to_string(), TokenType::Nil),
         ("or".to_string(), TokenType::Or),
         ("print".to_string(), TokenType::Print),
         ("return".to_string(), TokenType::Return),
         ("super".to_string(), TokenType::Super),
         ("this".to_string(), TokenType::This),
+        ("throw".to_string(), TokenType::Throw),
         ("true".to_string(), TokenType::True),
+        ("try".to_string(), TokenType::Try),
         ("var".to_string(), TokenType::Var),
         ("while".to_string(), TokenType::While),
     ]);
@@ -35,12 +42,16 @@ pub struct Scanner {
     pub source: String,
     /// The vector of `Token` parsed.
     pub tokens: Vec<Token>,
+    /// The comments met along the way, which the parser doesn't need.
+    pub comments: Vec<Comment>,
     /// The start of the current token (index in `self.source`).
     start: usize,
     /// The index in `self.source` the scanner is currently at.
     current: usize,
     /// The current line number being scanned.
     line: usize,
+    /// The source the tokens are scanned from.
+    source_id: SourceId,
 }
 
 impl Scanner {
@@ -48,13 +59,23 @@ impl Scanner {
      * Instanciates a new `Scanner` from raw source code as a String.
      */
     pub fn new(source: &str) -> Scanner {
+        Scanner::with_source_id(source, SourceId::MAIN)
+    }
+
+    /**
+     * Instanciates a new `Scanner` whose tokens tell they come from the source `source_id` (e.g.
+     * an imported module).
+     */
+    pub fn with_source_id(source: &str, source_id: SourceId) -> Scanner {
         Scanner {
             source: source.to_owned(),
             tokens: Vec::new(),
+            comments: Vec::new(),
             start: 0,
             current: 0,
             // Source code is written from line 1
             line: 1,
+            source_id,
         }
     }
 
@@ -73,6 +94,10 @@ impl Scanner {
 
         // Append a terminal `Eof` token at the end of the source code.
         self.tokens.push(Token::eof(self.line, self.current));
+        // Tell which source the tokens come from
+        for token in &mut self.tokens {
+            token.src_id = self.source_id;
+        }
 
         // Return the parsed tokens
         Ok(&self.tokens)
@@ -93,61 +118,66 @@ impl Scanner {
         let c = self.advance();
         match c {
             // Single character lexemes
-            '(' => self.tokens.push(Token::left_paren(self.line, self.current)),
-            ')' => self
+            '(' => self.tokens.push(Token::left_paren(self.line, self.start)),
+            ')' => self.tokens.push(Token::right_paren(self.line, self.start)),
+            '{' => self.tokens.push(Token::left_brace(self.line, self.start)),
+            '}' => self.tokens.push(Token::right_brace(self.line, self.start)),
+            '[' => self.tokens.push(Token::left_bracket(self.line, self.start)),
+            ']' => self
                 .tokens
-                .push(Token::right_paren(self.line, self.current)),
-            '{' => self.tokens.push(Token::left_brace(self.line, self.current)),
-            '}' => self
-                .tokens
-                .push(Token::right_brace(self.line, self.current)),
-            ',' => self.tokens.push(Token::comma(self.line, self.current)),
-            '.' => self.tokens.push(Token::dot(self.line, self.current)),
-            '-' => self.tokens.push(Token::minus(self.line, self.current)),
-            '+' => self.tokens.push(Token::plus(self.line, self.current)),
-            ';' => self.tokens.push(Token::semicolon(self.line, self.current)),
-            '*' => self.tokens.push(Token::star(self.line, self.current)),
+                .push(Token::right_bracket(self.line, self.start)),
+            ':' => self.tokens.push(Token::colon(self.line, self.start)),
+            ',' => self.tokens.push(Token::comma(self.line, self.start)),
+            '.' => self.tokens.push(Token::dot(self.line, self.start)),
+            '-' => self.tokens.push(Token::minus(self.line, self.start)),
+            '+' => self.tokens.push(Token::plus(self.line, self.start)),
+            ';' => self.tokens.push(Token::semicolon(self.line, self.start)),
+            '*' => self.tokens.push(Token::star(self.line, self.start)),
 
             // Two character lexemes
             '!' => {
                 if self.match_next('=') {
-                    self.tokens.push(Token::bang_equal(self.line, self.current));
+                    self.tokens.push(Token::bang_equal(self.line, self.start));
                 } else {
-                    self.tokens.push(Token::bang(self.line, self.current));
+                    self.tokens.push(Token::bang(self.line, self.start));
                 }
             }
             '=' => {
                 if self.match_next('=') {
-                    self.tokens
-                        .push(Token::equal_equal(self.line, self.current));
+                    self.tokens.push(Token::equal_equal(self.line, self.start));
                 } else {
-                    self.tokens.push(Token::equal(self.line, self.current));
+                    self.tokens.push(Token::equal(self.line, self.start));
                 }
             }
             '<' => {
                 if self.match_next('=') {
-                    self.tokens.push(Token::less_equal(self.line, self.current));
+                    self.tokens.push(Token::less_equal(self.line, self.start));
                 } else {
-                    self.tokens.push(Token::less(self.line, self.current));
+                    self.tokens.push(Token::less(self.line, self.start));
                 }
             }
             '>' => {
                 if self.match_next('=') {
                     self.tokens
-                        .push(Token::greater_equal(self.line, self.current));
+                        .push(Token::greater_equal(self.line, self.start));
                 } else {
-                    self.tokens.push(Token::greater(self.line, self.current));
+                    self.tokens.push(Token::greater(self.line, self.start));
                 }
             }
 
             // Special handling of '/' because it can be a comment.
             '/' => {
                 if self.match_next('/') {
+                    let mut text = String::from("//");
                     while self.peek() != '\n' && !self.is_at_end() {
-                        self.advance();
+                        text.push(self.advance());
                     }
+                    self.comments.push(Comment {
+                        text: text.trim_end().to_string(),
+                        span: self.span(),
+                    });
                 } else {
-                    self.tokens.push(Token::slash(self.line, self.current));
+                    self.tokens.push(Token::slash(self.line, self.start));
                 }
             }
 
@@ -167,13 +197,14 @@ impl Scanner {
             // Unexpected character, throw an error
             _ => {
                 // Number literals
-                if c.is_digit(10) {
+                if c.is_ascii_digit() {
                     self.scan_number()?;
                 } else if c.is_alphabetic() || c == '_' {
                     self.scan_identifier();
                 } else {
                     return Err(LoxResult::Scanner {
                         c,
+                        span: self.span(),
                         error_type: ScannerErrorType::InvalidCharacter,
                     });
                 }
@@ -183,6 +214,18 @@ impl Scanner {
         Ok(())
     }
 
+    /**
+     * Helper that returns the location of the token currently being scanned.
+     */
+    fn span(&self) -> Span {
+        Span {
+            source: self.source_id,
+            line: self.line,
+            start: self.start,
+            end: self.current,
+        }
+    }
+
     /**
      * Returns the next source code's character.
      *
@@ -228,7 +271,7 @@ impl Scanner {
         }
 
         // Return the current character
-        return self.source.chars().nth(self.current).unwrap();
+        self.source.chars().nth(self.current).unwrap()
     }
 
     /**
@@ -242,7 +285,7 @@ impl Scanner {
         }
 
         // Return the next character
-        return self.source.chars().nth(self.current + 1).unwrap();
+        self.source.chars().nth(self.current + 1).unwrap()
     }
 
     /**
@@ -267,6 +310,7 @@ impl Scanner {
         if self.is_at_end() {
             return Err(LoxResult::Scanner {
                 c: '"',
+                span: self.span(),
                 error_type: ScannerErrorType::UnterminatedString,
             });
         }
@@ -276,8 +320,12 @@ impl Scanner {
         // Get a substring of the source code using `self.start` and `self.current`
         let token_str = self.source.get(self.start + 1..self.current - 1).unwrap();
         // Push the parsed `Token::string` in `self.tokens`
-        self.tokens
-            .push(Token::string(self.line, self.current, token_str));
+        self.tokens.push(Token::string(
+            self.line,
+            self.start,
+            self.current,
+            token_str,
+        ));
 
         Ok(())
     }
@@ -290,16 +338,16 @@ impl Scanner {
      */
     fn scan_number(&mut self) -> Result<(), LoxResult> {
         // Advance as long as we find numbers
-        while self.peek().is_digit(10) {
+        while self.peek().is_ascii_digit() {
             self.advance();
         }
 
         // Check if we stopped by a `.` followed by another number
-        if self.peek() == '.' && self.peek_next().is_digit(10) {
+        if self.peek() == '.' && self.peek_next().is_ascii_digit() {
             // If so, advance after the `.`
             self.advance();
             // And advance as long as we find numbers
-            while self.peek().is_digit(10) {
+            while self.peek().is_ascii_digit() {
                 self.advance();
             }
         }
@@ -0,0 +1,633 @@
+use std::collections::HashMap;
+
+use crate::errors::LoxResult;
+use crate::expr::*;
+use crate::object::Object;
+use crate::stmt::*;
+use crate::token::{Span, Token};
+
+/**
+ * What a declared name stands for.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Variable,
+    Parameter,
+    Function,
+    Class,
+    /// A method of the instances of a class.
+    Method,
+    /// A method of the class itself.
+    ClassMethod,
+    Getter,
+    /// The namespace of an imported module.
+    Module,
+}
+
+/**
+ * A name declared in the source code.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// The location of the name in its declaration.
+    pub span: Span,
+    /// How the declaration reads, e.g. `fun add(a, b)` or `class Circle < Shape`.
+    pub signature: String,
+    /// The index of the class declaring this method, if it is one.
+    pub class: Option<usize>,
+}
+
+/**
+ * A use of a name in the source code, declarations included.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reference {
+    /// The location of the name.
+    pub span: Span,
+    /// The indices of the symbols the name may stand for. It is one symbol except for globals
+    /// declared more than once and for properties, which can be the methods of any class.
+    pub symbols: Vec<usize>,
+}
+
+/**
+ * A reference whose symbols are only known once the whole program was visited.
+ */
+enum Pending {
+    /// A variable that isn't local, looked up among the globals.
+    Global(usize, String),
+    /// A property (or `super` method), looked up among the methods of every class.
+    Property(usize, String),
+}
+
+/**
+ * An index of the names declared and used in a program, used by editors to go to definitions,
+ * find references or describe a name.
+ *
+ * Note: Like the `Resolver`, local variables are looked up in the enclosing scopes and the other
+ * variables are globals, which can be used before being declared.
+ */
+#[derive(Debug, Default)]
+pub struct SymbolIndex {
+    pub symbols: Vec<Symbol>,
+    pub references: Vec<Reference>,
+}
+
+/**
+ * Walks a program to build its `SymbolIndex`.
+ */
+struct Indexer {
+    index: SymbolIndex,
+    /// The local scopes, each mapping a name to its symbol.
+    scopes: Vec<HashMap<String, usize>>,
+    /// The global symbols of each name.
+    globals: HashMap<String, Vec<usize>>,
+    /// The methods (of any kind) of each name.
+    methods: HashMap<String, Vec<usize>>,
+    pending: Vec<Pending>,
+}
+
+impl SymbolIndex {
+    pub fn new(statements: &[Stmt]) -> Self {
+        let mut indexer = Indexer {
+            index: SymbolIndex::default(),
+            scopes: Vec::new(),
+            globals: HashMap::new(),
+            methods: HashMap::new(),
+            pending: Vec::new(),
+        };
+        indexer.statements(statements);
+
+        // Now that every global and method is known, complete the references to them
+        for pending in std::mem::take(&mut indexer.pending) {
+            let (reference, symbols) = match pending {
+                Pending::Global(reference, name) => (reference, indexer.globals.get(&name)),
+                Pending::Property(reference, name) => (reference, indexer.methods.get(&name)),
+            };
+            indexer.index.references[reference].symbols = symbols.cloned().unwrap_or_default();
+        }
+        indexer
+            .index
+            .references
+            .retain(|reference| !reference.symbols.is_empty());
+
+        indexer.index
+    }
+
+    /**
+     * Returns the reference at the given character offset of the source code.
+     */
+    pub fn reference_at(&self, offset: usize) -> Option<&Reference> {
+        self.references
+            .iter()
+            .find(|reference| reference.span.start <= offset && offset <= reference.span.end)
+    }
+
+    /**
+     * Returns the symbols the name at the given offset stands for.
+     */
+    pub fn definitions_at(&self, offset: usize) -> Vec<&Symbol> {
+        self.reference_at(offset)
+            .map(|reference| {
+                reference
+                    .symbols
+                    .iter()
+                    .map(|&i| &self.symbols[i])
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /**
+     * Returns the locations of every use of the symbols the name at the given offset stands for,
+     * with or without their declarations.
+     */
+    pub fn references_at(&self, offset: usize, include_declaration: bool) -> Vec<Span> {
+        let Some(target) = self.reference_at(offset) else {
+            return Vec::new();
+        };
+
+        self.references
+            .iter()
+            .filter(|reference| {
+                reference
+                    .symbols
+                    .iter()
+                    .any(|symbol| target.symbols.contains(symbol))
+            })
+            .filter(|reference| {
+                include_declaration
+                    || !reference
+                        .symbols
+                        .iter()
+                        .any(|&symbol| self.symbols[symbol].span == reference.span)
+            })
+            .map(|reference| reference.span)
+            .collect()
+    }
+}
+
+impl Indexer {
+    fn statements(&mut self, statements: &[Stmt]) {
+        for statement in statements {
+            // Indexing never fails
+            let _ = statement.accept(self);
+        }
+    }
+
+    fn expression(&mut self, expression: &Expr) {
+        let _ = expression.accept(self);
+    }
+
+    /**
+     * Adds a symbol declared in the current scope, along with the reference of its declaration.
+     */
+    fn declare(&mut self, name: &Token, kind: SymbolKind, signature: String) -> usize {
+        let symbol = self.add_symbol(name, kind, signature, None);
+        match self.scopes.last_mut() {
+            Some(scope) => {
+                scope.insert(name.lexeme.clone(), symbol);
+            }
+            None => self
+                .globals
+                .entry(name.lexeme.clone())
+                .or_default()
+                .push(symbol),
+        }
+
+        symbol
+    }
+
+    fn add_symbol(
+        &mut self,
+        name: &Token,
+        kind: SymbolKind,
+        signature: String,
+        class: Option<usize>,
+    ) -> usize {
+        let symbol = self.index.symbols.len();
+        self.index.symbols.push(Symbol {
+            name: name.lexeme.clone(),
+            kind,
+            span: name.span(),
+            signature,
+            class,
+        });
+        self.index.references.push(Reference {
+            span: name.span(),
+            symbols: vec![symbol],
+        });
+
+        symbol
+    }
+
+    /**
+     * Adds the use of a variable, bound to the innermost local declaring it or, failing that, to
+     * the globals of that name.
+     */
+    fn use_variable(&mut self, name: &Token) {
+        let local = self
+            .scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(&name.lexeme).copied());
+
+        let reference = self.index.references.len();
+        self.index.references.push(Reference {
+            span: name.span(),
+            symbols: local.into_iter().collect(),
+        });
+        if local.is_none() {
+            self.pending
+                .push(Pending::Global(reference, name.lexeme.clone()));
+        }
+    }
+
+    fn use_property(&mut self, name: &Token) {
+        let reference = self.index.references.len();
+        self.index.references.push(Reference {
+            span: name.span(),
+            symbols: Vec::new(),
+        });
+        self.pending
+            .push(Pending::Property(reference, name.lexeme.clone()));
+    }
+
+    /**
+     * Indexes the parameters and body of a function in their own scope.
+     */
+    fn function(&mut self, params: &[Token], body: &[Stmt]) {
+        self.scopes.push(HashMap::new());
+        for param in params {
+            self.declare(param, SymbolKind::Parameter, param.lexeme.clone());
+        }
+        self.statements(body);
+        self.scopes.pop();
+    }
+}
+
+/**
+ * Returns the names of the parameters between parentheses, e.g. `(a, b)`.
+ */
+fn parameters(params: &[Token]) -> String {
+    let params = params
+        .iter()
+        .map(|param| param.lexeme.as_str())
+        .collect::<Vec<&str>>();
+    format!("({})", params.join(", "))
+}
+
+impl StmtVisitor<()> for Indexer {
+    fn visit_block_stmt(&mut self, statements: &[Stmt]) -> Result<(), LoxResult> {
+        self.scopes.push(HashMap::new());
+        self.statements(statements);
+        self.scopes.pop();
+        Ok(())
+    }
+
+    fn visit_break_stmt(&mut self, _keyword: &Token) -> Result<(), LoxResult> {
+        Ok(())
+    }
+
+    fn visit_class_stmt(
+        &mut self,
+        name: &Token,
+        superclass: &Option<Expr>,
+        methods: &[Stmt],
+        class_methods: &[Stmt],
+        getters: &[Stmt],
+    ) -> Result<(), LoxResult> {
+        let signature = match superclass {
+            Some(Expr::Variable {
+                name: superclass, ..
+            }) => format!("class {} < {}", name.lexeme, superclass.lexeme),
+            _ => format!("class {}", name.lexeme),
+        };
+        let class = self.declare(name, SymbolKind::Class, signature);
+        if let Some(superclass) = superclass {
+            self.expression(superclass);
+        }
+
+        let kinds = [
+            (methods, SymbolKind::Method),
+            (class_methods, SymbolKind::ClassMethod),
+            (getters, SymbolKind::Getter),
+        ];
+        for (methods, kind) in kinds {
+            for method in methods {
+                let Stmt::Function {
+                    name: method,
+                    params,
+                    body,
+                } = method
+                else {
+                    unreachable!()
+                };
+
+                let signature = match kind {
+                    SymbolKind::Getter => format!("{}.{}", name.lexeme, method.lexeme),
+                    SymbolKind::ClassMethod => {
+                        format!(
+                            "class {}.{}{}",
+                            name.lexeme,
+                            method.lexeme,
+                            parameters(params)
+                        )
+                    }
+                    _ => format!(
+                        "fun {}.{}{}",
+                        name.lexeme,
+                        method.lexeme,
+                        parameters(params)
+                    ),
+                };
+                let symbol = self.add_symbol(method, kind, signature, Some(class));
+                self.methods
+                    .entry(method.lexeme.clone())
+                    .or_default()
+                    .push(symbol);
+
+                self.function(params, body);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn visit_continue_stmt(&mut self, _keyword: &Token) -> Result<(), LoxResult> {
+        Ok(())
+    }
+
+    fn visit_expression_stmt(&mut self, expression: &Expr) -> Result<(), LoxResult> {
+        self.expression(expression);
+        Ok(())
+    }
+
+    fn visit_function_stmt(
+        &mut self,
+        name: &Token,
+        params: &[Token],
+        body: &[Stmt],
+    ) -> Result<(), LoxResult> {
+        // The function is declared before its body, so that it can call itself
+        let signature = format!("fun {}{}", name.lexeme, parameters(params));
+        self.declare(name, SymbolKind::Function, signature);
+        self.function(params, body);
+        Ok(())
+    }
+
+    fn visit_if_stmt(
+        &mut self,
+        condition: &Expr,
+        then_branch: &Stmt,
+        else_branch: &Option<Stmt>,
+    ) -> Result<(), LoxResult> {
+        self.expression(condition);
+        then_branch.accept(self)?;
+        if let Some(else_branch) = else_branch {
+            else_branch.accept(self)?;
+        }
+        Ok(())
+    }
+
+    fn visit_import_stmt(
+        &mut self,
+        _keyword: &Token,
+        name: &Token,
+        path: &Token,
+    ) -> Result<(), LoxResult> {
+        let path = path
+            .literal
+            .as_ref()
+            .map(Object::to_string)
+            .unwrap_or_default();
+        let signature = format!("import {} from {path}", name.lexeme);
+        self.declare(name, SymbolKind::Module, signature);
+        Ok(())
+    }
+
+    fn visit_print_stmt(&mut self, _keyword: &Token, expression: &Expr) -> Result<(), LoxResult> {
+        self.expression(expression);
+        Ok(())
+    }
+
+    fn visit_return_stmt(
+        &mut self,
+        _keyword: &Token,
+        value: &Option<Expr>,
+    ) -> Result<(), LoxResult> {
+        if let Some(value) = value {
+            self.expression(value);
+        }
+        Ok(())
+    }
+
+    fn visit_throw_stmt(&mut self, _keyword: &Token, value: &Expr) -> Result<(), LoxResult> {
+        self.expression(value);
+        Ok(())
+    }
+
+    fn visit_try_stmt(
+        &mut self,
+        _keyword: &Token,
+        body: &[Stmt],
+        catch_name: &Option<Token>,
+        catch_body: &[Stmt],
+        finally_body: &[Stmt],
+    ) -> Result<(), LoxResult> {
+        self.visit_block_stmt(body)?;
+        if let Some(name) = catch_name {
+            self.scopes.push(HashMap::new());
+            self.declare(
+                name,
+                SymbolKind::Variable,
+                format!("catch ({})", name.lexeme),
+            );
+            self.statements(catch_body);
+            self.scopes.pop();
+        }
+        self.visit_block_stmt(finally_body)
+    }
+
+    fn visit_var_stmt(
+        &mut self,
+        name: &Token,
+        initializer: &Option<Expr>,
+    ) -> Result<(), LoxResult> {
+        // The variable is declared after its initializer, which can't use it
+        if let Some(initializer) = initializer {
+            self.expression(initializer);
+        }
+        self.declare(name, SymbolKind::Variable, format!("var {}", name.lexeme));
+        Ok(())
+    }
+
+    fn visit_while_stmt(
+        &mut self,
+        condition: &Expr,
+        body: &Stmt,
+        increment: &Option<Expr>,
+    ) -> Result<(), LoxResult> {
+        self.expression(condition);
+        body.accept(self)?;
+        if let Some(increment) = increment {
+            self.expression(increment);
+        }
+        Ok(())
+    }
+}
+
+impl ExprVisitor<()> for Indexer {
+    fn visit_assign_expr(
+        &mut self,
+        _id: &usize,
+        name: &Token,
+        value: &Expr,
+    ) -> Result<(), LoxResult> {
+        self.expression(value);
+        self.use_variable(name);
+        Ok(())
+    }
+
+    fn visit_binary_expr(
+        &mut self,
+        left: &Expr,
+        _operator: &Token,
+        right: &Expr,
+    ) -> Result<(), LoxResult> {
+        self.expression(left);
+        self.expression(right);
+        Ok(())
+    }
+
+    fn visit_call_expr(
+        &mut self,
+        callee: &Expr,
+        _paren: &Token,
+        arguments: &[Expr],
+    ) -> Result<(), LoxResult> {
+        self.expression(callee);
+        arguments
+            .iter()
+            .for_each(|argument| self.expression(argument));
+        Ok(())
+    }
+
+    fn visit_get_expr(&mut self, object: &Expr, name: &Token) -> Result<(), LoxResult> {
+        self.expression(object);
+        self.use_property(name);
+        Ok(())
+    }
+
+    fn visit_lambda_expr(
+        &mut self,
+        _keyword: &Token,
+        params: &[Token],
+        body: &[Stmt],
+    ) -> Result<(), LoxResult> {
+        self.function(params, body);
+        Ok(())
+    }
+
+    fn visit_index_expr(
+        &mut self,
+        object: &Expr,
+        _bracket: &Token,
+        index: &Expr,
+    ) -> Result<(), LoxResult> {
+        self.expression(object);
+        self.expression(index);
+        Ok(())
+    }
+
+    fn visit_list_expr(&mut self, _bracket: &Token, elements: &[Expr]) -> Result<(), LoxResult> {
+        elements.iter().for_each(|element| self.expression(element));
+        Ok(())
+    }
+
+    fn visit_logical_expr(
+        &mut self,
+        left: &Expr,
+        _operator: &Token,
+        right: &Expr,
+    ) -> Result<(), LoxResult> {
+        self.expression(left);
+        self.expression(right);
+        Ok(())
+    }
+
+    fn visit_map_expr(
+        &mut self,
+        _brace: &Token,
+        keys: &[Expr],
+        values: &[Expr],
+    ) -> Result<(), LoxResult> {
+        keys.iter()
+            .chain(values)
+            .for_each(|expression| self.expression(expression));
+        Ok(())
+    }
+
+    fn visit_set_expr(
+        &mut self,
+        object: &Expr,
+        name: &Token,
+        value: &Expr,
+    ) -> Result<(), LoxResult> {
+        self.expression(value);
+        self.expression(object);
+        self.use_property(name);
+        Ok(())
+    }
+
+    fn visit_setindex_expr(
+        &mut self,
+        object: &Expr,
+        _bracket: &Token,
+        index: &Expr,
+        value: &Expr,
+    ) -> Result<(), LoxResult> {
+        self.expression(value);
+        self.expression(object);
+        self.expression(index);
+        Ok(())
+    }
+
+    fn visit_super_expr(
+        &mut self,
+        _id: &usize,
+        _keyword: &Token,
+        method: &Token,
+    ) -> Result<(), LoxResult> {
+        self.use_property(method);
+        Ok(())
+    }
+
+    fn visit_this_expr(&mut self, _id: &usize, _keyword: &Token) -> Result<(), LoxResult> {
+        Ok(())
+    }
+
+    fn visit_unary_expr(&mut self, _operator: &Token, right: &Expr) -> Result<(), LoxResult> {
+        self.expression(right);
+        Ok(())
+    }
+
+    fn visit_grouping_expr(&mut self, expression: &Expr) -> Result<(), LoxResult> {
+        self.expression(expression);
+        Ok(())
+    }
+
+    fn visit_literal_expr(
+        &mut self,
+        _token: &Token,
+        _value: &Option<Object>,
+    ) -> Result<(), LoxResult> {
+        Ok(())
+    }
+
+    fn visit_variable_expr(&mut self, _id: &usize, name: &Token) -> Result<(), LoxResult> {
+        self.use_variable(name);
+        Ok(())
+    }
+}
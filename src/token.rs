@@ -4,11 +4,52 @@ use std::hash::Hash;
 use crate::object::Object;
 use crate::token_type::*;
 
+/**
+ * Identifies the source code a location is in. `SourceId::MAIN` is the code a session was given
+ * to run, the other ones are registered in its `SourceMap` (e.g. the imported modules).
+ */
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SourceId(pub u32);
+
+impl SourceId {
+    pub const MAIN: SourceId = SourceId(0);
+}
+
+/**
+ * A location in the source code: the source it is in, the line and the range of characters
+ * `[start, end)` it covers, counted from the beginning of the source.
+ */
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub source: SourceId,
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn location(&self) -> String {
+        format!("Line {} [{}:{}]", self.line, self.start, self.end)
+    }
+}
+
+/**
+ * A `//` comment, kept by the scanner apart from the tokens (e.g. for the formatter).
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comment {
+    /// The text of the comment, starting with `//`.
+    pub text: String,
+    pub span: Span,
+}
+
 #[derive(Debug, Clone)]
 pub struct Token {
     pub ttype: TokenType,
     pub lexeme: String,
     pub literal: Option<Object>,
+    /// The source the token was scanned from.
+    pub src_id: SourceId,
     pub src_line: usize,
     pub src_start: usize,
     pub src_end: usize,
@@ -20,6 +61,7 @@ impl Default for Token {
             lexeme: String::new(),
             literal: None,
             ttype: TokenType::Eof,
+            src_id: SourceId::MAIN,
             src_end: 0,
             src_line: 0,
             src_start: 0,
@@ -43,11 +85,17 @@ impl PartialEq for Token {
 impl Eq for Token {}
 
 impl Token {
+    pub fn span(&self) -> Span {
+        Span {
+            source: self.src_id,
+            line: self.src_line,
+            start: self.src_start,
+            end: self.src_end,
+        }
+    }
+
     pub fn location(&self) -> String {
-        format!(
-            "Line {} [{}:{}]",
-            self.src_line, self.src_start, self.src_end
-        )
+        self.span().location()
     }
 
     pub fn eof(src_line: usize, src_at: usize) -> Token {
@@ -55,6 +103,7 @@ impl Token {
             ttype: TokenType::Eof,
             lexeme: "".to_string(),
             literal: None,
+            src_id: SourceId::MAIN,
             src_line,
             src_start: src_at,
             src_end: src_at + 1,
@@ -66,6 +115,7 @@ impl Token {
             ttype: TokenType::LeftParen,
             lexeme: "(".to_string(),
             literal: None,
+            src_id: SourceId::MAIN,
             src_line,
             src_start: src_at,
             src_end: src_at + 1,
@@ -77,6 +127,7 @@ impl Token {
             ttype: TokenType::RightParen,
             lexeme: ")".to_string(),
             literal: None,
+            src_id: SourceId::MAIN,
             src_line,
             src_start: src_at,
             src_end: src_at + 1,
@@ -88,6 +139,7 @@ impl Token {
             ttype: TokenType::LeftBrace,
             lexeme: "{".to_string(),
             literal: None,
+            src_id: SourceId::MAIN,
             src_line,
             src_start: src_at,
             src_end: src_at + 1,
@@ -99,6 +151,43 @@ impl Token {
             ttype: TokenType::RightBrace,
             lexeme: "}".to_string(),
             literal: None,
+            src_id: SourceId::MAIN,
+            src_line,
+            src_start: src_at,
+            src_end: src_at + 1,
+        }
+    }
+
+    pub fn left_bracket(src_line: usize, src_at: usize) -> Token {
+        Token {
+            ttype: TokenType::LeftBracket,
+            lexeme: "[".to_string(),
+            literal: None,
+            src_id: SourceId::MAIN,
+            src_line,
+            src_start: src_at,
+            src_end: src_at + 1,
+        }
+    }
+
+    pub fn right_bracket(src_line: usize, src_at: usize) -> Token {
+        Token {
+            ttype: TokenType::RightBracket,
+            lexeme: "]".to_string(),
+            literal: None,
+            src_id: SourceId::MAIN,
+            src_line,
+            src_start: src_at,
+            src_end: src_at + 1,
+        }
+    }
+
+    pub fn colon(src_line: usize, src_at: usize) -> Token {
+        Token {
+            ttype: TokenType::Colon,
+            lexeme: ":".to_string(),
+            literal: None,
+            src_id: SourceId::MAIN,
             src_line,
             src_start: src_at,
             src_end: src_at + 1,
@@ -110,6 +199,7 @@ impl Token {
             ttype: TokenType::Comma,
             lexeme: ",".to_string(),
             literal: None,
+            src_id: SourceId::MAIN,
             src_line,
             src_start: src_at,
             src_end: src_at + 1,
@@ -121,6 +211,7 @@ impl Token {
             ttype: TokenType::Dot,
             lexeme: ".".to_string(),
             literal: None,
+            src_id: SourceId::MAIN,
             src_line,
             src_start: src_at,
             src_end: src_at + 1,
@@ -132,6 +223,7 @@ impl Token {
             ttype: TokenType::Minus,
             lexeme: "-".to_string(),
             literal: None,
+            src_id: SourceId::MAIN,
             src_line,
             src_start: src_at,
             src_end: src_at + 1,
@@ -143,6 +235,7 @@ impl Token {
             ttype: TokenType::Plus,
             lexeme: "+".to_string(),
             literal: None,
+            src_id: SourceId::MAIN,
             src_line,
             src_start: src_at,
             src_end: src_at + 1,
@@ -154,6 +247,7 @@ impl Token {
             ttype: TokenType::Semicolon,
             lexeme: ";".to_string(),
             literal: None,
+            src_id: SourceId::MAIN,
             src_line,
             src_start: src_at,
             src_end: src_at + 1,
@@ -165,6 +259,7 @@ impl Token {
             ttype: TokenType::Star,
             lexeme: "*".to_string(),
             literal: None,
+            src_id: SourceId::MAIN,
             src_line,
             src_start: src_at,
             src_end: src_at + 1,
@@ -176,6 +271,7 @@ impl Token {
             ttype: TokenType::Bang,
             lexeme: "!".to_string(),
             literal: None,
+            src_id: SourceId::MAIN,
             src_line,
             src_start: src_at,
             src_end: src_at + 1,
@@ -187,6 +283,7 @@ impl Token {
             ttype: TokenType::BangEqual,
             lexeme: "!=".to_string(),
             literal: None,
+            src_id: SourceId::MAIN,
             src_line,
             src_start: src_at,
             src_end: src_at + 2,
@@ -198,6 +295,7 @@ impl Token {
             ttype: TokenType::Equal,
             lexeme: "=".to_string(),
             literal: None,
+            src_id: SourceId::MAIN,
             src_line,
             src_start: src_at,
             src_end: src_at + 1,
@@ -209,6 +307,7 @@ impl Token {
             ttype: TokenType::EqualEqual,
             lexeme: "==".to_string(),
             literal: None,
+            src_id: SourceId::MAIN,
             src_line,
             src_start: src_at,
             src_end: src_at + 2,
@@ -220,6 +319,7 @@ impl Token {
             ttype: TokenType::Less,
             lexeme: "<".to_string(),
             literal: None,
+            src_id: SourceId::MAIN,
             src_line,
             src_start: src_at,
             src_end: src_at + 1,
@@ -231,6 +331,7 @@ impl Token {
             ttype: TokenType::LessEqual,
             lexeme: "<=".to_string(),
             literal: None,
+            src_id: SourceId::MAIN,
             src_line,
             src_start: src_at,
             src_end: src_at + 2,
@@ -242,6 +343,7 @@ impl Token {
             ttype: TokenType::Greater,
             lexeme: ">".to_string(),
             literal: None,
+            src_id: SourceId::MAIN,
             src_line,
             src_start: src_at,
             src_end: src_at + 1,
@@ -253,6 +355,7 @@ impl Token {
             ttype: TokenType::GreaterEqual,
             lexeme: ">=".to_string(),
             literal: None,
+            src_id: SourceId::MAIN,
             src_line,
             src_start: src_at,
             src_end: src_at + 2,
@@ -264,20 +367,22 @@ impl Token {
             ttype: TokenType::Slash,
             lexeme: "/".to_string(),
             literal: None,
+            src_id: SourceId::MAIN,
             src_line,
             src_start: src_at,
             src_end: src_at + 1,
         }
     }
 
-    pub fn string(src_line: usize, src_at: usize, s: &str) -> Token {
+    pub fn string(src_line: usize, src_start: usize, src_end: usize, s: &str) -> Token {
         Token {
             ttype: TokenType::String,
             lexeme: "".to_string(),
             literal: Some(Object::Str(s.to_string())),
+            src_id: SourceId::MAIN,
             src_line,
-            src_start: src_at,
-            src_end: src_at + s.len(),
+            src_start,
+            src_end,
         }
     }
 
@@ -286,6 +391,7 @@ impl Token {
             ttype: TokenType::Number,
             lexeme: "".to_string(),
             literal: Some(Object::Num(n)),
+            src_id: SourceId::MAIN,
             src_line,
             src_start,
             src_end,
@@ -303,6 +409,7 @@ impl Token {
             ttype,
             lexeme: l.to_string(),
             literal: None,
+            src_id: SourceId::MAIN,
             src_line,
             src_start,
             src_end,
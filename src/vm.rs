@@ -0,0 +1,762 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::chunk::OpCode;
+use crate::diagnostic::SourceFile;
+use crate::errors::{EnvironmentErrorType, LoxResult, RuntimeErrorType};
+use crate::heap;
+use crate::interpreter::DEFAULT_MAX_CALL_DEPTH;
+use crate::lox_map::{map_key, LoxMap};
+use crate::lox_native::NativeFunction;
+use crate::native_functions::{native_functions, NativeModule};
+use crate::object::{list_index, Object};
+use crate::output::{OutputSink, StderrSink, StdoutSink};
+use crate::token::Token;
+use crate::vm_object::{BoundMethod, Closure, CompiledFunction, Upvalue, VmClass, VmInstance};
+
+/**
+ * The state of a function call.
+ */
+struct CallFrame {
+    /// The closure being run.
+    closure: Rc<Closure>,
+    /// The offset of the next instruction to run in the closure's chunk.
+    ip: usize,
+    /// The index in the stack of the first slot of the frame: the called function (or `this`),
+    /// followed by its arguments and local variables.
+    slots: usize,
+}
+
+/**
+ * Where to resume the execution when a runtime error occurs in an expression statement.
+ */
+struct Handler {
+    /// The number of call frames when the expression statement started.
+    frames: usize,
+    /// The height of the stack when the expression statement started.
+    stack: usize,
+    /// The offset of the instruction following the expression statement.
+    ip: usize,
+}
+
+/**
+ * The Vm runs the bytecode produced by the `Compiler` on a stack of values.
+ *
+ * It behaves like the tree-walking `Interpreter`: same values, same output and same runtime
+ * errors.
+ *
+ * Note: The compiler rejects imports and exceptions, and the vm has neither execution budget,
+ * debugger nor traceback. Those are only supported by the `Interpreter`.
+ */
+pub struct Vm {
+    /// The values being computed and the local variables of the running functions.
+    stack: Vec<Object>,
+    /// The functions being run, the innermost one last.
+    frames: Vec<CallFrame>,
+    /// The global variables.
+    globals: HashMap<String, Object>,
+    /// The maximum number of nested calls, past which calls raise a stack overflow error.
+    max_call_depth: usize,
+    /// The variables captured by closures that are still on the stack, ordered by stack index.
+    open_upvalues: Vec<Rc<RefCell<Upvalue>>>,
+    /// The expression statements being run, the innermost one last.
+    handlers: Vec<Handler>,
+    /// Where the outcome of `print` statements is written.
+    output: Box<dyn OutputSink>,
+    /// Where the runtime diagnostics are written.
+    diagnostics: Box<dyn OutputSink>,
+    /// The name and source code of the program, which the runtime diagnostics are rendered
+    /// against once known.
+    source_file: Option<(String, String)>,
+}
+
+impl Default for Vm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Vm {
+    /**
+     * Creates a vm with every native function defined.
+     */
+    pub fn new() -> Self {
+        Vm::with_natives(&NativeModule::ALL)
+    }
+
+    /**
+     * Creates a vm only defining the native functions of the given modules.
+     *
+     * Note: This is where `Object::Native` functions are defined in `self.globals`.
+     */
+    pub fn with_natives(natives: &[NativeModule]) -> Self {
+        let globals = native_functions(natives)
+            .into_iter()
+            .map(|(name, function)| {
+                (
+                    name.to_string(),
+                    Object::Native(Rc::new(NativeFunction { function })),
+                )
+            })
+            .collect();
+
+        Vm {
+            stack: Vec::new(),
+            frames: Vec::new(),
+            globals,
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            open_upvalues: Vec::new(),
+            handlers: Vec::new(),
+            output: Box::new(StdoutSink),
+            diagnostics: Box::new(StderrSink),
+            source_file: None,
+        }
+    }
+
+    /**
+     * Replaces the sink `print` statements write to (stdout by default).
+     */
+    pub fn set_output(&mut self, output: Box<dyn OutputSink>) {
+        self.output = output;
+    }
+
+    /**
+     * Replaces the sink runtime diagnostics are written to (stderr by default).
+     */
+    pub fn set_diagnostics(&mut self, diagnostics: Box<dyn OutputSink>) {
+        self.diagnostics = diagnostics;
+    }
+
+    /**
+     * Renders the runtime diagnostics against the given program, the way `SourceFile::render`
+     * does. They are written on a single line otherwise.
+     */
+    pub fn set_source_file(&mut self, name: &str, source: &str) {
+        self.source_file = Some((name.to_string(), source.to_string()));
+    }
+
+    /**
+     * Sets the maximum number of nested calls, `DEFAULT_MAX_CALL_DEPTH` by default as on the
+     * tree-walking backend.
+     */
+    pub fn set_max_call_depth(&mut self, max_call_depth: usize) {
+        self.max_call_depth = max_call_depth;
+    }
+
+    /**
+     * Defines (or redefines) the global variable `name`.
+     */
+    pub fn define_global(&mut self, name: &str, value: Object) {
+        self.globals.insert(name.to_string(), value);
+    }
+
+    /**
+     * Returns the value of the global variable `name`, or `None` if it isn't defined.
+     */
+    pub fn get_global(&self, name: &str) -> Option<Object> {
+        self.globals.get(name).cloned()
+    }
+
+    /**
+     * Returns the global variables along with their names, sorted by name.
+     */
+    pub fn globals(&self) -> Vec<(String, Object)> {
+        let mut globals = self
+            .globals
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect::<Vec<(String, Object)>>();
+        globals.sort_by(|(a, _), (b, _)| a.cmp(b));
+        globals
+    }
+
+    /**
+     * Runs a program compiled by the `Compiler` and returns the value it returned.
+     */
+    pub fn interpret(&mut self, function: CompiledFunction) -> Result<Object, LoxResult> {
+        let closure = Rc::new(Closure {
+            function: Rc::new(function),
+            upvalues: Vec::new(),
+        });
+        self.stack.push(Object::Closure(Rc::clone(&closure)));
+        self.frames.push(CallFrame {
+            closure,
+            ip: 0,
+            slots: 0,
+        });
+
+        loop {
+            match self.run() {
+                Ok(value) => return Ok(value),
+                // Report the error of an expression statement and resume after it
+                Err(e) if !self.handlers.is_empty() => {
+                    let handler = self.handlers.pop().unwrap();
+                    self.frames.truncate(handler.frames);
+                    self.close_upvalues(handler.stack);
+                    self.stack.truncate(handler.stack);
+                    self.frames.last_mut().unwrap().ip = handler.ip;
+                    let line = match &self.source_file {
+                        Some((name, source)) => {
+                            format!("{}\n", SourceFile::new(name, source).render(&e))
+                        }
+                        None => e.to_string(),
+                    };
+                    self.diagnostics.write_line(&line);
+                }
+                Err(e) => {
+                    self.reset();
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    /**
+     * Discards the state of the program that was running.
+     */
+    fn reset(&mut self) {
+        // Closures that outlive the program keep the values they captured
+        self.close_upvalues(0);
+        self.stack.clear();
+        self.frames.clear();
+        self.handlers.clear();
+    }
+
+    fn frame(&self) -> &CallFrame {
+        self.frames.last().unwrap()
+    }
+
+    fn frame_mut(&mut self) -> &mut CallFrame {
+        self.frames.last_mut().unwrap()
+    }
+
+    /**
+     * Returns the token the instruction being run was compiled from.
+     */
+    fn token(&self) -> Token {
+        let frame = self.frame();
+        frame.closure.function.chunk.token(frame.ip - 1).clone()
+    }
+
+    fn runtime_error(&self, error_type: RuntimeErrorType) -> LoxResult {
+        LoxResult::Runtime {
+            token: self.token(),
+            error_type,
+            trace: Vec::new(),
+        }
+    }
+
+    /**
+     * Returns the name stored in the constant at `index` of the running chunk.
+     */
+    fn constant_name(&self, index: u16) -> &str {
+        constant_name(&self.frame().closure, index)
+    }
+
+    fn pop(&mut self) -> Object {
+        self.stack.pop().unwrap()
+    }
+
+    fn peek(&self, distance: usize) -> &Object {
+        &self.stack[self.stack.len() - 1 - distance]
+    }
+
+    fn is_truthy(obj: &Object) -> bool {
+        !(*obj == Object::Nil || *obj == Object::False)
+    }
+
+    /**
+     * Pops two numbers and pushes the result of `op` on them.
+     *
+     * Note: Returns a `RuntimeErrorType::ExpectedNumberOperands` if either isn't a number.
+     */
+    fn binary_number_op<T: Into<Object>>(
+        &mut self,
+        op: fn(f64, f64) -> T,
+    ) -> Result<(), LoxResult> {
+        let right = self.pop();
+        let left = self.pop();
+        match (left, right) {
+            (Object::Num(left), Object::Num(right)) => {
+                self.stack.push(op(left, right).into());
+                Ok(())
+            }
+            _ => Err(self.runtime_error(RuntimeErrorType::ExpectedNumberOperands)),
+        }
+    }
+
+    /**
+     * Runs the instructions of the current call frame until the top level function returns.
+     */
+    fn run(&mut self) -> Result<Object, LoxResult> {
+        loop {
+            let frame = self.frame_mut();
+            let op = frame.closure.function.chunk.code[frame.ip];
+            frame.ip += 1;
+
+            match op {
+                OpCode::Constant(index) => {
+                    let value =
+                        self.frame().closure.function.chunk.constants[index as usize].clone();
+                    self.stack.push(value);
+                }
+                OpCode::Nil => self.stack.push(Object::Nil),
+                OpCode::True => self.stack.push(Object::True),
+                OpCode::False => self.stack.push(Object::False),
+                OpCode::Pop => {
+                    self.pop();
+                }
+
+                OpCode::GetLocal(slot) => {
+                    let value = self.stack[self.frame().slots + slot as usize].clone();
+                    self.stack.push(value);
+                }
+                OpCode::SetLocal(slot) => {
+                    let slot = self.frame().slots + slot as usize;
+                    self.stack[slot] = self.peek(0).clone();
+                }
+                OpCode::GetGlobal(index) => {
+                    let name = self.constant_name(index);
+                    match self.globals.get(name).cloned() {
+                        Some(value) => self.stack.push(value),
+                        None => {
+                            return Err(LoxResult::Environment {
+                                token: self.token(),
+                                error_type: EnvironmentErrorType::UnknownVariable,
+                                msg: format!("No such variable '{}'.", name),
+                            })
+                        }
+                    }
+                }
+                OpCode::DefineGlobal(index) => {
+                    let name = self.constant_name(index).to_string();
+                    let value = self.pop();
+                    self.globals.insert(name, value);
+                }
+                OpCode::SetGlobal(index) => {
+                    let closure = Rc::clone(&self.frame().closure);
+                    let name = constant_name(&closure, index);
+                    let value = self.peek(0).clone();
+                    match self.globals.get_mut(name) {
+                        Some(global) => *global = value,
+                        None => {
+                            return Err(LoxResult::Environment {
+                                token: self.token(),
+                                error_type: EnvironmentErrorType::UnknownVariable,
+                                msg: format!("Cannot assign value to unknown variable '{}'.", name),
+                            })
+                        }
+                    }
+                }
+                OpCode::GetUpvalue(index) => {
+                    let upvalue = Rc::clone(&self.frame().closure.upvalues[index as usize]);
+                    let value = match &*upvalue.borrow() {
+                        Upvalue::Open(slot) => self.stack[*slot].clone(),
+                        Upvalue::Closed(value) => value.clone(),
+                    };
+                    self.stack.push(value);
+                }
+                OpCode::SetUpvalue(index) => {
+                    let upvalue = Rc::clone(&self.frame().closure.upvalues[index as usize]);
+                    let value = self.peek(0).clone();
+                    match &mut *upvalue.borrow_mut() {
+                        Upvalue::Open(slot) => self.stack[*slot] = value,
+                        Upvalue::Closed(closed) => *closed = value,
+                    };
+                }
+
+                OpCode::GetProperty(index) => self.get_property(index)?,
+                OpCode::SetProperty(index) => {
+                    let value = self.pop();
+                    let object = self.pop();
+                    if let Object::VmInstance(instance) = object {
+                        let name = self.constant_name(index).to_string();
+                        instance.fields.borrow_mut().insert(name, value.clone());
+                        self.stack.push(value);
+                    } else {
+                        return Err(self.runtime_error(RuntimeErrorType::InvalidObjectProperty));
+                    }
+                }
+                OpCode::GetSuper(index) => {
+                    let superclass = self.pop();
+                    if let Object::VmClass(superclass) = superclass {
+                        // In a class method, `this` is the class whose static methods are found
+                        let name = self.constant_name(index);
+                        let method = match self.stack.last() {
+                            Some(Object::VmClass(_)) => superclass.find_class_method(name),
+                            _ => superclass.find_method(name),
+                        };
+                        match method {
+                            // The receiver stays on the stack as the `this` of the getter
+                            Some(method) if method.function.is_getter => {
+                                self.call_closure(method, 0)?
+                            }
+                            Some(method) => {
+                                let receiver = self.pop();
+                                self.stack.push(Object::BoundMethod(Rc::new(BoundMethod {
+                                    receiver,
+                                    method,
+                                })))
+                            }
+                            None => {
+                                return Err(self.runtime_error(RuntimeErrorType::UndefinedProperty))
+                            }
+                        }
+                    } else {
+                        unreachable!()
+                    }
+                }
+                OpCode::GetIndex => {
+                    let index = self.pop();
+                    let object = self.pop();
+                    let value = self.get_index(object, index)?;
+                    self.stack.push(value);
+                }
+                OpCode::SetIndex => {
+                    let value = self.pop();
+                    let index = self.pop();
+                    let object = self.pop();
+                    self.set_index(object, index, value.clone())?;
+                    self.stack.push(value);
+                }
+
+                OpCode::Equal => {
+                    let right = self.pop();
+                    let left = self.pop();
+                    self.stack.push(Object::from(left == right));
+                }
+                OpCode::NotEqual => {
+                    let right = self.pop();
+                    let left = self.pop();
+                    self.stack.push(Object::from(left != right));
+                }
+                OpCode::Greater => self.binary_number_op(|a, b| a > b)?,
+                OpCode::GreaterEqual => self.binary_number_op(|a, b| a >= b)?,
+                OpCode::Less => self.binary_number_op(|a, b| a < b)?,
+                OpCode::LessEqual => self.binary_number_op(|a, b| a <= b)?,
+                OpCode::Subtract => self.binary_number_op(|a, b| a - b)?,
+                OpCode::Multiply => self.binary_number_op(|a, b| a * b)?,
+                OpCode::Divide => self.binary_number_op(|a, b| a / b)?,
+                OpCode::Add => {
+                    let right = self.pop();
+                    let left = self.pop();
+                    match (left, right) {
+                        (Object::Num(left), Object::Num(right)) => {
+                            self.stack.push(Object::from(left + right))
+                        }
+                        (Object::Str(mut left), Object::Str(right)) => {
+                            left.push_str(&right);
+                            self.stack.push(Object::from(left));
+                        }
+                        _ => {
+                            return Err(
+                                self.runtime_error(RuntimeErrorType::ExpectedAddableOperands)
+                            )
+                        }
+                    }
+                }
+                OpCode::Not => {
+                    let value = self.pop();
+                    self.stack.push(Object::from(!Self::is_truthy(&value)));
+                }
+                OpCode::Negate => match self.pop() {
+                    Object::Num(x) => self.stack.push(Object::Num(-x)),
+                    _ => return Err(self.runtime_error(RuntimeErrorType::ExpectedNumberOperand)),
+                },
+
+                OpCode::Print => {
+                    let value = self.pop();
+                    self.output.write_line(&value.to_string());
+                }
+                OpCode::Jump(offset) => self.frame_mut().ip += offset as usize,
+                OpCode::JumpIfFalse(offset) => {
+                    if !Self::is_truthy(self.peek(0)) {
+                        self.frame_mut().ip += offset as usize;
+                    }
+                }
+                // Loops and calls are where most objects get allocated, and nothing is borrowed
+                // in between two instructions
+                OpCode::Loop(offset) => {
+                    heap::maybe_collect();
+                    self.frame_mut().ip -= offset as usize
+                }
+
+                OpCode::Call(argc) => {
+                    heap::maybe_collect();
+                    self.call_value(argc as usize)?
+                }
+                OpCode::Closure(index) => {
+                    let function =
+                        Rc::clone(&self.frame().closure.function.chunk.functions[index as usize]);
+                    let mut upvalues = Vec::with_capacity(function.upvalues.len());
+                    for upvalue in &function.upvalues {
+                        let index = upvalue.index as usize;
+                        upvalues.push(match upvalue.is_local {
+                            true => self.capture_upvalue(self.frame().slots + index),
+                            false => Rc::clone(&self.frame().closure.upvalues[index]),
+                        });
+                    }
+                    self.stack
+                        .push(Object::Closure(Rc::new(Closure { function, upvalues })));
+                }
+                OpCode::CloseUpvalue => {
+                    self.close_upvalues(self.stack.len() - 1);
+                    self.pop();
+                }
+                OpCode::Return => {
+                    let result = self.pop();
+                    let frame = self.frames.pop().unwrap();
+                    self.close_upvalues(frame.slots);
+                    self.stack.truncate(frame.slots);
+
+                    // Leaving the top level function ends the program
+                    if self.frames.is_empty() {
+                        return Ok(result);
+                    }
+                    self.stack.push(result);
+                }
+
+                OpCode::List(count) => {
+                    let elements = self.stack.split_off(self.stack.len() - count as usize);
+                    self.stack.push(Object::from(elements));
+                }
+                OpCode::Map => self.stack.push(Object::from(LoxMap::new())),
+                OpCode::MapInsert => {
+                    let value = self.pop();
+                    let key = self.pop();
+                    if let Object::Map(map) = self.peek(0) {
+                        let hashed_key = map_key(&key, &self.token())?;
+                        map.borrow_mut().insert(hashed_key, key, value);
+                    }
+                }
+                OpCode::Class(index) => {
+                    let class = Rc::new(VmClass::new(self.constant_name(index).to_string(), None));
+                    heap::track(&class);
+                    self.stack.push(Object::VmClass(class));
+                }
+                OpCode::Subclass(index) => {
+                    if let Object::VmClass(superclass) = self.pop() {
+                        let class = Rc::new(VmClass::new(
+                            self.constant_name(index).to_string(),
+                            Some(superclass),
+                        ));
+                        heap::track(&class);
+                        self.stack.push(Object::VmClass(class));
+                    } else {
+                        return Err(self.runtime_error(RuntimeErrorType::SuperclassNotClass));
+                    }
+                }
+                OpCode::Method(index) | OpCode::StaticMethod(index) => {
+                    let name = self.constant_name(index).to_string();
+                    if let (Object::Closure(method), Object::VmClass(class)) =
+                        (self.pop(), self.peek(0))
+                    {
+                        let methods = match op {
+                            OpCode::Method(_) => &class.methods,
+                            _ => &class.class_methods,
+                        };
+                        methods.borrow_mut().insert(name, method);
+                    }
+                }
+
+                OpCode::PushHandler(offset) => {
+                    let handler = Handler {
+                        frames: self.frames.len(),
+                        stack: self.stack.len(),
+                        ip: self.frame().ip + offset as usize,
+                    };
+                    self.handlers.push(handler);
+                }
+                OpCode::PopHandler => {
+                    self.handlers.pop();
+                }
+            }
+        }
+    }
+
+    /**
+     * Replaces the object on top of the stack with its member named after the constant at
+     * `index`: a field or a method of an instance, or a static method of a class.
+     *
+     * Note: A getter is called right away, its value replaces the instance once it returns.
+     */
+    fn get_property(&mut self, index: u16) -> Result<(), LoxResult> {
+        let closure = Rc::clone(&self.frame().closure);
+        let name = constant_name(&closure, index);
+        let object = self.peek(0).clone();
+
+        let method = match &object {
+            Object::VmInstance(instance) => {
+                // Look for a field with that name
+                if let Some(field) = instance.fields.borrow().get(name) {
+                    *self.stack.last_mut().unwrap() = field.clone();
+                    return Ok(());
+                }
+                instance.class.find_method(name)
+            }
+            Object::VmClass(class) => class.find_class_method(name),
+            _ => return Err(self.runtime_error(RuntimeErrorType::InvalidObjectProperty)),
+        };
+
+        match method {
+            Some(method) if method.function.is_getter => self.call_closure(method, 0),
+            Some(method) => {
+                let receiver = self.pop();
+                self.stack.push(Object::BoundMethod(Rc::new(BoundMethod {
+                    receiver,
+                    method,
+                })));
+                Ok(())
+            }
+            None => Err(self.runtime_error(RuntimeErrorType::UndefinedProperty)),
+        }
+    }
+
+    fn get_index(&self, object: Object, index: Object) -> Result<Object, LoxResult> {
+        match object {
+            Object::List(elements) => {
+                let elements = elements.borrow();
+                let i = list_index(&index, elements.len(), &self.token())?;
+                Ok(elements[i].clone())
+            }
+            Object::Map(map) => {
+                let key = map_key(&index, &self.token())?;
+                map.borrow()
+                    .get(&key)
+                    .ok_or_else(|| self.runtime_error(RuntimeErrorType::UndefinedKey))
+            }
+            _ => Err(self.runtime_error(RuntimeErrorType::InvalidIndexedObject)),
+        }
+    }
+
+    fn set_index(&self, object: Object, index: Object, value: Object) -> Result<(), LoxResult> {
+        match object {
+            Object::List(elements) => {
+                let mut elements = elements.borrow_mut();
+                let i = list_index(&index, elements.len(), &self.token())?;
+                elements[i] = value;
+                Ok(())
+            }
+            Object::Map(map) => {
+                let key = map_key(&index, &self.token())?;
+                map.borrow_mut().insert(key, index, value);
+                Ok(())
+            }
+            _ => Err(self.runtime_error(RuntimeErrorType::InvalidIndexedObject)),
+        }
+    }
+
+    /**
+     * Calls the value below the `argc` arguments on top of the stack.
+     */
+    fn call_value(&mut self, argc: usize) -> Result<(), LoxResult> {
+        let callee_slot = self.stack.len() - argc - 1;
+
+        match self.stack[callee_slot].clone() {
+            Object::Closure(closure) => self.call_closure(closure, argc),
+            Object::BoundMethod(bound) => {
+                // The receiver becomes the `this` of the method
+                self.stack[callee_slot] = bound.receiver.clone();
+                self.call_closure(Rc::clone(&bound.method), argc)
+            }
+            Object::VmClass(class) => {
+                let init = class.find_method("init");
+                // Without `init()`, a class takes no argument
+                if init.is_none() && argc != 0 {
+                    return Err(self.runtime_error(RuntimeErrorType::InvalidArgsCount));
+                }
+
+                // Create a new instance and run `init()` on it
+                let instance = Rc::new(VmInstance::new(&class));
+                heap::track(&instance);
+                self.stack[callee_slot] = Object::VmInstance(instance);
+                match init {
+                    Some(init) => self.call_closure(init, argc),
+                    None => Ok(()),
+                }
+            }
+            Object::Native(native) => {
+                if argc != native.function.arity() {
+                    return Err(self.runtime_error(RuntimeErrorType::InvalidArgsCount));
+                }
+
+                let arguments = self.stack.split_off(callee_slot + 1);
+                self.pop();
+                let result = native.function.call(arguments, &self.token())?;
+                self.stack.push(result);
+                Ok(())
+            }
+            _ => Err(self.runtime_error(RuntimeErrorType::InvalidCallObjectType)),
+        }
+    }
+
+    /**
+     * Starts running `closure` with the `argc` arguments on top of the stack.
+     */
+    fn call_closure(&mut self, closure: Rc<Closure>, argc: usize) -> Result<(), LoxResult> {
+        if argc != closure.function.arity() {
+            return Err(self.runtime_error(RuntimeErrorType::InvalidArgsCount));
+        }
+        // The frame of the top-level code isn't a call
+        if self.frames.len() > self.max_call_depth {
+            return Err(self.runtime_error(RuntimeErrorType::StackOverflow));
+        }
+
+        self.frames.push(CallFrame {
+            closure,
+            ip: 0,
+            slots: self.stack.len() - argc - 1,
+        });
+
+        Ok(())
+    }
+
+    /**
+     * Returns the upvalue capturing the variable in the given stack slot, creating it if the
+     * variable wasn't captured yet.
+     */
+    fn capture_upvalue(&mut self, slot: usize) -> Rc<RefCell<Upvalue>> {
+        // Find where the upvalue is, or should be, in the upvalues ordered by slot
+        let position = self.open_upvalues.partition_point(
+            |upvalue| matches!(*upvalue.borrow(), Upvalue::Open(open_slot) if open_slot < slot),
+        );
+
+        if let Some(upvalue) = self.open_upvalues.get(position) {
+            if matches!(*upvalue.borrow(), Upvalue::Open(open_slot) if open_slot == slot) {
+                return Rc::clone(upvalue);
+            }
+        }
+
+        let upvalue = Rc::new(RefCell::new(Upvalue::Open(slot)));
+        heap::track(&upvalue);
+        self.open_upvalues.insert(position, Rc::clone(&upvalue));
+        upvalue
+    }
+
+    /**
+     * Moves the variables at or above the given stack slot into the upvalues capturing them.
+     */
+    fn close_upvalues(&mut self, from: usize) {
+        let position = self.open_upvalues.partition_point(
+            |upvalue| matches!(*upvalue.borrow(), Upvalue::Open(open_slot) if open_slot < from),
+        );
+
+        for upvalue in self.open_upvalues.drain(position..) {
+            let mut upvalue = upvalue.borrow_mut();
+            if let Upvalue::Open(slot) = *upvalue {
+                *upvalue = Upvalue::Closed(self.stack[slot].clone());
+            }
+        }
+    }
+}
+
+/**
+ * Returns the name stored in the constant at `index` of the closure's chunk.
+ */
+fn constant_name(closure: &Closure, index: u16) -> &str {
+    match &closure.function.chunk.constants[index as usize] {
+        Object::Str(name) => name,
+        _ => unreachable!(),
+    }
+}
@@ -0,0 +1,232 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::chunk::Chunk;
+use crate::object::Object;
+
+/**
+ * A function compiled to bytecode by the `Compiler`.
+ */
+#[derive(Debug, Default)]
+pub struct CompiledFunction {
+    /// The name of the function, `None` for an anonymous function or the top level script.
+    pub name: Option<String>,
+    /// The names of the parameters of the function.
+    pub params: Vec<String>,
+    /// The bytecode of the function's body.
+    pub chunk: Chunk,
+    /// Where to find each variable captured by the function when creating a closure of it.
+    pub upvalues: Vec<UpvalueRef>,
+    /// Tells if this is a class's `init()` function
+    pub is_init_function: bool,
+    /// Tells if this is a getter method, called as soon as it is accessed on an instance.
+    pub is_getter: bool,
+}
+
+impl CompiledFunction {
+    pub fn arity(&self) -> usize {
+        self.params.len()
+    }
+}
+
+impl fmt::Display for CompiledFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "<fn {}({})>",
+            self.name.as_deref().unwrap_or("anonymous"),
+            self.params.join(", ")
+        )
+    }
+}
+
+/**
+ * Tells where a closure captures a variable from when it is created: a local variable of the
+ * enclosing function (`is_local`) or a variable already captured by the enclosing function.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UpvalueRef {
+    pub is_local: bool,
+    pub index: u16,
+}
+
+/**
+ * A variable captured by a closure. It stays on the stack while the variable is in scope and is
+ * moved into the upvalue once the variable goes out of scope.
+ */
+#[derive(Debug)]
+pub enum Upvalue {
+    /// The variable is still on the stack, at the given index.
+    Open(usize),
+    /// The variable went out of scope, this is its value.
+    Closed(Object),
+}
+
+/**
+ * A compiled function along with the variables it captured.
+ */
+pub struct Closure {
+    pub function: Rc<CompiledFunction>,
+    pub upvalues: Vec<Rc<RefCell<Upvalue>>>,
+}
+
+impl fmt::Debug for Closure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{self}")
+    }
+}
+
+impl fmt::Display for Closure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.function)
+    }
+}
+
+/**
+ * A method bound to the object it was accessed on, which becomes its `this`.
+ */
+pub struct BoundMethod {
+    pub receiver: Object,
+    pub method: Rc<Closure>,
+}
+
+impl fmt::Debug for BoundMethod {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{self}")
+    }
+}
+
+impl fmt::Display for BoundMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.method)
+    }
+}
+
+/**
+ * A class created by the `Vm`. Its methods are added one by one after its creation.
+ */
+pub struct VmClass {
+    /// The name of the class.
+    pub name: String,
+    /// Optional superclass
+    pub superclass: Option<Rc<VmClass>>,
+    /// A map of defined methods (including getters) for this class.
+    pub methods: RefCell<HashMap<String, Rc<Closure>>>,
+    /// A map of static methods, called on the class itself.
+    pub class_methods: RefCell<HashMap<String, Rc<Closure>>>,
+}
+
+impl VmClass {
+    pub fn new(name: String, superclass: Option<Rc<VmClass>>) -> Self {
+        VmClass {
+            name,
+            superclass,
+            methods: RefCell::new(HashMap::new()),
+            class_methods: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /**
+     * Function used in order to retrieve a defined method of the class or of its superclasses.
+     *
+     * Note: Returns `None` if not found.
+     */
+    pub fn find_method(&self, name: &str) -> Option<Rc<Closure>> {
+        if let Some(method) = self.methods.borrow().get(name) {
+            return Some(Rc::clone(method));
+        }
+
+        self.superclass.as_ref()?.find_method(name)
+    }
+
+    /**
+     * Function used in order to retrieve a static method of the class or of its superclasses.
+     *
+     * Note: Returns `None` if not found.
+     */
+    pub fn find_class_method(&self, name: &str) -> Option<Rc<Closure>> {
+        if let Some(method) = self.class_methods.borrow().get(name) {
+            return Some(Rc::clone(method));
+        }
+
+        self.superclass.as_ref()?.find_class_method(name)
+    }
+}
+
+impl fmt::Debug for VmClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{self}")
+    }
+}
+
+impl fmt::Display for VmClass {
+    /**
+     * Note: Displayed the same way as a `LoxClass`.
+     */
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let methods = self.methods.borrow();
+        let class_methods = self.class_methods.borrow();
+
+        writeln!(f, "<class {}", self.name)?;
+        if !methods.is_empty() || !class_methods.is_empty() {
+            for (name, obj) in methods.iter() {
+                writeln!(f, "- this.{} = {}", name, obj)?;
+            }
+            for (name, obj) in class_methods.iter() {
+                writeln!(f, "- {}.{} = {}", self.name, name, obj)?;
+            }
+        } else {
+            writeln!(f, "Methods: None")?;
+        }
+        writeln!(f, ">")?;
+        Ok(())
+    }
+}
+
+/**
+ * An instance of a `VmClass`, along with its fields.
+ */
+pub struct VmInstance {
+    /// The class this instance comes from.
+    pub class: Rc<VmClass>,
+    /// The fields declared for this instance.
+    pub fields: RefCell<HashMap<String, Object>>,
+}
+
+impl VmInstance {
+    pub fn new(class: &Rc<VmClass>) -> Self {
+        VmInstance {
+            class: Rc::clone(class),
+            fields: RefCell::new(HashMap::new()),
+        }
+    }
+}
+
+impl fmt::Debug for VmInstance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{self}")
+    }
+}
+
+impl fmt::Display for VmInstance {
+    /**
+     * Note: Displayed the same way as a `LoxInstance`.
+     */
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<instance of {}", self.class)?;
+
+        if !self.fields.borrow().is_empty() {
+            for (name, obj) in self.fields.borrow().iter() {
+                writeln!(f, "- this.{} = {}", name, obj)?;
+            }
+        } else {
+            writeln!(f, "No defined properties.")?;
+        }
+
+        writeln!(f, ">")?;
+
+        Ok(())
+    }
+}
@@ -1,4 +1,10 @@
+#![allow(dead_code)]
+
+use rs_lox_tw::errors::EvalError;
 use rs_lox_tw::interpreter::Interpreter;
+use rs_lox_tw::lox::{Backend, Lox};
+use rs_lox_tw::object::Object;
+use rs_lox_tw::output::BufferSink;
 use rs_lox_tw::scanner::Scanner;
 
 pub fn scanner_and_interpreter(source: &str) -> (Scanner, Interpreter) {
@@ -7,3 +13,29 @@ pub fn scanner_and_interpreter(source: &str) -> (Scanner, Interpreter) {
 
     (scanner, interpreter)
 }
+
+/**
+ * Runs the given source code in a new session, and returns its outcome along with what it
+ * printed.
+ */
+pub fn eval(source: &str) -> (Result<Object, EvalError>, String) {
+    let (result, output, _) = eval_on(Backend::TreeWalk, source);
+    (result, output)
+}
+
+/**
+ * Same as `eval` on the given backend, also returning the diagnostics written while running.
+ */
+pub fn eval_on(backend: Backend, source: &str) -> (Result<Object, EvalError>, String, String) {
+    let mut lox = Lox::with_backend(backend);
+    let output = BufferSink::new();
+    let diagnostics = BufferSink::new();
+    lox.interpreter().set_output(Box::new(output.clone()));
+    lox.interpreter()
+        .set_diagnostics(Box::new(diagnostics.clone()));
+    lox.vm().set_output(Box::new(output.clone()));
+    lox.vm().set_diagnostics(Box::new(diagnostics.clone()));
+
+    let result = lox.eval_source(source);
+    (result, output.contents(), diagnostics.contents())
+}
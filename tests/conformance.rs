@@ -0,0 +1,219 @@
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+
+use rs_lox_tw::errors::LoxResult;
+use rs_lox_tw::lox::{Backend, Lox};
+use rs_lox_tw::output::BufferSink;
+
+/// Scripts whose expectations are known not to hold yet on the tree-walking backend. They are
+/// still run and reported, and the test fails if one of them starts passing so that it gets
+/// removed from this list.
+const KNOWN_FAILURES: &[&str] = &[];
+
+/// Same as `KNOWN_FAILURES`, for the bytecode backend.
+const KNOWN_VM_FAILURES: &[&str] = &[];
+
+/**
+ * The output a script is expected to produce, read from its annotations:
+ * - `// expect: <line>` for a line printed to the output,
+ * - `// expect error: <message>` for a scanner, parser or resolver error,
+ * - `// expect runtime error: <message>` for a runtime error.
+ *
+ * An error is expected on the line of its annotation, unless the annotation starts with the line
+ * of the error, as in `// [line 6] expect error: <message>`.
+ */
+#[derive(Default)]
+struct Expectations {
+    output: Vec<String>,
+    errors: Vec<(usize, String)>,
+}
+
+impl Expectations {
+    fn parse(source: &str) -> Self {
+        let mut expectations = Self::default();
+
+        for (index, line) in source.lines().enumerate() {
+            let (line_number, line) =
+                explicit_line(line).unwrap_or_else(|| (index + 1, line.to_string()));
+            if let Some(expected) = annotation(&line, "// expect:") {
+                expectations.output.push(expected);
+            } else if let Some(expected) = annotation(&line, "// expect error:") {
+                expectations.errors.push((line_number, expected));
+            } else if let Some(expected) = annotation(&line, "// expect runtime error:") {
+                expectations.errors.push((line_number, expected));
+            }
+        }
+
+        expectations
+    }
+}
+
+/**
+ * Returns the line given by a `// [line N] ` prefix and the annotation following it, if any.
+ */
+fn explicit_line(line: &str) -> Option<(usize, String)> {
+    let start = line.find("// [line ")? + "// [line ".len();
+    let (number, rest) = line[start..].split_once("] ")?;
+    Some((number.parse().ok()?, format!("// {rest}")))
+}
+
+/**
+ * Returns the text following `marker` on the given line, if any.
+ */
+fn annotation(line: &str, marker: &str) -> Option<String> {
+    let start = line.find(marker)? + marker.len();
+    let text = &line[start..];
+    Some(text.strip_prefix(' ').unwrap_or(text).to_string())
+}
+
+/**
+ * Returns the line an error was reported at, read from its `Line N` location.
+ */
+fn error_line(error: &str) -> Option<usize> {
+    let start = error.find("Line ")? + "Line ".len();
+    let digits = error[start..]
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .collect::<String>();
+    digits.parse().ok()
+}
+
+/**
+ * Runs the script at `path` and compares what it printed with its annotations.
+ *
+ * Returns a description of every mismatch.
+ */
+fn check_script(path: &Path, backend: Backend) -> Result<(), String> {
+    let source = fs::read_to_string(path).map_err(|e| format!("cannot read the file: {e}"))?;
+    let expectations = Expectations::parse(&source);
+
+    let output = BufferSink::new();
+    let diagnostics = BufferSink::new();
+
+    let mut lox = Lox::with_backend(backend);
+    lox.interpreter().set_output(Box::new(output.clone()));
+    lox.interpreter()
+        .set_diagnostics(Box::new(diagnostics.clone()));
+    lox.vm().set_output(Box::new(output.clone()));
+    lox.vm().set_diagnostics(Box::new(diagnostics.clone()));
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| lox.eval_source(&source)))
+        .map_err(|_| "the interpreter panicked".to_string())?;
+
+    // Errors are either written to the diagnostics sink or returned, in that order
+    let mut errors: Vec<String> = diagnostics.contents().lines().map(String::from).collect();
+    if let Err(e) = result {
+        errors.extend(e.to_string().lines().map(String::from));
+    }
+
+    let mut mismatches = Vec::new();
+
+    let printed: Vec<String> = output.contents().lines().map(String::from).collect();
+    if printed != expectations.output {
+        mismatches.push(format!(
+            "expected output {:?}, got {:?}",
+            expectations.output, printed
+        ));
+    }
+
+    if errors.len() != expectations.errors.len()
+        || errors
+            .iter()
+            .zip(&expectations.errors)
+            .any(|(error, (line, expected))| {
+                error_line(error) != Some(*line) || !error.contains(expected.as_str())
+            })
+    {
+        mismatches.push(format!(
+            "expected errors {:?}, got {:?}",
+            expectations.errors, errors
+        ));
+    }
+
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(mismatches.join("\n    "))
+    }
+}
+
+/**
+ * Runs every script of the data directory on the given backend and fails if any of them
+ * doesn't behave as expected.
+ */
+fn check_data_scripts(backend: Backend, known_failures: &[&str]) {
+    let mut paths: Vec<_> = fs::read_dir("data")
+        .expect("Unable to read the data directory.")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "lox"))
+        .collect();
+    paths.sort();
+
+    // Silence the panic messages of the scripts that crash the interpreter
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let results: Vec<_> = paths
+        .iter()
+        .map(|path| (path, check_script(path, backend)))
+        .collect();
+    panic::set_hook(hook);
+
+    let mut failures = Vec::new();
+    for (path, result) in results {
+        let name = path.file_name().unwrap().to_string_lossy();
+        let known_failure = known_failures.contains(&name.as_ref());
+
+        match (result, known_failure) {
+            (Ok(()), false) => println!("PASS  {}", path.display()),
+            (Err(e), true) => println!("XFAIL {}\n    {e}", path.display()),
+            (Ok(()), true) => {
+                println!("XPASS {}", path.display());
+                failures.push(format!("{}: passes but is listed as a known failure", name));
+            }
+            (Err(e), false) => {
+                println!("FAIL  {}\n    {e}", path.display());
+                failures.push(format!("{name}:\n    {e}"));
+            }
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} script(s) failed:\n{}",
+        failures.len(),
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn test_data_scripts() {
+    check_data_scripts(Backend::TreeWalk, KNOWN_FAILURES);
+}
+
+#[test]
+fn test_data_scripts_vm() {
+    check_data_scripts(Backend::Vm, KNOWN_VM_FAILURES);
+}
+
+#[test]
+fn test_vm_unsupported_features() {
+    // The features only the tree-walking backend supports are rejected before running anything
+    let scripts = [
+        ("import \"module.lox\";", "E0405"),
+        ("import m from \"module.lox\";", "E0405"),
+        ("throw \"error\";", "E0406"),
+        ("try { print 1; } catch (e) { print e; }", "E0406"),
+        ("try { print 1; } finally { print 2; }", "E0406"),
+    ];
+
+    for (source, code) in scripts {
+        let output = BufferSink::new();
+        let mut lox = Lox::with_backend(Backend::Vm);
+        lox.vm().set_output(Box::new(output.clone()));
+
+        let error = LoxResult::from(lox.eval_source(source).unwrap_err());
+        assert_eq!(error.code(), Some(code), "{source}");
+        assert_eq!(output.contents(), "", "{source}");
+    }
+}
@@ -0,0 +1,354 @@
+use std::io::{Cursor, Write};
+use std::{env, fs};
+
+use rs_lox_tw::dap::DapServer;
+use rs_lox_tw::output::BufferSink;
+use serde_json::{json, Value};
+
+const PROGRAM: &str = "fun add(a, b) {
+  var sum = a + b;
+  return sum;
+}
+var x = 1;
+print add(x, 2);
+print add(x, 3);";
+
+/**
+ * Runs a session made of the given requests, as `(command, arguments)`, against a server, and
+ * returns the messages it sent back.
+ */
+fn session(name: &str, requests: &[(&str, Value)]) -> Vec<Value> {
+    session_with(name, PROGRAM, requests)
+}
+
+/**
+ * Same as `session`, debugging the given program. A source path of `"program"` stands for the
+ * path of the program.
+ */
+fn session_with(name: &str, program: &str, requests: &[(&str, Value)]) -> Vec<Value> {
+    let path = env::temp_dir().join(format!("rs_lox_tw_dap_{name}.lox"));
+    fs::write(&path, program).unwrap();
+    let path = path.to_str().unwrap();
+
+    // Frame the requests the way a client would
+    let mut input = String::new();
+    for (seq, (command, arguments)) in requests.iter().enumerate() {
+        let mut arguments = arguments.clone();
+        if *command == "launch" {
+            arguments["program"] = json!(path);
+        }
+        if arguments["source"]["path"] == "program" {
+            arguments["source"]["path"] = json!(path);
+        }
+        let request = json!({
+            "seq": seq + 1,
+            "type": "request",
+            "command": command,
+            "arguments": arguments,
+        })
+        .to_string();
+        input.push_str(&format!(
+            "Content-Length: {}\r\n\r\n{request}",
+            request.len()
+        ));
+    }
+
+    let output = BufferSink::new();
+    DapServer::new(Cursor::new(input), output.clone()).run();
+
+    // Split the output back into messages
+    let output = output.contents();
+    let mut messages = Vec::new();
+    let mut rest = output.as_str();
+    while let Some((header, content)) = rest.split_once("\r\n\r\n") {
+        let length = header["Content-Length: ".len()..].parse::<usize>().unwrap();
+        messages.push(serde_json::from_str(&content[..length]).unwrap());
+        rest = &content[length..];
+    }
+
+    messages
+}
+
+fn response<'a>(messages: &'a [Value], command: &str) -> Vec<&'a Value> {
+    messages
+        .iter()
+        .filter(|message| message["type"] == "response" && message["command"] == command)
+        .collect()
+}
+
+fn events<'a>(messages: &'a [Value], event: &str) -> Vec<&'a Value> {
+    messages
+        .iter()
+        .filter(|message| message["type"] == "event" && message["event"] == event)
+        .collect()
+}
+
+#[test]
+fn test_breakpoints_and_inspection() {
+    let messages = session(
+        "inspection",
+        &[
+            ("initialize", json!({ "adapterID": "lox" })),
+            ("launch", json!({})),
+            ("setBreakpoints", json!({ "breakpoints": [{ "line": 3 }] })),
+            ("configurationDone", json!({})),
+            ("threads", json!({})),
+            ("stackTrace", json!({ "threadId": 1 })),
+            ("scopes", json!({ "frameId": 2 })),
+            ("variables", json!({ "variablesReference": 1 })),
+            ("variables", json!({ "variablesReference": 2 })),
+            ("continue", json!({ "threadId": 1 })),
+            ("setBreakpoints", json!({ "breakpoints": [] })),
+            ("continue", json!({ "threadId": 1 })),
+            ("disconnect", json!({})),
+        ],
+    );
+
+    // Every request got a successful response, in order
+    assert!(messages
+        .iter()
+        .filter(|message| message["type"] == "response")
+        .all(|message| message["success"] == true));
+    assert_eq!(events(&messages, "initialized").len(), 1);
+
+    let stopped = events(&messages, "stopped");
+    assert_eq!(stopped.len(), 2);
+    assert_eq!(stopped[0]["body"]["reason"], "breakpoint");
+
+    let trace = &response(&messages, "stackTrace")[0]["body"];
+    assert_eq!(trace["totalFrames"], 2);
+    assert_eq!(trace["stackFrames"][0]["name"], "add");
+    assert_eq!(trace["stackFrames"][0]["line"], 3);
+    assert_eq!(trace["stackFrames"][0]["id"], 2);
+    assert_eq!(trace["stackFrames"][1]["name"], "script");
+    assert_eq!(trace["stackFrames"][1]["line"], 6);
+
+    let scopes = &response(&messages, "scopes")[0]["body"]["scopes"];
+    assert_eq!(scopes[0]["name"], "Locals");
+    assert_eq!(scopes[0]["variablesReference"], 1);
+    assert_eq!(
+        scopes.as_array().unwrap().last().unwrap()["name"],
+        "Globals"
+    );
+
+    let variables = response(&messages, "variables");
+    assert_eq!(
+        variables[0]["body"]["variables"],
+        json!([
+            { "name": "a", "value": "1", "variablesReference": 0 },
+            { "name": "b", "value": "2", "variablesReference": 0 },
+            { "name": "sum", "value": "3", "variablesReference": 0 },
+        ])
+    );
+    let globals = variables[1]["body"]["variables"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|variable| variable["name"].as_str().unwrap())
+        .collect::<Vec<&str>>();
+    assert!(globals.contains(&"add") && globals.contains(&"x"));
+
+    // The prints were sent as output events, then the program ended
+    let output = events(&messages, "output")
+        .iter()
+        .map(|event| event["body"]["output"].as_str().unwrap())
+        .collect::<String>();
+    assert_eq!(output, "3\n4\n");
+    assert_eq!(events(&messages, "exited")[0]["body"]["exitCode"], 0);
+    assert_eq!(events(&messages, "terminated").len(), 1);
+}
+
+#[test]
+fn test_stepping() {
+    let messages = session(
+        "stepping",
+        &[
+            ("initialize", json!({})),
+            ("launch", json!({ "stopOnEntry": true })),
+            ("configurationDone", json!({})),
+            ("next", json!({ "threadId": 1 })),
+            ("next", json!({ "threadId": 1 })),
+            ("stepIn", json!({ "threadId": 1 })),
+            ("stackTrace", json!({ "threadId": 1 })),
+            ("stepOut", json!({ "threadId": 1 })),
+            ("stackTrace", json!({ "threadId": 1 })),
+            ("disconnect", json!({})),
+        ],
+    );
+
+    let reasons = events(&messages, "stopped")
+        .iter()
+        .map(|event| event["body"]["reason"].as_str().unwrap())
+        .collect::<Vec<&str>>();
+    assert_eq!(reasons, vec!["entry", "step", "step", "step", "step"]);
+
+    let traces = response(&messages, "stackTrace");
+    assert_eq!(traces[0]["body"]["stackFrames"][0]["name"], "add");
+    assert_eq!(traces[0]["body"]["stackFrames"][0]["line"], 2);
+    assert_eq!(traces[1]["body"]["stackFrames"][0]["name"], "script");
+    assert_eq!(traces[1]["body"]["stackFrames"][0]["line"], 7);
+
+    // Disconnecting stopped the program before its last print
+    let output = events(&messages, "output");
+    assert_eq!(output.len(), 1);
+    assert_eq!(output[0]["body"]["output"], "3\n");
+}
+
+#[test]
+fn test_unsupported_requests() {
+    let messages = session(
+        "unsupported",
+        &[
+            ("initialize", json!({})),
+            ("evaluate", json!({ "expression": "x" })),
+            ("launch", json!({})),
+            ("configurationDone", json!({})),
+        ],
+    );
+
+    assert_eq!(response(&messages, "evaluate")[0]["success"], false);
+    assert_eq!(events(&messages, "exited")[0]["body"]["exitCode"], 0);
+}
+
+#[test]
+fn test_breakpoints_by_source() {
+    let messages = session(
+        "sources",
+        &[
+            ("initialize", json!({})),
+            ("launch", json!({})),
+            (
+                "setBreakpoints",
+                json!({
+                    "source": { "path": "program" },
+                    "breakpoints": [{ "line": 7 }, { "line": 3 }, { "line": 3 }, {}],
+                }),
+            ),
+            (
+                "setBreakpoints",
+                json!({
+                    "source": { "path": "other.lox" },
+                    "breakpoints": [{ "line": 2 }],
+                }),
+            ),
+            ("configurationDone", json!({})),
+            ("continue", json!({ "threadId": 1 })),
+            ("continue", json!({ "threadId": 1 })),
+            ("continue", json!({ "threadId": 1 })),
+        ],
+    );
+
+    // Each requested breakpoint is answered, in order
+    let responses = response(&messages, "setBreakpoints");
+    assert_eq!(
+        responses[0]["body"]["breakpoints"],
+        json!([
+            { "verified": true, "line": 7 },
+            { "verified": true, "line": 3 },
+            { "verified": true, "line": 3 },
+            { "verified": false, "message": "Expected a line." },
+        ])
+    );
+    assert_eq!(
+        responses[1]["body"]["breakpoints"],
+        json!([{ "verified": true, "line": 2 }])
+    );
+
+    // The program paused at its lines 3 (twice) and 7, not at the line 2 of another file
+    let stopped = events(&messages, "stopped");
+    assert_eq!(stopped.len(), 3);
+    assert!(stopped
+        .iter()
+        .all(|event| event["body"]["reason"] == "breakpoint"));
+    assert_eq!(events(&messages, "exited")[0]["body"]["exitCode"], 0);
+}
+
+#[test]
+fn test_lint_warnings() {
+    let program = "fun f() {\n  var unused = 1;\n}\nf();";
+    let messages = session_with(
+        "lint",
+        program,
+        &[
+            ("initialize", json!({})),
+            ("launch", json!({})),
+            ("configurationDone", json!({})),
+        ],
+    );
+
+    let output = events(&messages, "output");
+    assert_eq!(output.len(), 1);
+    assert_eq!(output[0]["body"]["category"], "stderr");
+    let warning = output[0]["body"]["output"].as_str().unwrap();
+    assert!(warning.starts_with("warning[W0001]"), "{warning}");
+    assert_eq!(events(&messages, "exited")[0]["body"]["exitCode"], 0);
+}
+
+#[test]
+fn test_no_input() {
+    let messages = session_with(
+        "input",
+        "print input();",
+        &[
+            ("initialize", json!({})),
+            ("launch", json!({})),
+            ("configurationDone", json!({})),
+        ],
+    );
+
+    // The program can't read the standard input, where the requests come from
+    let output = events(&messages, "output");
+    let error = output.last().unwrap()["body"]["output"].as_str().unwrap();
+    assert!(
+        error.starts_with("error[E0601]: No such variable 'input'."),
+        "{error}"
+    );
+    assert_eq!(events(&messages, "exited")[0]["body"]["exitCode"], 70);
+}
+
+/// An output the client closed after some messages, as a broken pipe.
+struct ClosedOutput(usize);
+
+impl Write for ClosedOutput {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self.0.checked_sub(1) {
+            Some(left) => {
+                self.0 = left;
+                Ok(buf.len())
+            }
+            None => Err(std::io::ErrorKind::BrokenPipe.into()),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_closed_output() {
+    let path = env::temp_dir().join("rs_lox_tw_dap_closed.lox");
+    fs::write(&path, PROGRAM).unwrap();
+    let mut input = String::new();
+    for (seq, command) in ["initialize", "launch", "configurationDone", "continue"]
+        .iter()
+        .enumerate()
+    {
+        let request = json!({
+            "seq": seq + 1,
+            "type": "request",
+            "command": command,
+            "arguments": { "program": path.to_str().unwrap(), "stopOnEntry": true },
+        })
+        .to_string();
+        input.push_str(&format!(
+            "Content-Length: {}\r\n\r\n{request}",
+            request.len()
+        ));
+    }
+
+    // The session ends instead of panicking, whenever the client goes away
+    for written in 0..8 {
+        DapServer::new(Cursor::new(input.clone()), ClosedOutput(written)).run();
+    }
+}
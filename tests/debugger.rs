@@ -0,0 +1,297 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::env;
+use std::fs;
+use std::io::Cursor;
+use std::rc::Rc;
+
+use rs_lox_tw::debugger::*;
+use rs_lox_tw::debugger_cli::CliDebugger;
+use rs_lox_tw::errors::*;
+use rs_lox_tw::lox::Lox;
+use rs_lox_tw::object::Object;
+use rs_lox_tw::output::BufferSink;
+
+const SOURCE: &str = "fun add(a, b) {
+  var sum = a + b;
+  return sum;
+}
+var x = 1;
+var y = add(x, 2);
+print y;
+print add(y, y);";
+
+/// A pause seen by the front end: why, where and in which function.
+type Seen = (PauseReason, usize, String);
+
+/**
+ * A front end answering each pause with the next scripted command, and recording the pauses.
+ */
+struct Scripted {
+    commands: VecDeque<DebugCommand>,
+    seen: Rc<RefCell<Vec<Seen>>>,
+    variables: Rc<RefCell<Vec<Option<Object>>>>,
+}
+
+impl DebugFrontend for Scripted {
+    fn paused(&mut self, pause: &mut Pause) -> DebugCommand {
+        let function = pause.frames.last().unwrap().name.clone();
+        self.seen
+            .borrow_mut()
+            .push((pause.reason, pause.line(), function));
+        self.variables.borrow_mut().push(pause.variable("sum"));
+        self.commands.pop_front().unwrap_or(DebugCommand::Continue)
+    }
+}
+
+/**
+ * Runs `SOURCE` in the debugger with the given commands and breakpoints, and returns the pauses,
+ * the values of `sum` at each pause, the outcome and the output.
+ */
+fn debug(
+    commands: &[DebugCommand],
+    breakpoints: &[usize],
+) -> (
+    Vec<Seen>,
+    Vec<Option<Object>>,
+    Result<Object, EvalError>,
+    String,
+) {
+    let mut lox = Lox::new();
+    let mut lines = Breakpoints::new();
+    for &line in breakpoints {
+        lines.insert(line);
+    }
+    debug_source(&mut lox, SOURCE, commands, lines)
+}
+
+/**
+ * Same as `debug`, for the given session and source code.
+ */
+fn debug_source(
+    lox: &mut Lox,
+    source: &str,
+    commands: &[DebugCommand],
+    breakpoints: Breakpoints,
+) -> (
+    Vec<Seen>,
+    Vec<Option<Object>>,
+    Result<Object, EvalError>,
+    String,
+) {
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    let variables = Rc::new(RefCell::new(Vec::new()));
+    let mut debugger = Debugger::new(Box::new(Scripted {
+        commands: commands.iter().copied().collect(),
+        seen: Rc::clone(&seen),
+        variables: Rc::clone(&variables),
+    }));
+    debugger.set_breakpoints(breakpoints);
+
+    let output = BufferSink::new();
+    lox.interpreter().set_output(Box::new(output.clone()));
+    lox.interpreter().set_debugger(debugger);
+
+    let result = lox.eval_source(source);
+    let seen = seen.borrow().clone();
+    let variables = variables.borrow().clone();
+    (seen, variables, result, output.contents())
+}
+
+fn at(reason: PauseReason, line: usize, function: &str) -> Seen {
+    (reason, line, function.to_string())
+}
+
+#[test]
+fn test_breakpoints() {
+    let (seen, variables, result, output) = debug(&[], &[3]);
+
+    assert!(result.is_ok());
+    assert_eq!(output, "3\n6\n");
+    assert_eq!(
+        seen,
+        vec![
+            at(PauseReason::Entry, 1, "script"),
+            at(PauseReason::Breakpoint, 3, "add"),
+            at(PauseReason::Breakpoint, 3, "add"),
+        ]
+    );
+    assert_eq!(
+        variables,
+        vec![None, Some(Object::Num(3.0)), Some(Object::Num(6.0))]
+    );
+}
+
+#[test]
+fn test_breakpoints_in_loops() {
+    // A breakpoint pauses on each pass of a loop, even on a single line: the first pass of
+    // each loop pauses on its initializer or condition, the next ones on its body
+    let source = "var total = 0;
+for (var i = 0; i < 3; i = i + 1) total = total + i;
+while (total < 5) { total = total + 1; }
+print total;";
+    let mut breakpoints = Breakpoints::new();
+    breakpoints.insert(2);
+    breakpoints.insert(3);
+
+    let (seen, _, result, output) = debug_source(&mut Lox::new(), source, &[], breakpoints.clone());
+    assert!(result.is_ok());
+    assert_eq!(output, "5\n");
+    let lines: Vec<usize> = seen.iter().map(|(_, line, _)| *line).collect();
+    assert_eq!(lines, vec![1, 2, 2, 2, 3, 3]);
+
+    // Two statements on a line pause once
+    let source = "print 0;\nvar a = 1; var b = 2;";
+    let (seen, _, _, _) = debug_source(&mut Lox::new(), source, &[], breakpoints);
+    assert_eq!(seen.len(), 2);
+}
+
+#[test]
+fn test_breakpoint_on_trailing_expression() {
+    // The value of the last expression statement is returned, it still pauses the program
+    let source = "fun f() {\n  return 1;\n}\nvar a = 0;\nf();";
+    let mut breakpoints = Breakpoints::new();
+    breakpoints.insert(5);
+
+    let (seen, _, result, _) = debug_source(&mut Lox::new(), source, &[], breakpoints);
+    assert_eq!(result, Ok(Object::Num(1.0)));
+    assert_eq!(
+        seen,
+        vec![
+            at(PauseReason::Entry, 1, "script"),
+            at(PauseReason::Breakpoint, 5, "script"),
+        ]
+    );
+}
+
+#[test]
+fn test_breakpoints_on_literals() {
+    // Statements starting with a literal pause on it
+    let source = "var i = 0;
+while (true) {
+  i = i + 1;
+  if (i == 2) break;
+}
+\"done\";";
+    let mut breakpoints = Breakpoints::new();
+    breakpoints.insert(2);
+    breakpoints.insert(6);
+
+    let (seen, _, result, _) = debug_source(&mut Lox::new(), source, &[], breakpoints);
+    assert_eq!(result, Ok(Object::Str("done".to_string())));
+    assert_eq!(
+        seen,
+        vec![
+            at(PauseReason::Entry, 1, "script"),
+            at(PauseReason::Breakpoint, 2, "script"),
+            at(PauseReason::Breakpoint, 6, "script"),
+        ]
+    );
+}
+
+#[test]
+fn test_breakpoints_in_modules() {
+    let directory = env::temp_dir().join("rs_lox_tw_debugger_modules");
+    fs::create_dir_all(&directory).unwrap();
+    fs::write(
+        directory.join("module.lox"),
+        "fun double(n) {\n  return n * 2;\n}",
+    )
+    .unwrap();
+    let script = directory.join("main.lox");
+    let source = "import m from \"module.lox\";\nprint m.double(2);";
+    fs::write(&script, source).unwrap();
+
+    // The line 2 of the module isn't the line 2 of the program
+    let mut breakpoints = Breakpoints::new();
+    breakpoints.set_file(directory.join("module.lox").to_str().unwrap(), [2]);
+    let mut lox = Lox::new();
+    lox.interpreter().set_script(script.to_str().unwrap());
+    let (seen, _, result, output) = debug_source(&mut lox, source, &[], breakpoints);
+
+    assert!(result.is_ok());
+    assert_eq!(output, "4\n");
+    assert_eq!(
+        seen,
+        vec![
+            at(PauseReason::Entry, 1, "script"),
+            at(PauseReason::Breakpoint, 2, "double"),
+        ]
+    );
+
+    // The program can be named by its path too
+    let mut breakpoints = Breakpoints::new();
+    breakpoints.set_file(script.to_str().unwrap(), [2]);
+    let mut lox = Lox::new();
+    lox.interpreter().set_script(script.to_str().unwrap());
+    let (seen, _, _, _) = debug_source(&mut lox, source, &[], breakpoints);
+    assert_eq!(seen[1], at(PauseReason::Breakpoint, 2, "script"));
+}
+
+#[test]
+fn test_stepping() {
+    use DebugCommand::*;
+
+    let (seen, _, _, _) = debug(&[StepOver, StepOver, StepInto, StepOver, StepOver], &[]);
+    assert_eq!(
+        seen,
+        vec![
+            at(PauseReason::Entry, 1, "script"),
+            at(PauseReason::Step, 5, "script"),
+            at(PauseReason::Step, 6, "script"),
+            at(PauseReason::Step, 2, "add"),
+            at(PauseReason::Step, 3, "add"),
+            at(PauseReason::Step, 7, "script"),
+        ]
+    );
+
+    let (seen, _, _, _) = debug(&[Continue, StepOut, StepOver], &[2]);
+    assert_eq!(
+        seen,
+        vec![
+            at(PauseReason::Entry, 1, "script"),
+            at(PauseReason::Breakpoint, 2, "add"),
+            at(PauseReason::Step, 7, "script"),
+            at(PauseReason::Step, 8, "script"),
+            at(PauseReason::Breakpoint, 2, "add"),
+        ]
+    );
+}
+
+#[test]
+fn test_stop() {
+    let (seen, _, result, output) = debug(&[DebugCommand::Continue, DebugCommand::Stop], &[2]);
+
+    assert_eq!(seen.len(), 2);
+    assert_eq!(result, Err(EvalError::Runtime(LoxResult::Stop)));
+    assert_eq!(output, "");
+}
+
+#[test]
+fn test_command_line() {
+    let output = BufferSink::new();
+    let input = Cursor::new("b 3\nc\nbt\np sum\np nothing\nd 3\nc\n");
+    let frontend = CliDebugger::new(SOURCE, input, output.clone());
+
+    let mut lox = Lox::new();
+    lox.interpreter().set_output(Box::new(BufferSink::new()));
+    lox.interpreter()
+        .set_debugger(Debugger::new(Box::new(frontend)));
+    assert!(lox.eval_source(SOURCE).is_ok());
+
+    let output = output.contents();
+    assert_eq!(
+        output,
+        "Paused on entry at line 1 in script
+   1 | fun add(a, b) {
+(debug) Breakpoint set at line 3.
+(debug) Paused on breakpoint at line 3 in add
+   3 |   return sum;
+(debug) #0 add at line 3
+#1 script at line 6
+(debug) sum = 3
+(debug) No variable named 'nothing'.
+(debug) Breakpoint removed at line 3.
+(debug) "
+    );
+}
@@ -0,0 +1,274 @@
+use rs_lox_tw::diagnostic::{SourceFile, SourceMap};
+use rs_lox_tw::errors::*;
+use rs_lox_tw::lox::{Backend, Lox};
+use rs_lox_tw::object::Object;
+use rs_lox_tw::output::BufferSink;
+use rs_lox_tw::scanner::Scanner;
+
+#[test]
+fn test_render_underlines_the_token() {
+    let source = "var a = 1;\nprint a + \"two\";\n";
+    let mut lox = Lox::new();
+    let error: LoxResult = lox.eval_source(source).unwrap_err().into();
+
+    assert_eq!(
+        SourceFile::new("script.lox", source).render(&error),
+        "error[E0504]: Operands must be two numbers or two strings.
+ --> script.lox:2:9
+  |
+2 | print a + \"two\";
+  |         ^"
+    );
+}
+
+#[test]
+fn test_scanner_errors_have_a_span() {
+    let source = "var s = \"abc;";
+    let mut lox = Lox::new();
+    let error: LoxResult = lox.eval_source(source).unwrap_err().into();
+
+    assert_eq!(
+        SourceFile::new("script.lox", source).render(&error),
+        "error[E0102]: Encountered an unterminated string.
+ --> script.lox:1:9
+  |
+1 | var s = \"abc;
+  |         ^^^^^"
+    );
+}
+
+#[test]
+fn test_spans_tell_their_source() {
+    let mut sources = SourceMap::new();
+    let id = sources.add("lib.lox", "// A library\nvar s = \"abc;");
+    let error = Scanner::with_source_id(sources.get(id).unwrap().source, id)
+        .scan_tokens()
+        .unwrap_err();
+    assert_eq!(error.span().map(|span| span.source), Some(id));
+
+    // The error is rendered against the source it is in, not the one being rendered
+    assert_eq!(
+        SourceFile::new("script.lox", "var a = 1;")
+            .with_sources(&sources)
+            .render(&error),
+        "error[E0102]: Encountered an unterminated string.
+ --> lib.lox:2:9
+  |
+2 | var s = \"abc;
+  |         ^^^^^"
+    );
+}
+
+#[test]
+fn test_render_every_syntax_error() {
+    let source = "print ;\nvar = 2;";
+    let mut lox = Lox::new();
+    let error: LoxResult = lox.eval_source(source).unwrap_err().into();
+    let rendered = SourceFile::new("script.lox", source).render(&error);
+
+    assert!(rendered.contains(" --> script.lox:1:7"));
+    assert!(rendered.contains(" --> script.lox:2:5"));
+}
+
+#[test]
+fn test_render_error_at_end_of_input() {
+    // The missing operand is pointed at after the last token, not under it
+    let source = "print 1 +";
+    let mut lox = Lox::new();
+    let error: LoxResult = lox.eval_source(source).unwrap_err().into();
+
+    assert_eq!(
+        SourceFile::new("script.lox", source).render(&error),
+        "error[E0201]: Expected expression.
+ --> script.lox:1:10
+  |
+1 | print 1 +
+  |          ^"
+    );
+}
+
+#[test]
+fn test_render_help() {
+    let source = "return 1;";
+    let mut lox = Lox::new();
+    let error: LoxResult = lox.eval_source(source).unwrap_err().into();
+
+    assert_eq!(error.code(), Some("E0303"));
+    assert_eq!(
+        SourceFile::new("script.lox", source).render(&error),
+        "error[E0303]: Can't return from top level code.
+ --> script.lox:1:1
+  |
+1 | return 1;
+  | ^^^^^^
+  = help: Only functions can return."
+    );
+}
+
+#[test]
+fn test_render_traceback() {
+    let source = "fun area(side) {
+  return side * side;
+}
+fun describe(side) {
+  print \"area: \" + area(side);
+}
+describe(\"big\");";
+    let mut lox = Lox::new();
+    let error: LoxResult = lox.eval_source(source).unwrap_err().into();
+
+    let LoxResult::Runtime { trace, .. } = &error else {
+        panic!("expected a runtime error");
+    };
+    let functions = trace
+        .iter()
+        .map(|frame| frame.function.as_str())
+        .collect::<Vec<&str>>();
+    assert_eq!(functions, vec!["describe", "area"]);
+
+    assert_eq!(
+        SourceFile::new("script.lox", source).render(&error),
+        "Traceback (most recent call last):
+  File \"script.lox\", line 7, in script
+    describe(\"big\");
+  File \"script.lox\", line 5, in describe
+    print \"area: \" + area(side);
+  File \"script.lox\", line 2, in area
+    return side * side;
+error[E0503]: Both operands must be a number.
+ --> script.lox:2:15
+  |
+2 |   return side * side;
+  |               ^"
+    );
+
+    // The top-level code has no traceback
+    let error: LoxResult = lox.eval_source("-\"muffin\";").unwrap_err().into();
+    assert!(matches!(error, LoxResult::Runtime { ref trace, .. } if trace.is_empty()));
+}
+
+#[test]
+fn test_render_expression_statement_traceback() {
+    let source = "fun outer(shape) {
+  return shape.side;
+}
+outer(nil);
+print \"done\";";
+    let output = BufferSink::new();
+    let diagnostics = BufferSink::new();
+    let mut lox = Lox::new();
+    lox.interpreter().set_output(Box::new(output.clone()));
+    lox.interpreter()
+        .set_diagnostics(Box::new(diagnostics.clone()));
+    lox.interpreter().set_source_file("script.lox", source);
+
+    // The program goes on after the error of an expression statement, which is rendered
+    assert_eq!(lox.eval_source(source), Ok(Object::Nil));
+    assert_eq!(output.contents(), "\"done\"\n");
+    assert_eq!(
+        diagnostics.contents(),
+        "Traceback (most recent call last):
+  File \"script.lox\", line 4, in script
+    outer(nil);
+  File \"script.lox\", line 2, in outer
+    return shape.side;
+error[E0507]: Only classes have properties.
+ --> script.lox:2:16
+  |
+2 |   return shape.side;
+  |                ^^^^
+
+"
+    );
+}
+
+#[test]
+fn test_render_expression_statement_errors_on_the_vm() {
+    let source = "var m = {};\nm[[1]] = 3;\nm;";
+    let diagnostics = BufferSink::new();
+    let mut lox = Lox::with_backend(Backend::Vm);
+    lox.vm().set_diagnostics(Box::new(diagnostics.clone()));
+    lox.set_source_file("script.lox", source);
+
+    assert!(lox.eval_source(source).is_ok());
+    assert_eq!(
+        diagnostics.contents(),
+        "error[E0515]: Map keys must be strings, numbers, booleans or nil.
+ --> script.lox:2:6
+  |
+2 | m[[1]] = 3;
+  |      ^
+
+"
+    );
+}
+
+#[test]
+fn test_warnings_do_not_stop_the_program() {
+    let source = "fun f(unused) { return 1; }\nf(2);";
+    let mut lox = Lox::new();
+    lox.set_lint(true);
+
+    assert_eq!(lox.eval_source(source), Ok(Object::Num(1.0)));
+    let warnings = lox.take_warnings();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].severity(), Severity::Warning);
+    assert_eq!(
+        SourceFile::new("script.lox", source).render(&warnings[0]),
+        "warning[W0002]: Unused parameter 'unused'.
+ --> script.lox:1:7
+  |
+1 | fun f(unused) { return 1; }
+  |       ^^^^^^
+  = help: Start the name with '_' if it is meant to be unused."
+    );
+    assert_eq!(lox.take_warnings(), vec![]);
+
+    // Each run only keeps its own warnings
+    assert!(lox.eval_source(source).is_ok());
+    assert!(lox.eval_source("f(3);").is_ok());
+    assert_eq!(lox.take_warnings(), vec![]);
+
+    // The code isn't linted unless asked to
+    let mut lox = Lox::new();
+    assert!(lox.eval_source(source).is_ok());
+    assert_eq!(lox.take_warnings(), vec![]);
+}
+
+#[test]
+fn test_deny_warnings() {
+    let mut lox = Lox::new();
+    lox.set_lint(true);
+    lox.set_deny_warnings(true);
+
+    // Warnings keep the program from running
+    let result = lox.eval_source("var ran = true;\nfun f(unused) {}");
+    assert!(matches!(result, Err(EvalError::Warnings(warnings)) if warnings.len() == 1));
+    assert_eq!(lox.get_global("ran"), None);
+
+    // Notes don't
+    let result = lox.eval_source("var a = 1;\n{ var a = 2; a = a + 1; }\na;");
+    assert_eq!(result, Ok(Object::Num(1.0)));
+    let notes = lox.take_warnings();
+    assert_eq!(notes.len(), 1);
+    assert_eq!(notes[0].severity(), Severity::Note);
+    assert_eq!(notes[0].code(), Some("W0004"));
+}
+
+#[test]
+fn test_lint_knows_the_session_globals() {
+    let mut lox = Lox::new();
+    lox.set_lint(true);
+    lox.set_deny_warnings(true);
+
+    // Globals defined by the host or by previous snippets aren't undeclared
+    lox.define_global("score", Object::Num(1.0));
+    assert_eq!(lox.eval_source("score = score + 1;"), Ok(Object::Num(2.0)));
+    assert!(lox.eval_source("fun add(a, b) { return a + b; }").is_ok());
+    assert_eq!(lox.eval_source("add(score, 1);"), Ok(Object::Num(3.0)));
+
+    // Their arity is still checked
+    let result = lox.eval_source("add(1);");
+    assert!(matches!(result, Err(EvalError::Warnings(warnings))
+        if warnings[0].code() == Some("W0006")));
+}
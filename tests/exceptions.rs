@@ -0,0 +1,114 @@
+use rs_lox_tw::errors::*;
+use rs_lox_tw::lox::{Backend, Lox};
+use rs_lox_tw::object::Object;
+
+mod common;
+use common::eval;
+
+#[test]
+fn test_throw_and_catch() {
+    let (result, output) = eval(
+        "fun check(n) {
+          if (n > 2) throw \"too big\";
+          return n;
+        }
+        try {
+          print check(1);
+          print check(3);
+          print \"not printed\";
+        } catch (e) {
+          print e;
+        }
+        class Oops { init(message) { this.message = message; } }
+        try {
+          try { throw Oops(\"inner\"); } finally { print \"cleanup\"; }
+        } catch (oops) {
+          print oops.message;
+        }",
+    );
+
+    assert!(result.is_ok());
+    assert_eq!(output, "1\n\"too big\"\n\"cleanup\"\n\"inner\"\n");
+}
+
+#[test]
+fn test_catch_runtime_errors() {
+    let (result, output) = eval(
+        "try {
+          print 1 - \"one\";
+        } catch (error) {
+          print error.kind;
+          print error.message;
+        }
+        try { print unknown; } catch (error) { print error.kind; }
+        fun f() {
+          nil.field;
+          print \"not printed\";
+        }
+        try { f(); } catch (error) { print error.kind; }
+        nil.field;
+        print \"reported\";",
+    );
+
+    assert!(result.is_ok());
+    assert_eq!(
+        output,
+        "\"ExpectedNumberOperands\"
+\"Both operands must be a number.\"
+\"UnknownVariable\"
+\"InvalidObjectProperty\"
+\"reported\"
+"
+    );
+}
+
+#[test]
+fn test_finally() {
+    let (result, output) = eval(
+        "fun f() {
+          try { return \"returned\"; } finally { print \"finally\"; }
+        }
+        print f();
+        var i = 0;
+        while (true) {
+          try { i = i + 1; if (i == 2) break; } finally { print i; }
+        }
+        fun g() {
+          try { throw \"lost\"; } finally { return \"replaced\"; }
+        }
+        print g();",
+    );
+
+    assert!(result.is_ok());
+    assert_eq!(output, "\"finally\"\n\"returned\"\n1\n2\n\"replaced\"\n");
+}
+
+#[test]
+fn test_uncaught() {
+    let (result, output) = eval(
+        "fun fail() { throw \"boom\"; }
+        try { fail(); } finally { print \"finally\"; }
+        print \"not printed\";",
+    );
+
+    assert_eq!(output, "\"finally\"\n");
+    let error: LoxResult = result.unwrap_err().into();
+    assert_eq!(error.code(), Some("E0519"));
+    assert_eq!(error.message(), "Uncaught \"boom\".");
+    let LoxResult::Thrown { value, trace, .. } = error else {
+        panic!("expected a thrown value");
+    };
+    assert_eq!(value, Object::from("boom"));
+    assert_eq!(trace.len(), 1);
+    assert_eq!(trace[0].function, "fail");
+}
+
+#[test]
+fn test_try_errors() {
+    let (result, _) = eval("try { print 1; }");
+    assert!(matches!(result, Err(EvalError::Parser(_))));
+
+    let mut lox = Lox::with_backend(Backend::Vm);
+    let error: LoxResult = lox.eval_source("throw 1;").unwrap_err().into();
+    assert_eq!(error.code(), Some("E0406"));
+}
@@ -0,0 +1,151 @@
+use std::fs;
+
+use rs_lox_tw::formatter::format;
+use rs_lox_tw::lox::Lox;
+use rs_lox_tw::output::BufferSink;
+use rs_lox_tw::scanner::Scanner;
+
+/**
+ * Returns what a script prints, along with whether it ran without errors.
+ */
+fn run(source: &str) -> (String, bool) {
+    let mut lox = Lox::new();
+    let output = BufferSink::new();
+    lox.interpreter().set_output(Box::new(output.clone()));
+    lox.interpreter()
+        .set_diagnostics(Box::new(BufferSink::new()));
+
+    let ok = lox.eval_source(source).is_ok();
+    (output.contents(), ok)
+}
+
+#[test]
+fn test_layout() {
+    let source = "class   Point<Base{init(x,y){this.x=x;this.y=y;}
+norm{return this.x*this.x+this.y*this.y;}
+
+
+class origin(){return Point(0,-0);}}
+var p=Point(1,2);if(p.norm>=-1and!false)print p.norm;else{print[1,2][0];}
+for(var i=0;i<2;i=i+1){print{\"a\":i}[\"a\"];}
+for(;;){break;}
+var f=fun(a){return -a;};print f(1)-f(2);
+class Empty{}
+";
+
+    let expected = "class Point < Base {
+  init(x, y) {
+    this.x = x;
+    this.y = y;
+  }
+  norm {
+    return this.x * this.x + this.y * this.y;
+  }
+
+  class origin() {
+    return Point(0, -0);
+  }
+}
+var p = Point(1, 2);
+if (p.norm >= -1 and !false) print p.norm;
+else {
+  print [1, 2][0];
+}
+for (var i = 0; i < 2; i = i + 1) {
+  print {\"a\": i}[\"a\"];
+}
+for (;;) {
+  break;
+}
+var f = fun (a) {
+  return -a;
+};
+print f(1) - f(2);
+class Empty {}
+";
+
+    assert_eq!(format(source).unwrap(), expected);
+}
+
+#[test]
+fn test_comments() {
+    let source = "// Header
+
+var a = 1;   // trailing
+{ // after a brace
+  // own line
+  print a; }
+if (a) { print a; } // after a block
+else print -a;
+// footer
+";
+
+    let expected = "// Header
+
+var a = 1; // trailing
+{ // after a brace
+  // own line
+  print a;
+}
+if (a) {
+  print a;
+} // after a block
+else print -a;
+// footer
+";
+
+    assert_eq!(format(source).unwrap(), expected);
+}
+
+#[test]
+fn test_functions_in_literals() {
+    // The bodies are indented from the line of the literal, which closes right after them
+    let source = fs::read_to_string("data/literal_functions.lox").unwrap();
+    assert_eq!(format(&source).unwrap(), source);
+
+    let source = "var m = {\"a\": 1, \"b\": fun (y) { return y; }};";
+    let expected = "var m = {\"a\": 1, \"b\": fun (y) {
+  return y;
+}};
+";
+    assert_eq!(format(source).unwrap(), expected);
+}
+
+#[test]
+fn test_scanner_errors() {
+    assert!(format("var a = \"unterminated;").is_err());
+    assert!(format("var a = 1 # 2;").is_err());
+}
+
+#[test]
+fn test_data_scripts() {
+    for entry in fs::read_dir("data").unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_none_or(|extension| extension != "lox") {
+            continue;
+        }
+        let source = fs::read_to_string(&path).unwrap();
+        let formatted = format(&source).unwrap();
+
+        // Formatting is idempotent
+        assert_eq!(format(&formatted).unwrap(), formatted, "{path:?}");
+
+        // Only the layout changed: same tokens and comments, same behavior
+        let mut before = Scanner::new(&source);
+        let mut after = Scanner::new(&formatted);
+        assert_eq!(
+            before.scan_tokens().unwrap(),
+            after.scan_tokens().unwrap(),
+            "{path:?}"
+        );
+        let comments = |scanner: &Scanner| {
+            scanner
+                .comments
+                .iter()
+                .map(|comment| comment.text.clone())
+                .collect::<Vec<String>>()
+        };
+        assert_eq!(comments(&before), comments(&after), "{path:?}");
+        assert_eq!(run(&source), run(&formatted), "{path:?}");
+    }
+}
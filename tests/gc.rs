@@ -0,0 +1,85 @@
+use rs_lox_tw::lox::{Backend, Lox};
+use rs_lox_tw::object::Object;
+
+/// Allocates, on each iteration, an instance holding a method bound to itself, a closure
+/// capturing it, a list containing itself and a local function calling itself.
+const CYCLES: &str = "class Node {
+    init() {
+        this.self = this;
+        this.method = this.describe;
+        this.callback = fun () { return this; };
+        this.list = [this];
+        push(this.list, this.list);
+    }
+    describe() { return \"node\"; }
+}
+
+fun allocate() {
+    var node = Node();
+    fun recurse(n) {
+        if (n > 0) return recurse(n - 1);
+        return node;
+    }
+    return recurse(2);
+}
+
+var last;
+for (var i = 0; i < 5000; i = i + 1) {
+    last = allocate();
+}
+last.callback().describe();";
+
+fn check_bounded_memory(backend: Backend) {
+    let mut lox = Lox::with_backend(backend);
+
+    assert_eq!(lox.eval_source(CYCLES), Ok(Object::from("node")));
+
+    // Automatic collections kept the heap from growing with the number of iterations, each one
+    // allocating about ten objects
+    assert!(
+        lox.live_objects() < 10_000,
+        "{} live objects",
+        lox.live_objects()
+    );
+
+    // Only the objects still referenced by the globals survive a full collection
+    lox.collect_garbage();
+    let live = lox.live_objects();
+    assert!(live < 50, "{live} live objects");
+    assert_eq!(lox.get_global("i"), None);
+    assert!(lox.eval_source("last.self.list[1][0].describe();").is_ok());
+
+    // Dropping the last reference makes the remaining cycle garbage
+    lox.eval_source("last = nil;").unwrap();
+    assert!(lox.collect_garbage() > 0);
+    assert!(lox.live_objects() < live);
+}
+
+#[test]
+fn test_cycles_are_collected() {
+    check_bounded_memory(Backend::TreeWalk);
+}
+
+#[test]
+fn test_cycles_are_collected_vm() {
+    check_bounded_memory(Backend::Vm);
+}
+
+#[test]
+fn test_reachable_objects_are_kept() {
+    let mut lox = Lox::new();
+
+    lox.eval_source(
+        "fun counter() {
+            var count = 0;
+            fun increment() { count = count + 1; return count; }
+            return increment;
+        }
+        var next = counter();
+        next();",
+    )
+    .unwrap();
+
+    lox.collect_garbage();
+    assert_eq!(lox.eval_source("next();"), Ok(Object::Num(2.0)));
+}
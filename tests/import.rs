@@ -0,0 +1,232 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use rs_lox_tw::diagnostic::SourceFile;
+use rs_lox_tw::errors::*;
+use rs_lox_tw::lox::{Backend, Lox};
+use rs_lox_tw::object::Object;
+
+/**
+ * Writes the given `(file name, source)` modules to a directory of their own, and returns the
+ * path of the first one.
+ */
+fn write_modules(name: &str, modules: &[(&str, &str)]) -> String {
+    let directory = env::temp_dir().join(format!("rs_lox_tw_import_{name}"));
+    for (file, source) in modules {
+        let path = directory.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, source).unwrap();
+    }
+
+    let path: PathBuf = directory.join(modules[0].0);
+    path.to_str().unwrap().to_string()
+}
+
+fn run(name: &str, modules: &[(&str, &str)]) -> (Lox, Result<Object, EvalError>) {
+    let path = write_modules(name, modules);
+    let mut lox = Lox::new();
+    let result = lox.eval_file(&path);
+    (lox, result)
+}
+
+#[test]
+fn test_import() {
+    let (lox, result) = run(
+        "namespace",
+        &[
+            (
+                "main.lox",
+                "import \"geometry.lox\";
+import shapes from \"lib/shapes.lox\";
+var area = geometry.square(3);
+var name = shapes.name;",
+            ),
+            ("geometry.lox", "fun square(side) { return side * side; }"),
+            ("lib/shapes.lox", "var name = \"circle\";"),
+        ],
+    );
+
+    assert!(result.is_ok());
+    assert_eq!(lox.get_global("area"), Some(Object::Num(9.0)));
+    assert_eq!(lox.get_global("name"), Some(Object::from("circle")));
+    // The definitions of a module stay out of the globals of the script
+    assert_eq!(lox.get_global("square"), None);
+}
+
+#[test]
+fn test_modules_run_once() {
+    let (lox, result) = run(
+        "once",
+        &[
+            (
+                "main.lox",
+                "import first from \"counter.lox\";
+import second from \"counter.lox\";
+first.next();
+var count = second.next();",
+            ),
+            (
+                "counter.lox",
+                "var count = 0;
+fun next() { count = count + 1; return count; }",
+            ),
+        ],
+    );
+
+    assert!(result.is_ok());
+    // Both imports share the module, and its functions use its own globals
+    assert_eq!(lox.get_global("count"), Some(Object::Num(2.0)));
+}
+
+#[test]
+fn test_import_sibling_directory() {
+    let (lox, result) = run(
+        "sibling",
+        &[
+            (
+                "app/main.lox",
+                "import util from \"../shared/util.lox\";
+var twice = util.double(2);",
+            ),
+            ("shared/util.lox", "fun double(x) { return x * 2; }"),
+        ],
+    );
+
+    assert!(result.is_ok());
+    assert_eq!(lox.get_global("twice"), Some(Object::Num(4.0)));
+}
+
+#[test]
+fn test_import_errors() {
+    let (_, result) = run("missing", &[("main.lox", "import \"missing.lox\";")]);
+    let error: LoxResult = result.unwrap_err().into();
+    assert_eq!(error.code(), Some("E0517"));
+
+    let (_, result) = run(
+        "circular",
+        &[
+            ("main.lox", "import \"a.lox\";"),
+            ("a.lox", "import \"b.lox\";"),
+            ("b.lox", "import \"a.lox\";"),
+        ],
+    );
+    let error: LoxResult = result.unwrap_err().into();
+    assert_eq!(error.code(), Some("E0518"));
+    assert_eq!(
+        error.message(),
+        "In the module \"a.lox\": In the module \"b.lox\": Circular import of the module \"a.lox\"."
+    );
+
+    let (_, result) = run(
+        "undefined",
+        &[
+            ("main.lox", "import \"empty.lox\";\nprint empty.nothing;"),
+            ("empty.lox", ""),
+        ],
+    );
+    let error: LoxResult = result.unwrap_err().into();
+    assert_eq!(error.code(), Some("E0508"));
+
+    let (_, result) = run(
+        "read_only",
+        &[
+            ("main.lox", "import \"constants.lox\";\nconstants.pi = 4;"),
+            ("constants.lox", "var pi = 3.14;"),
+        ],
+    );
+    let error: LoxResult = result.unwrap_err().into();
+    assert_eq!(error.code(), Some("E0525"));
+    assert_eq!(
+        error.message(),
+        "Cannot assign 'pi', modules are read-only."
+    );
+
+    let (_, result) = run("name", &[("main.lox", "import \"my-module.lox\";")]);
+    let Err(EvalError::Parser(errors)) = result else {
+        panic!("expected a syntax error");
+    };
+    assert_eq!(errors[0].code(), Some("E0205"));
+}
+
+#[test]
+fn test_render_module_errors() {
+    let path = write_modules(
+        "render",
+        &[
+            (
+                "main.lox",
+                "import \"lib/geometry.lox\";\nprint geometry.area(2);",
+            ),
+            (
+                "lib/geometry.lox",
+                "fun area(side) {\n  return side * \"x\";\n}",
+            ),
+        ],
+    );
+    let source = fs::read_to_string(&path).unwrap();
+    let mut lox = Lox::new();
+    let error: LoxResult = lox.eval_file(&path).unwrap_err().into();
+
+    // The error and the traceback point at the module where they are
+    let rendered = SourceFile::new("main.lox", &source)
+        .with_sources(lox.sources())
+        .render(&error);
+    let module = PathBuf::from(&path).with_file_name("lib/geometry.lox");
+    let module = module.to_str().unwrap();
+    assert!(rendered.contains(&format!(
+        "  File \"main.lox\", line 2, in script\n    print geometry.area(2);\n  File \"{module}\", line 2, in area"
+    )));
+    assert!(rendered.contains(&format!(
+        "error[E0503]: Both operands must be a number.\n --> {module}:2:15\n  |\n2 |   return side * \"x\";"
+    )));
+
+    // As do the errors raised while importing a module, followed by the import
+    let path = write_modules(
+        "render_circular",
+        &[
+            ("main.lox", "import \"a.lox\";"),
+            ("a.lox", "import \"b.lox\";"),
+            ("b.lox", "\nimport \"a.lox\";"),
+        ],
+    );
+    let source = fs::read_to_string(&path).unwrap();
+    let mut lox = Lox::new();
+    let error: LoxResult = lox.eval_file(&path).unwrap_err().into();
+    let rendered = SourceFile::new("main.lox", &source)
+        .with_sources(lox.sources())
+        .render(&error);
+    let directory = PathBuf::from(&path).with_file_name("");
+    let directory = directory.to_str().unwrap();
+    assert_eq!(
+        rendered,
+        format!(
+            "error[E0518]: Circular import of the module \"a.lox\".
+ --> {directory}b.lox:2:8
+  |
+2 | import \"a.lox\";
+  |        ^^^^^^^
+note: In the module imported here.
+ --> {directory}a.lox:1:8
+  |
+1 | import \"b.lox\";
+  |        ^^^^^^^
+note: In the module imported here.
+ --> main.lox:1:8
+  |
+1 | import \"a.lox\";
+  |        ^^^^^^^"
+        )
+    );
+}
+
+#[test]
+fn test_vm_does_not_import() {
+    let path = write_modules(
+        "vm",
+        &[("main.lox", "import \"other.lox\";"), ("other.lox", "")],
+    );
+    let mut lox = Lox::with_backend(Backend::Vm);
+    let error: LoxResult = lox.eval_file(&path).unwrap_err().into();
+    assert_eq!(error.code(), Some("E0405"));
+}
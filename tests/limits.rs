@@ -0,0 +1,201 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use rs_lox_tw::budget::Budget;
+use rs_lox_tw::diagnostic::SourceFile;
+use rs_lox_tw::errors::*;
+use rs_lox_tw::interpreter::{Interpreter, DEFAULT_MAX_CALL_DEPTH, DEFAULT_MAX_STACK_SIZE};
+use rs_lox_tw::lox::{Backend, Lox};
+use rs_lox_tw::object::Object;
+
+/**
+ * Returns the type of the runtime error the given source raises, if any.
+ */
+fn runtime_error(lox: &mut Lox, source: &str) -> Option<RuntimeErrorType> {
+    match lox.eval_source(source) {
+        Err(EvalError::Runtime(LoxResult::Runtime { error_type, .. })) => Some(error_type),
+        _ => None,
+    }
+}
+
+/**
+ * Runs `f` on a thread with the default stack size of a spawned thread (2 MiB), which the
+ * default maximum stack size of the calls is meant for.
+ */
+fn on_spawned_thread<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> T {
+    thread::spawn(f).join().unwrap()
+}
+
+#[test]
+fn test_stack_overflow() {
+    // Methods, blocks and loops on each level of the recursion
+    let source = "class Walker {
+      walk(n) {
+        while (true) {
+          if (n >= 0) {
+            try { return this.walk(n + 1); } finally { var unused = n; }
+          }
+        }
+      }
+    }
+    print Walker().walk(0);";
+    let error = on_spawned_thread(move || runtime_error(&mut Lox::new(), source));
+    assert_eq!(error, Some(RuntimeErrorType::StackOverflow));
+
+    on_spawned_thread(|| {
+        let mut lox = Lox::new();
+        let result = lox.eval_source(
+            "fun count(n) { if (n == 0) return 0; return count(n - 1) + 1; }
+            var total = count(50);
+            fun forever() { return forever(); }
+            var kind;
+            try { forever(); } catch (error) { kind = error.kind; }",
+        );
+        assert!(result.is_ok());
+        // Ordinary recursion stays within the limit
+        assert_eq!(lox.get_global("total"), Some(Object::Num(50.0)));
+        assert_eq!(lox.get_global("kind"), Some(Object::from("StackOverflow")));
+    });
+}
+
+#[test]
+fn test_max_stack_size() {
+    let source = "fun depth(n) { if (n == 1) return 1; return depth(n - 1); }
+    print depth(20);";
+
+    // The calls are limited by the native stack they take, well before the maximum call depth
+    let interpreter = Interpreter::builder().max_stack_size(1024).build();
+    let mut lox = Lox::with_interpreter(interpreter, Backend::TreeWalk);
+    assert_eq!(
+        runtime_error(&mut lox, source),
+        Some(RuntimeErrorType::StackOverflow)
+    );
+
+    lox.interpreter().set_max_stack_size(DEFAULT_MAX_STACK_SIZE);
+    assert_eq!(lox.eval_source(source), Ok(Object::Nil));
+}
+
+#[test]
+fn test_vm_stack_overflow() {
+    let mut lox = Lox::with_backend(Backend::Vm);
+    lox.eval_source("fun depth(n) { if (n == 1) return 1; return depth(n - 1); }")
+        .unwrap();
+
+    assert_eq!(
+        lox.eval_source(&format!("depth({DEFAULT_MAX_CALL_DEPTH});")),
+        Ok(Object::Num(1.0))
+    );
+    assert_eq!(
+        runtime_error(
+            &mut lox,
+            &format!("print depth({});", DEFAULT_MAX_CALL_DEPTH + 1)
+        ),
+        Some(RuntimeErrorType::StackOverflow)
+    );
+
+    lox.vm().set_max_call_depth(10);
+    assert_eq!(
+        runtime_error(&mut lox, "print depth(11);"),
+        Some(RuntimeErrorType::StackOverflow)
+    );
+    assert_eq!(lox.eval_source("depth(10);"), Ok(Object::Num(1.0)));
+}
+
+#[test]
+fn test_max_call_depth() {
+    let mut lox = Lox::new();
+    lox.interpreter().set_max_call_depth(10);
+    lox.eval_source("fun depth(n) { if (n == 1) return 1; return depth(n - 1); }")
+        .unwrap();
+
+    assert_eq!(lox.eval_source("print depth(10);"), Ok(Object::Nil));
+    assert_eq!(
+        runtime_error(&mut lox, "print depth(11);"),
+        Some(RuntimeErrorType::StackOverflow)
+    );
+    // The call stack is empty again after the error
+    assert_eq!(lox.eval_source("print depth(10);"), Ok(Object::Nil));
+
+    // The traceback of a recursion only counts its repeated calls
+    let source = "fun down(n) { return down(n - 1); }\nprint down(1);";
+    let error: LoxResult = lox.eval_source(source).unwrap_err().into();
+    let rendered = SourceFile::new("script.lox", source).render(&error);
+    assert_eq!(rendered.matches("line 1, in down").count(), 3);
+    assert!(rendered.contains("  [Previous line repeated 7 more times]\nerror[E0520]"));
+}
+
+/**
+ * Returns why the given source was interrupted, if it was.
+ */
+fn interruption(lox: &mut Lox, source: &str) -> Option<Interruption> {
+    match lox.eval_source(source) {
+        Err(EvalError::Runtime(LoxResult::Interrupted { interruption })) => Some(interruption),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_statement_limit() {
+    let mut lox = Lox::new();
+    lox.interpreter()
+        .set_budget(Budget::new().with_max_statements(100));
+
+    assert_eq!(
+        interruption(&mut lox, "var i = 0; while (true) { i = i + 1; }"),
+        Some(Interruption::StatementLimit)
+    );
+    // Neither a catch clause nor a finally clause runs
+    assert_eq!(
+        interruption(
+            &mut lox,
+            "try { while (true) {} } catch (e) { i = -1; } finally { i = -2; }"
+        ),
+        Some(Interruption::StatementLimit)
+    );
+
+    // The interpreter is left usable, and each run gets the whole budget
+    assert!(matches!(lox.get_global("i"), Some(Object::Num(i)) if i > 0.0));
+    assert_eq!(
+        lox.eval_source("for (var j = 0; j < 10; j = j + 1) { i = j; }"),
+        Ok(Object::Nil)
+    );
+    assert_eq!(lox.get_global("i"), Some(Object::Num(9.0)));
+}
+
+#[test]
+fn test_timeout_and_cancellation() {
+    let mut lox = Lox::new();
+    lox.interpreter()
+        .set_budget(Budget::new().with_timeout(Duration::from_millis(50)));
+    assert_eq!(
+        interruption(&mut lox, "fun spin() { while (true) {} } spin();"),
+        Some(Interruption::Timeout)
+    );
+
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let mut lox = Lox::new();
+    lox.interpreter()
+        .set_budget(Budget::new().with_cancel_flag(Arc::clone(&cancel_flag)));
+    let canceller = thread::spawn({
+        let cancel_flag = Arc::clone(&cancel_flag);
+        move || {
+            thread::sleep(Duration::from_millis(50));
+            cancel_flag.store(true, Ordering::Relaxed);
+        }
+    });
+    assert_eq!(
+        interruption(&mut lox, "while (true) {}"),
+        Some(Interruption::Cancelled)
+    );
+    canceller.join().unwrap();
+
+    let error: LoxResult = lox.eval_source("print 1;").unwrap_err().into();
+    assert_eq!(error.code(), Some("E0523"));
+    // Even when the program is only an expression, whose value would be returned
+    let error: LoxResult = lox.eval_source("1 + 1;").unwrap_err().into();
+    assert_eq!(error.code(), Some("E0523"));
+    cancel_flag.store(false, Ordering::Relaxed);
+    assert_eq!(lox.eval_source("print 1;"), Ok(Object::Nil));
+}
@@ -0,0 +1,159 @@
+use rs_lox_tw::lint::{lint, LintCode};
+use rs_lox_tw::parser::Parser;
+use rs_lox_tw::scanner::Scanner;
+
+/**
+ * Returns the code and line of each lint of the given source.
+ */
+fn lints(source: &str) -> Vec<(LintCode, usize)> {
+    let mut scanner = Scanner::new(source);
+    let tokens = scanner.scan_tokens().unwrap();
+    let (statements, errors) = Parser::new(tokens).parse();
+    assert_eq!(errors, vec![]);
+
+    lint(&statements, &scanner.comments)
+        .iter()
+        .map(|lint| (lint.code, lint.span.line))
+        .collect()
+}
+
+#[test]
+fn test_unused() {
+    let source = "var global = 1;
+fun f(used, unused, _ignored) {
+  var a = used;
+  var b = 2;
+  b = 3;
+  fun helper() {}
+  return a;
+}
+print f(1, 2, 3);";
+
+    assert_eq!(
+        lints(source),
+        vec![
+            (LintCode::UnusedParameter, 2),
+            (LintCode::UnusedVariable, 4),
+            (LintCode::UnusedVariable, 6),
+        ]
+    );
+}
+
+#[test]
+fn test_unreachable_code() {
+    let source = "fun f(n) {
+  while (n > 0) {
+    if (n == 1) {
+      break;
+      print \"after break\";
+      print \"still unreachable\";
+    }
+    n = n - 1;
+  }
+  if (n) return 1; else { return 2; }
+  print \"after both branches\";
+}
+print f(3);";
+
+    assert_eq!(
+        lints(source),
+        vec![
+            (LintCode::UnreachableCode, 5),
+            (LintCode::UnreachableCode, 11)
+        ]
+    );
+}
+
+#[test]
+fn test_shadowing_and_undeclared_assignment() {
+    let source = "var a = 1;
+fun f(a) {
+  var b = a;
+  {
+    var b = 2;
+    print b;
+  }
+  c = b;
+}
+f(a);";
+
+    assert_eq!(
+        lints(source),
+        vec![
+            (LintCode::ShadowedVariable, 2),
+            (LintCode::ShadowedVariable, 5),
+            (LintCode::UndeclaredAssignment, 8),
+        ]
+    );
+}
+
+#[test]
+fn test_shadowing_later_global() {
+    // A local only shadows the globals declared before it
+    let source = "fun f() {
+  var total = 1;
+  return total;
+}
+var total = f();
+fun g() {
+  var total = 2;
+  return total;
+}
+g();";
+
+    assert_eq!(lints(source), vec![(LintCode::ShadowedVariable, 7)]);
+}
+
+#[test]
+fn test_wrong_arity() {
+    let source = "fun add(a, b) { return a + b; }
+class Point { init(x, y) { this.x = x; this.y = y; } }
+class Empty {}
+var later = add;
+fun reassigned(a) { return a; }
+reassigned = later;
+add(1);
+Point(1, 2);
+Empty(1);
+len(\"a\", \"b\");
+reassigned(1, 2);
+later(1, 2, 3);";
+
+    assert_eq!(
+        lints(source),
+        vec![
+            (LintCode::WrongArity, 7),
+            (LintCode::WrongArity, 9),
+            (LintCode::WrongArity, 10),
+        ]
+    );
+}
+
+#[test]
+fn test_allow() {
+    let source = "// lint: allow(unused_parameter, shadowed_variable)
+var a = 1;
+fun f(a, b) {
+  var unused = a;
+}
+f(1, 2);";
+
+    assert_eq!(lints(source), vec![(LintCode::UnusedVariable, 4)]);
+    assert_eq!(
+        LintCode::from_name("wrong_arity"),
+        Some(LintCode::WrongArity)
+    );
+    assert_eq!(LintCode::from_name("unknown"), None);
+}
+
+#[test]
+fn test_undeclared_variable() {
+    // Globals declared later and natives are known, only the names declared nowhere are reported
+    let source = "fun f() {
+  return later + len(\"abc\");
+}
+var later = 1;
+print f() + missing;";
+
+    assert_eq!(lints(source), vec![(LintCode::UndeclaredVariable, 5)]);
+}
@@ -0,0 +1,96 @@
+use rs_lox_tw::errors::*;
+use rs_lox_tw::lox::Backend;
+
+mod common;
+use common::{eval, eval_on};
+
+#[test]
+fn test_list_literal_and_indexing() {
+    let (result, output) = eval(
+        "var a = [1, \"two\", [3]];
+        print a;
+        print a[1];
+        a[0] = a[2][0] + 1;
+        print a[0];
+        print [];",
+    );
+
+    assert!(result.is_ok());
+    assert_eq!(output, "[1, \"two\", [3]]\n\"two\"\n4\n[]\n");
+}
+
+#[test]
+fn test_list_natives() {
+    let (result, output) = eval(
+        "var a = [];
+        push(a, 1);
+        push(a, 2);
+        print len(a);
+        print pop(a);
+        print a;
+        print len(\"four\");",
+    );
+
+    assert!(result.is_ok());
+    assert_eq!(output, "2\n2\n[1]\n4\n");
+}
+
+#[test]
+fn test_list_storage_is_shared() {
+    let (_, output) = eval(
+        "var a = [1];
+        var b = a;
+        push(b, 2);
+        print a;
+        print a == b;
+        print a == [1, 2];",
+    );
+
+    assert_eq!(output, "[1, 2]\ntrue\nfalse\n");
+}
+
+#[test]
+fn test_list_errors() {
+    let runtime_error = |source: &str| match eval(source).0 {
+        Err(EvalError::Runtime(LoxResult::Runtime { error_type, .. })) => Some(error_type),
+        _ => None,
+    };
+
+    assert_eq!(
+        runtime_error("[1, 2][2];"),
+        Some(RuntimeErrorType::IndexOutOfBounds)
+    );
+    assert_eq!(
+        runtime_error("[1, 2][0.5];"),
+        Some(RuntimeErrorType::InvalidIndex)
+    );
+    assert_eq!(
+        runtime_error("var a = 1; a[0] = 2;"),
+        Some(RuntimeErrorType::InvalidIndexedObject)
+    );
+    assert_eq!(
+        runtime_error("pop([]);"),
+        Some(RuntimeErrorType::PopFromEmptyList)
+    );
+    assert_eq!(
+        runtime_error("len(1);"),
+        Some(RuntimeErrorType::InvalidArgumentType)
+    );
+}
+
+#[test]
+fn test_list_containing_itself() {
+    for backend in [Backend::TreeWalk, Backend::Vm] {
+        let (result, output, _) = eval_on(
+            backend,
+            "var a = [1];
+            push(a, a);
+            print a;
+            var b = [a, a];
+            print b;",
+        );
+
+        assert!(result.is_ok());
+        assert_eq!(output, "[1, [...]]\n[[1, [...]], [1, [...]]]\n");
+    }
+}
@@ -0,0 +1,46 @@
+use rs_lox_tw::errors::*;
+use rs_lox_tw::lox::Lox;
+use rs_lox_tw::object::Object;
+
+#[test]
+fn test_eval_source_returns_last_expression() {
+    let mut lox = Lox::new();
+
+    assert_eq!(lox.eval_source("var a = 1; a + 2;"), Ok(Object::Num(3.0)));
+    assert_eq!(lox.eval_source("var b = 1;"), Ok(Object::Nil));
+}
+
+#[test]
+fn test_globals_are_shared_with_the_host() {
+    let mut lox = Lox::new();
+
+    lox.define_global("name", Object::from("Lox"));
+    lox.eval_source("var greeting = \"Hello \" + name;").unwrap();
+
+    assert_eq!(lox.get_global("greeting"), Some(Object::from("Hello Lox")));
+    assert_eq!(lox.get_global("unknown"), None);
+}
+
+#[test]
+fn test_eval_errors_tell_the_failing_step() {
+    let mut lox = Lox::new();
+
+    assert!(matches!(
+        lox.eval_source("var a = ; var b = ;"),
+        Err(EvalError::Parser(errors)) if errors.len() == 2
+    ));
+    assert!(matches!(
+        lox.eval_source("return 1;"),
+        Err(EvalError::Resolver(LoxResult::Resolver {
+            error_type: ResolverErrorType::TopLevelReturn,
+            ..
+        }))
+    ));
+    assert!(matches!(
+        lox.eval_source("-\"muffin\";"),
+        Err(EvalError::Runtime(LoxResult::Runtime {
+            error_type: RuntimeErrorType::ExpectedNumberOperand,
+            ..
+        }))
+    ));
+}
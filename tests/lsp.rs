@@ -0,0 +1,239 @@
+use std::io::Cursor;
+
+use rs_lox_tw::lsp::LspServer;
+use rs_lox_tw::output::BufferSink;
+use serde_json::{json, Value};
+
+const URI: &str = "file:///shapes.lox";
+
+const SHAPES: &str = "class Shape {
+  init(name) { this.name = name; }
+  describe() { return this.name; }
+}
+class Circle < Shape {
+  init(radius) { super.init(\"circle\"); this.radius = radius; }
+  area { return 3 * this.radius * this.radius; }
+}
+fun scale(shape, factor) {
+  var scaled = shape.area * factor;
+  return scaled;
+}
+var circle = Circle(2);
+print scale(circle, 2);
+print circle.describe();";
+
+/**
+ * Opens `text` then sends the given `(method, params)` messages to a server, and returns the
+ * messages it sent back. The messages with an id of `0` are notifications.
+ */
+fn session(text: &str, messages: &[(&str, Value)]) -> Vec<Value> {
+    let open = (
+        "textDocument/didOpen",
+        json!({ "textDocument": { "uri": URI, "languageId": "lox", "version": 1, "text": text } }),
+    );
+
+    let mut input = String::new();
+    for (id, (method, params)) in std::iter::once(&open).chain(messages).enumerate() {
+        let mut message = json!({ "jsonrpc": "2.0", "method": method, "params": params });
+        if !method.starts_with("textDocument/did") {
+            message["id"] = json!(id);
+        }
+        let message = message.to_string();
+        input.push_str(&format!(
+            "Content-Length: {}\r\n\r\n{message}",
+            message.len()
+        ));
+    }
+
+    let output = BufferSink::new();
+    LspServer::new(Cursor::new(input), output.clone()).run();
+
+    let output = output.contents();
+    let mut messages = Vec::new();
+    let mut rest = output.as_str();
+    while let Some((header, content)) = rest.split_once("\r\n\r\n") {
+        let length = header["Content-Length: ".len()..].parse::<usize>().unwrap();
+        messages.push(serde_json::from_str(&content[..length]).unwrap());
+        rest = &content[length..];
+    }
+
+    messages
+}
+
+/**
+ * Returns the parameters of a request about the given position.
+ */
+fn at(line: usize, character: usize) -> Value {
+    json!({ "textDocument": { "uri": URI }, "position": { "line": line, "character": character } })
+}
+
+fn range(line: usize, start: usize, end: usize) -> Value {
+    json!({
+        "start": { "line": line, "character": start },
+        "end": { "line": line, "character": end },
+    })
+}
+
+fn diagnostics(messages: &[Value]) -> Vec<(String, Value)> {
+    messages
+        .iter()
+        .rfind(|message| message["method"] == "textDocument/publishDiagnostics")
+        .unwrap()["params"]["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|diagnostic| {
+            (
+                diagnostic["code"].as_str().unwrap().to_string(),
+                diagnostic["range"].clone(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_diagnostics() {
+    let messages = session(
+        "var a = ;
+fun f() { var b = 1; var b = 2; }
+return 1;
+print this;
+class A < A {}",
+        &[],
+    );
+
+    assert_eq!(
+        diagnostics(&messages),
+        vec![
+            ("E0201".to_string(), range(0, 8, 9)),
+            ("E0302".to_string(), range(1, 25, 26)),
+            ("E0303".to_string(), range(2, 0, 6)),
+            ("E0304".to_string(), range(3, 6, 10)),
+            ("E0306".to_string(), range(4, 6, 7)),
+        ]
+    );
+
+    // Fixing the file clears its errors
+    let messages = session(
+        "var a = ;",
+        &[(
+            "textDocument/didChange",
+            json!({ "textDocument": { "uri": URI, "version": 2 }, "contentChanges": [{ "text": "var a = 1;" }] }),
+        )],
+    );
+    assert_eq!(diagnostics(&messages), vec![]);
+
+    // Lints are reported as warnings
+    let messages = session("fun f(unused) {}", &[]);
+    assert_eq!(
+        diagnostics(&messages),
+        vec![("W0002".to_string(), range(0, 6, 12))]
+    );
+    assert_eq!(
+        messages[0]["params"]["diagnostics"][0]["severity"],
+        json!(2)
+    );
+}
+
+#[test]
+fn test_definitions_and_references() {
+    let messages = session(
+        SHAPES,
+        &[
+            // `scale` in the print statement
+            ("textDocument/definition", at(13, 7)),
+            // `shape` in the function body
+            ("textDocument/definition", at(9, 16)),
+            // `describe` called on an instance
+            ("textDocument/definition", at(14, 15)),
+            // `circle` at its declaration
+            (
+                "textDocument/references",
+                json!({ "textDocument": { "uri": URI }, "position": { "line": 12, "character": 5 }, "context": { "includeDeclaration": false } }),
+            ),
+            // `init` called through `super`
+            (
+                "textDocument/references",
+                json!({ "textDocument": { "uri": URI }, "position": { "line": 5, "character": 24 }, "context": { "includeDeclaration": true } }),
+            ),
+        ],
+    );
+    let result =
+        |id: usize| messages.iter().find(|message| message["id"] == id).unwrap()["result"].clone();
+
+    let location = |line, start, end| json!({ "uri": URI, "range": range(line, start, end) });
+    assert_eq!(result(1), json!([location(8, 4, 9)]));
+    assert_eq!(result(2), json!([location(8, 10, 15)]));
+    assert_eq!(result(3), json!([location(2, 2, 10)]));
+    assert_eq!(
+        result(4),
+        json!([location(13, 12, 18), location(14, 6, 12)])
+    );
+    // Properties may be any method of that name, so both initializers are found
+    assert_eq!(
+        result(5),
+        json!([location(1, 2, 6), location(5, 2, 6), location(5, 23, 27)])
+    );
+}
+
+#[test]
+fn test_hover_and_symbols() {
+    let messages = session(
+        SHAPES,
+        &[
+            ("textDocument/hover", at(13, 8)),
+            ("textDocument/hover", at(4, 7)),
+            ("textDocument/hover", at(9, 22)),
+            ("textDocument/hover", at(10, 0)),
+            (
+                "textDocument/documentSymbol",
+                json!({ "textDocument": { "uri": URI } }),
+            ),
+            ("textDocument/formatting", at(0, 0)),
+        ],
+    );
+    let result = |id: usize| {
+        messages
+            .iter()
+            .find(|message| message["id"] == id)
+            .unwrap()
+            .clone()
+    };
+    let hover = |id: usize| result(id)["result"]["contents"]["value"].clone();
+
+    assert_eq!(hover(1), "```lox\nfun scale(shape, factor)\n```");
+    assert_eq!(hover(2), "```lox\nclass Circle < Shape\n```");
+    assert_eq!(hover(3), "```lox\nCircle.area\n```");
+    assert_eq!(result(4)["result"], Value::Null);
+
+    let symbols = result(5)["result"].clone();
+    let outline = symbols
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|class| {
+            let methods = class["children"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|method| (method["name"].clone(), method["kind"].clone()))
+                .collect::<Vec<(Value, Value)>>();
+            (class["name"].clone(), methods)
+        })
+        .collect::<Vec<(Value, Vec<(Value, Value)>)>>();
+    assert_eq!(
+        outline,
+        vec![
+            (
+                json!("Shape"),
+                vec![(json!("init"), json!(6)), (json!("describe"), json!(6))]
+            ),
+            (
+                json!("Circle"),
+                vec![(json!("init"), json!(6)), (json!("area"), json!(7))]
+            ),
+        ]
+    );
+
+    assert_eq!(result(6)["error"]["code"], -32601);
+}
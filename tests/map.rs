@@ -0,0 +1,104 @@
+use rs_lox_tw::errors::*;
+use rs_lox_tw::lox::Backend;
+
+mod common;
+use common::{eval, eval_on};
+
+#[test]
+fn test_map_literal_and_subscripting() {
+    let (result, output) = eval(
+        "var m = {\"a\": 1, 2: \"two\", true: nil, nil: [1]};
+        print m;
+        print m[\"a\"];
+        print m[2];
+        m[\"a\"] = m[\"a\"] + 1;
+        m[-0] = \"zero\";
+        print m[0];
+        print m;
+        print {};",
+    );
+
+    assert!(result.is_ok());
+    assert_eq!(
+        output,
+        "{\"a\": 1, 2: \"two\", true: nil, nil: [1]}
+1
+\"two\"
+\"zero\"
+{\"a\": 2, 2: \"two\", true: nil, nil: [1], -0: \"zero\"}
+{}
+"
+    );
+}
+
+#[test]
+fn test_map_natives() {
+    let (result, output) = eval(
+        "var m = {\"a\": 1, \"b\": 2, \"c\": 3};
+        print keys(m);
+        print values(m);
+        print has(m, \"b\");
+        print remove(m, \"b\");
+        print has(m, \"b\");
+        print remove(m, \"b\");
+        print len(m);
+        print m;",
+    );
+
+    assert!(result.is_ok());
+    assert_eq!(
+        output,
+        "[\"a\", \"b\", \"c\"]\n[1, 2, 3]\ntrue\n2\nfalse\nnil\n2\n{\"a\": 1, \"c\": 3}\n"
+    );
+}
+
+#[test]
+fn test_map_errors() {
+    let runtime_error = |source: &str| match eval(source).0 {
+        Err(EvalError::Runtime(LoxResult::Runtime { error_type, .. })) => Some(error_type),
+        _ => None,
+    };
+
+    assert_eq!(
+        runtime_error("var m = {}; m[[]] = 1;"),
+        Some(RuntimeErrorType::UnhashableKey)
+    );
+    assert_eq!(
+        runtime_error("({clock: 1});"),
+        Some(RuntimeErrorType::UnhashableKey)
+    );
+    assert_eq!(
+        runtime_error("has({}, {});"),
+        Some(RuntimeErrorType::UnhashableKey)
+    );
+    assert_eq!(
+        runtime_error("var m = {}; m[0 / 0] = 1;"),
+        Some(RuntimeErrorType::NanKey)
+    );
+    assert_eq!(
+        runtime_error("({\"a\": 1})[\"b\"];"),
+        Some(RuntimeErrorType::UndefinedKey)
+    );
+    assert_eq!(
+        runtime_error("keys([]);"),
+        Some(RuntimeErrorType::InvalidArgumentType)
+    );
+}
+
+#[test]
+fn test_map_containing_itself() {
+    for backend in [Backend::TreeWalk, Backend::Vm] {
+        let (result, output, _) = eval_on(
+            backend,
+            "var m = {};
+            m[\"x\"] = m;
+            print m;
+            var l = [m];
+            m[\"l\"] = l;
+            print l;",
+        );
+
+        assert!(result.is_ok());
+        assert_eq!(output, "{\"x\": {...}}\n[{\"x\": {...}, \"l\": [...]}]\n");
+    }
+}
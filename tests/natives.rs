@@ -0,0 +1,150 @@
+use std::env;
+use std::fs;
+
+use rs_lox_tw::errors::*;
+use rs_lox_tw::interpreter::Interpreter;
+use rs_lox_tw::lox::{Backend, Lox};
+use rs_lox_tw::native_functions::NativeModule;
+use rs_lox_tw::object::Object;
+
+fn sandboxed(backend: Backend) -> Lox {
+    Lox::with_interpreter(
+        Interpreter::builder()
+            .natives(&NativeModule::SANDBOXED)
+            .build(),
+        backend,
+    )
+}
+
+/**
+ * Returns whether the given global is undefined when the source reads it.
+ */
+fn is_undefined(lox: &mut Lox, name: &str) -> bool {
+    let error: LoxResult = match lox.eval_source(&format!("print {name};")) {
+        Ok(_) => return false,
+        Err(error) => error.into(),
+    };
+    error.code() == Some("E0601")
+}
+
+#[test]
+fn test_sandboxed_natives() {
+    for backend in [Backend::TreeWalk, Backend::Vm] {
+        let mut lox = sandboxed(backend);
+        for name in ["input", "readFile", "writeFile"] {
+            assert!(is_undefined(&mut lox, name), "{name} is defined");
+        }
+
+        // The pure and time modules are still there
+        let result = lox.eval_source("var size = len([1, 2, 3]); var now = clock();");
+        assert!(result.is_ok());
+        assert_eq!(lox.get_global("size"), Some(Object::Num(3.0)));
+    }
+
+    let interpreter = Interpreter::builder().natives(&[]).build();
+    let mut lox = Lox::with_interpreter(interpreter, Backend::TreeWalk);
+    for name in ["clock", "len", "readFile"] {
+        assert!(is_undefined(&mut lox, name), "{name} is defined");
+    }
+}
+
+#[test]
+fn test_lint_sandboxed_natives() {
+    let mut lox = sandboxed(Backend::TreeWalk);
+    lox.set_lint(true);
+    lox.set_deny_warnings(true);
+
+    assert!(lox.eval_source("print len(\"abc\");").is_ok());
+    let Err(EvalError::Warnings(warnings)) = lox.eval_source("print readFile(\"secret.txt\");")
+    else {
+        panic!("expected a warning");
+    };
+    assert_eq!(warnings[0].code(), Some("W0007"));
+    assert_eq!(
+        warnings[0].message(),
+        "Use of the undeclared variable 'readFile'."
+    );
+}
+
+#[test]
+fn test_fs_natives() {
+    let path = env::temp_dir().join("rs_lox_tw_natives.txt");
+    let path = path.to_str().unwrap().replace('\\', "/");
+    let mut lox = Lox::new();
+    let result = lox.eval_source(&format!(
+        "writeFile(\"{path}\", \"written\");
+        var contents = readFile(\"{path}\");"
+    ));
+
+    assert!(result.is_ok());
+    assert_eq!(lox.get_global("contents"), Some(Object::from("written")));
+    assert_eq!(fs::read_to_string(&path).unwrap(), "written");
+
+    let error: LoxResult = lox
+        .eval_source("print readFile(\"missing/file.txt\");")
+        .unwrap_err()
+        .into();
+    assert_eq!(error.code(), Some("E0524"));
+}
+
+#[test]
+fn test_sandboxed_modules() {
+    let directory = env::temp_dir().join("rs_lox_tw_natives_modules");
+    fs::create_dir_all(&directory).unwrap();
+    fs::write(directory.join("main.lox"), "import \"files.lox\";").unwrap();
+    fs::write(directory.join("files.lox"), "var read = readFile;").unwrap();
+    let path = directory.join("main.lox");
+
+    // An imported module gets the natives of the program importing it
+    let interpreter = Interpreter::builder()
+        .natives(&[NativeModule::Pure, NativeModule::Fs])
+        .build();
+    let mut lox = Lox::with_interpreter(interpreter, Backend::TreeWalk);
+    assert!(lox.eval_file(path.to_str().unwrap()).is_ok());
+    let interpreter = Interpreter::builder().natives(&[NativeModule::Fs]).build();
+    let mut lox = Lox::with_interpreter(interpreter, Backend::TreeWalk);
+    fs::write(directory.join("files.lox"), "var size = len;").unwrap();
+    let error: LoxResult = lox.eval_file(path.to_str().unwrap()).unwrap_err().into();
+    let LoxResult::Import { error, .. } = error else {
+        panic!("expected an import error");
+    };
+    assert_eq!(error.code(), Some("E0601"));
+}
+
+#[test]
+fn test_sandboxed_imports() {
+    let directory = env::temp_dir().join("rs_lox_tw_natives_imports");
+    fs::create_dir_all(directory.join("scripts")).unwrap();
+    fs::write(directory.join("secret.lox"), "var name = \"s3cr3t\";").unwrap();
+    fs::write(
+        directory.join("scripts/module.lox"),
+        "var name = \"module\";",
+    )
+    .unwrap();
+    let secret = directory.join("secret.lox");
+    let script = directory.join("scripts/main.lox");
+    let import = |lox: &mut Lox, path: &str| -> Option<&'static str> {
+        fs::write(
+            &script,
+            format!("import m from \"{path}\";\nvar name = m.name;"),
+        )
+        .unwrap();
+        match lox.eval_file(script.to_str().unwrap()) {
+            Ok(_) => None,
+            Err(error) => LoxResult::from(error).code(),
+        }
+    };
+
+    // Without file access, no module can be imported
+    let mut lox = sandboxed(Backend::TreeWalk);
+    assert_eq!(import(&mut lox, "module.lox"), Some("E0526"));
+    assert_eq!(import(&mut lox, secret.to_str().unwrap()), Some("E0526"));
+
+    // Otherwise, any module the script can read can be imported
+    let mut lox = Lox::new();
+    assert_eq!(import(&mut lox, "module.lox"), None);
+    assert_eq!(lox.get_global("name"), Some(Object::from("module")));
+    assert_eq!(import(&mut lox, secret.to_str().unwrap()), None);
+    assert_eq!(lox.get_global("name"), Some(Object::from("s3cr3t")));
+    assert_eq!(import(&mut lox, "../missing.lox"), Some("E0517"));
+}
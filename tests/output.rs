@@ -0,0 +1,32 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use rs_lox_tw::lox::Lox;
+use rs_lox_tw::output::{BufferSink, CallbackSink};
+
+#[test]
+fn test_print_writes_to_buffer() {
+    let mut lox = Lox::new();
+    let output = BufferSink::new();
+    lox.interpreter().set_output(Box::new(output.clone()));
+
+    lox.eval_source("print 1 + 2; print \"hello\";").unwrap();
+
+    assert_eq!(output.contents(), "3\n\"hello\"\n");
+}
+
+#[test]
+fn test_diagnostics_write_to_callback() {
+    let mut lox = Lox::new();
+    let lines = Rc::new(RefCell::new(Vec::new()));
+    let captured = Rc::clone(&lines);
+    lox.interpreter()
+        .set_diagnostics(Box::new(CallbackSink(move |line: &str| {
+            captured.borrow_mut().push(line.to_string())
+        })));
+
+    lox.eval_source("-\"muffin\"; print 1;").unwrap();
+
+    assert_eq!(lines.borrow().len(), 1);
+    assert!(lines.borrow()[0].contains("Operand must be a number."));
+}
@@ -0,0 +1,70 @@
+use rs_lox_tw::errors::*;
+use rs_lox_tw::parser::Parser;
+use rs_lox_tw::scanner::Scanner;
+use rs_lox_tw::stmt::Stmt;
+
+#[test]
+fn test_reports_every_syntax_error() {
+    let source = "var a = ;
+    print a *;
+    var b = 2;
+    fun f( { }
+    print b;";
+
+    let mut scanner = Scanner::new(source);
+    let tokens = scanner.scan_tokens().unwrap();
+    let mut parser = Parser::new(tokens);
+
+    let (stmts, errors) = parser.parse();
+
+    // `var a = ;`, `print a *;` and `fun f( {` are all reported
+    assert_eq!(errors.len(), 3);
+    assert!(errors.iter().all(|e| matches!(e, LoxResult::Parser { .. })));
+    // `var b = 2;` and `print b;` are still part of the partial AST
+    assert_eq!(stmts.len(), 2);
+    assert!(matches!(stmts[0], Stmt::Var { .. }));
+    assert!(matches!(stmts[1], Stmt::Print { .. }));
+}
+
+#[test]
+fn test_recovers_inside_blocks() {
+    let source = "{
+        print 1 +;
+        print 2;
+    }";
+
+    let mut scanner = Scanner::new(source);
+    let tokens = scanner.scan_tokens().unwrap();
+    let mut parser = Parser::new(tokens);
+
+    let (stmts, errors) = parser.parse();
+
+    assert_eq!(errors.len(), 1);
+    if let Stmt::Block { statements } = &stmts[0] {
+        assert_eq!(statements.len(), 1);
+    } else {
+        panic!("Expected a block statement.");
+    }
+}
+
+#[test]
+fn test_recovers_at_the_next_declaration() {
+    let source = "print 1
+    fun add(a, b) {
+        return a + b;
+    }
+    print add(1, 2);";
+
+    let mut scanner = Scanner::new(source);
+    let tokens = scanner.scan_tokens().unwrap();
+    let mut parser = Parser::new(tokens);
+
+    let (stmts, errors) = parser.parse();
+
+    // Only the missing ';' is reported, at the 'fun' keyword which starts the next declaration
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message(), "Expected ';' after value.");
+    assert_eq!(errors[0].span().map(|span| span.start), source.find("fun"));
+    assert!(matches!(stmts[0], Stmt::Function { .. }));
+    assert!(matches!(stmts[1], Stmt::Print { .. }));
+}
@@ -1,7 +1,7 @@
 use rs_lox_tw::errors::*;
 use rs_lox_tw::parser::Parser;
 use rs_lox_tw::resolver::Resolver;
-use rs_lox_tw::token::Token;
+use rs_lox_tw::token::{SourceId, Token};
 use rs_lox_tw::token_type::TokenType;
 
 mod common;
@@ -19,7 +19,7 @@ fn test_error_variable_already_exists() {
         let mut parser = Parser::new(tokens);
 
         match parser.parse() {
-            Ok(stmts) => {
+            (stmts, errors) if errors.is_empty() => {
                 match resolver.resolve_stmts(&stmts) {
                     Ok(_) => {}
                     Err(e) => {
@@ -36,8 +36,8 @@ fn test_error_variable_already_exists() {
                     })
                 )
             }
-            Err(e) => {
-                eprintln!("There was an error: {}", e)
+            (_, errors) => {
+                eprintln!("There was an error: {}", LoxResult::Multiple { errors })
             }
         }
     }
@@ -53,11 +53,12 @@ fn test_invalid_this_top_level() {
         let mut parser = Parser::new(tokens);
 
         match parser.parse() {
-            Ok(stmts) => {
+            (stmts, errors) if errors.is_empty() => {
                 assert_eq!(
                     resolver.resolve_stmts(&stmts),
                     Err(LoxResult::Resolver {
                         token: Token {
+                            src_id: SourceId::MAIN,
                             src_end: 0,
                             ttype: TokenType::This,
                             src_line: 0,
@@ -69,8 +70,8 @@ fn test_invalid_this_top_level() {
                     })
                 )
             }
-            Err(e) => {
-                eprintln!("There was an error: {}", e)
+            (_, errors) => {
+                eprintln!("There was an error: {}", LoxResult::Multiple { errors })
             }
         }
     }
@@ -88,11 +89,12 @@ fn test_invalid_this_in_function() {
         let mut parser = Parser::new(tokens);
 
         match parser.parse() {
-            Ok(stmts) => {
+            (stmts, errors) if errors.is_empty() => {
                 assert_eq!(
                     resolver.resolve_stmts(&stmts),
                     Err(LoxResult::Resolver {
                         token: Token {
+                            src_id: SourceId::MAIN,
                             src_end: 0,
                             ttype: TokenType::This,
                             src_line: 0,
@@ -104,8 +106,8 @@ fn test_invalid_this_in_function() {
                     })
                 )
             }
-            Err(e) => {
-                eprintln!("There was an error: {}", e)
+            (_, errors) => {
+                eprintln!("There was an error: {}", LoxResult::Multiple { errors })
             }
         }
     }
@@ -125,11 +127,12 @@ fn test_return_from_init() {
         let mut parser = Parser::new(tokens);
 
         match parser.parse() {
-            Ok(stmts) => {
+            (stmts, errors) if errors.is_empty() => {
                 assert_eq!(
                     resolver.resolve_stmts(&stmts),
                     Err(LoxResult::Resolver {
                         token: Token {
+                            src_id: SourceId::MAIN,
                             src_end: 0,
                             ttype: TokenType::Return,
                             src_line: 0,
@@ -141,15 +144,15 @@ fn test_return_from_init() {
                     })
                 )
             }
-            Err(e) => {
-                eprintln!("There was an error: {}", e)
+            (_, errors) => {
+                eprintln!("There was an error: {}", LoxResult::Multiple { errors })
             }
         }
     }
 }
 
 #[test]
-fn test_return_from_init() {
+fn test_class_inherit_itself() {
     let source = "class Oops < Oops {}";
 
     let (mut scanner, mut interpreter) = common::scanner_and_interpreter(source);
@@ -158,11 +161,12 @@ fn test_return_from_init() {
         let mut parser = Parser::new(tokens);
 
         match parser.parse() {
-            Ok(stmts) => {
+            (stmts, errors) if errors.is_empty() => {
                 assert_eq!(
                     resolver.resolve_stmts(&stmts),
                     Err(LoxResult::Resolver {
                         token: Token {
+                            src_id: SourceId::MAIN,
                             src_end: 0,
                             ttype: TokenType::Identifier,
                             src_line: 0,
@@ -174,8 +178,80 @@ fn test_return_from_init() {
                     })
                 )
             }
-            Err(e) => {
-                eprintln!("There was an error: {}", e)
+            (_, errors) => {
+                eprintln!("There was an error: {}", LoxResult::Multiple { errors })
+            }
+        }
+    }
+}
+
+#[test]
+fn test_break_outside_loop() {
+    let source = "while (true) {
+        fun f() {
+            break;
+        }
+    }";
+
+    let (mut scanner, mut interpreter) = common::scanner_and_interpreter(source);
+    let mut resolver = Resolver::new(&mut interpreter);
+    if let Ok(tokens) = scanner.scan_tokens() {
+        let mut parser = Parser::new(tokens);
+
+        match parser.parse() {
+            (stmts, errors) if errors.is_empty() => {
+                assert_eq!(
+                    resolver.resolve_stmts(&stmts),
+                    Err(LoxResult::Resolver {
+                        token: Token {
+                            src_id: SourceId::MAIN,
+                            src_end: 0,
+                            ttype: TokenType::Break,
+                            src_line: 0,
+                            src_start: 0,
+                            lexeme: "break".to_string(),
+                            literal: None
+                        },
+                        error_type: ResolverErrorType::BreakOutsideLoop
+                    })
+                )
+            }
+            (_, errors) => {
+                eprintln!("There was an error: {}", LoxResult::Multiple { errors })
+            }
+        }
+    }
+}
+
+#[test]
+fn test_continue_outside_loop() {
+    let source = "continue;";
+
+    let (mut scanner, mut interpreter) = common::scanner_and_interpreter(source);
+    let mut resolver = Resolver::new(&mut interpreter);
+    if let Ok(tokens) = scanner.scan_tokens() {
+        let mut parser = Parser::new(tokens);
+
+        match parser.parse() {
+            (stmts, errors) if errors.is_empty() => {
+                assert_eq!(
+                    resolver.resolve_stmts(&stmts),
+                    Err(LoxResult::Resolver {
+                        token: Token {
+                            src_id: SourceId::MAIN,
+                            src_end: 0,
+                            ttype: TokenType::Continue,
+                            src_line: 0,
+                            src_start: 0,
+                            lexeme: "continue".to_string(),
+                            literal: None
+                        },
+                        error_type: ResolverErrorType::ContinueOutsideLoop
+                    })
+                )
+            }
+            (_, errors) => {
+                eprintln!("There was an error: {}", LoxResult::Multiple { errors })
             }
         }
     }
@@ -0,0 +1,129 @@
+use rs_lox_tw::chunk::OpCode;
+use rs_lox_tw::compiler::Compiler;
+use rs_lox_tw::errors::*;
+use rs_lox_tw::lox::{Backend, Lox};
+use rs_lox_tw::object::Object;
+use rs_lox_tw::parser::Parser;
+use rs_lox_tw::scanner::Scanner;
+
+mod common;
+use common::eval_on;
+
+#[test]
+fn test_compile_expression() {
+    let mut scanner = Scanner::new("1 + 2;");
+    let tokens = scanner.scan_tokens().unwrap();
+    let (statements, _) = Parser::new(tokens).parse();
+
+    let function = Compiler::new().compile(&statements).unwrap();
+
+    assert_eq!(
+        function.chunk.code,
+        vec![
+            OpCode::Constant(0),
+            OpCode::Constant(1),
+            OpCode::Add,
+            OpCode::Return
+        ]
+    );
+    assert_eq!(function.chunk.token(2).lexeme, "+");
+}
+
+#[test]
+fn test_closures_keep_captured_variables() {
+    let (result, output, _) = eval_on(
+        Backend::Vm,
+        "fun counter() {
+            var count = 0;
+            return fun () { count = count + 1; return count; };
+        }
+        var a = counter();
+        var b = counter();
+        a();
+        a();
+        print a();
+        print b();
+
+        var closures = [];
+        for (var i = 0; i < 3; i = i + 1) {
+            var j = i;
+            push(closures, fun () { return j; });
+            if (i == 1) break;
+        }
+        print closures[0]() + closures[1]();",
+    );
+
+    assert!(result.is_ok());
+    assert_eq!(output, "3\n1\n1\n");
+}
+
+#[test]
+fn test_classes() {
+    let (result, output, _) = eval_on(
+        Backend::Vm,
+        "class Shape {
+            init(name) { this.name = name; }
+            describe() { return this.name + \" of area \"; }
+        }
+        class Square < Shape {
+            init(side) {
+                super.init(\"square\");
+                this.side = side;
+            }
+            area { return this.side * this.side; }
+            class unit() { return Square(1); }
+        }
+        var square = Square(3);
+        print square.describe();
+        print square.area;
+        print Square.unit().area;",
+    );
+
+    assert!(result.is_ok());
+    assert_eq!(output, "\"square of area \"\n9\n1\n");
+}
+
+#[test]
+fn test_expression_statement_errors_are_reported() {
+    let (result, output, diagnostics) = eval_on(
+        Backend::Vm,
+        "fun fail() { print undefined; }
+        fail();
+        print \"after\";",
+    );
+
+    assert!(result.is_ok());
+    assert_eq!(output, "\"after\"\n");
+    assert!(diagnostics.contains("No such variable 'undefined'."));
+}
+
+#[test]
+fn test_runtime_error_stops_the_program() {
+    let (result, output, _) = eval_on(Backend::Vm, "print 1; print -\"muffin\"; print 2;");
+
+    assert_eq!(output, "1\n");
+    assert!(matches!(
+        result,
+        Err(EvalError::Runtime(LoxResult::Runtime {
+            error_type: RuntimeErrorType::ExpectedNumberOperand,
+            ..
+        }))
+    ));
+}
+
+#[test]
+fn test_session_state_is_kept() {
+    let mut lox = Lox::with_backend(Backend::Vm);
+
+    lox.define_global("name", Object::from("Lox"));
+    lox.eval_source("var greeting = \"Hello \" + name;")
+        .unwrap();
+    // A failing evaluation doesn't break the next ones
+    assert!(lox.eval_source("-nil;").is_err());
+
+    assert_eq!(
+        lox.eval_source("greeting + \"!\";"),
+        Ok(Object::from("Hello Lox!"))
+    );
+    assert_eq!(lox.get_global("greeting"), Some(Object::from("Hello Lox")));
+}